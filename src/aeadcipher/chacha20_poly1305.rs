@@ -71,14 +71,14 @@ impl Chacha20Poly1305 {
         poly1305.update(aad);
         // padding AAD
         let r = Poly1305::BLOCK_LEN - alen % Poly1305::BLOCK_LEN;
-        if r > 0 {
+        if r < Poly1305::BLOCK_LEN {
             poly1305.update(&Self::PADDING_BLOCK[..r]);
         }
 
         poly1305.update(plaintext);
         // padding ciphertext
         let r = Poly1305::BLOCK_LEN - plen % Poly1305::BLOCK_LEN;
-        if r > 0 {
+        if r < Poly1305::BLOCK_LEN {
             poly1305.update(&Self::PADDING_BLOCK[..r]);
         }
 
@@ -106,14 +106,14 @@ impl Chacha20Poly1305 {
         poly1305.update(aad);
         // padding AAD
         let r = Poly1305::BLOCK_LEN - alen % Poly1305::BLOCK_LEN;
-        if r > 0 {
+        if r < Poly1305::BLOCK_LEN {
             poly1305.update(&Self::PADDING_BLOCK[..r]);
         }
 
         poly1305.update(&ciphertext);
         // padding ciphertext
         let r = Poly1305::BLOCK_LEN - plen % Poly1305::BLOCK_LEN;
-        if r > 0 {
+        if r < Poly1305::BLOCK_LEN {
             poly1305.update(&Self::PADDING_BLOCK[..r]);
         }
 
@@ -326,5 +326,69 @@ work in progress.\x2f\xe2\x80\x9d";
     assert_eq!(&plaintext[..], &cleartext[..]);
 }
 
+#[test]
+fn test_aead_chacha20_poly1305_aligned() {
+    // 2.8.  AEAD Construction
+    // https://tools.ietf.org/html/rfc8439#section-2.8
+    //
+    // padding1 -- the padding is up to 15 zero bytes, and it brings
+    //    the total length so far to an integral multiple of 16.  If the
+    //    length of the AAD was already an integral multiple of 16 bytes,
+    //    this field is zero-length.
+    //
+    // NOTE: RFC 8439 的测试向量中 AAD 和密文的长度都不是 16 的倍数，
+    //       这里使用 2.8.2 的 Key、Nonce 和明文，结果与 OpenSSL 的输出一致。
+    let plaintext: &[u8] = b"Ladies and Gentlemen of the class of '99: \
+If I could offer you only one tip for the future, sunscreen would be it.";
+    let aad = hex::decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+    let key = hex::decode("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f").unwrap();
+    let nonce = hex::decode("070000004041424344454647").unwrap();
+
+    let suites: [(&[u8], &[u8], &str); 3] = [
+        // 2.8.2，对照
+        (&aad, plaintext, "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+3ff4def08e4b7a9de576d26586cec64b61161ae10b594f09e26a7e902ecbd0600691"),
+        // 空的 AAD
+        (&[], plaintext, "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+92ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc\
+3ff4def08e4b7a9de576d26586cec64b61166a23a4681fd59456aea1d29f82477216"),
+        // 密文的长度为 64
+        (&aad, &plaintext[..64], "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d6\
+3dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b36\
+57728d89811f44e3449f0d1c25a3e95e"),
+    ];
+
+    for (aad, plaintext, expected) in suites.iter() {
+        let expected = hex::decode(expected).unwrap();
+        let plen = plaintext.len();
+        let mut ciphertext_and_tag = plaintext.to_vec();
+        ciphertext_and_tag.resize(plen + Chacha20Poly1305::TAG_LEN, 0);
+
+        Chacha20Poly1305::new(&key, &nonce).aead_encrypt(aad, &mut ciphertext_and_tag);
+        assert_eq!(&ciphertext_and_tag[..], &expected[..]);
+
+        assert!(Chacha20Poly1305::new(&key, &nonce).aead_decrypt(aad, &mut ciphertext_and_tag));
+        assert_eq!(&ciphertext_and_tag[..plen], &plaintext[..]);
+    }
+
+    // AAD 和密文的长度都是 16 的倍数
+    let key: Vec<u8> = (0..32).collect();
+    let nonce: Vec<u8> = (0..12).collect();
+    let aad: Vec<u8> = (0..16).collect();
+    let plaintext: Vec<u8> = (0..32).collect();
+
+    let mut ciphertext_and_tag = plaintext.clone();
+    ciphertext_and_tag.resize(plaintext.len() + Chacha20Poly1305::TAG_LEN, 0);
+    Chacha20Poly1305::new(&key, &nonce).aead_encrypt(&aad, &mut ciphertext_and_tag);
+    assert_eq!(&ciphertext_and_tag[..], &hex::decode("89fa0a032d12a347bf8a35f89410006cd961a0f44561bbaefe8e35de69ddb823\
+80cc4bcfc141cfad9f78b4ff3043164d").unwrap()[..]);
+
+    assert!(Chacha20Poly1305::new(&key, &nonce).aead_decrypt(&aad, &mut ciphertext_and_tag));
+    assert_eq!(&ciphertext_and_tag[..plaintext.len()], &plaintext[..]);
+}
+
 // Appendix A.  Additional Test Vectors
 // https://tools.ietf.org/html/rfc8439#appendix-A
\ No newline at end of file
@@ -0,0 +1,656 @@
+// Argon2 Memory-Hard Function for Password Hashing and Proof-of-Work Applications
+// https://www.rfc-editor.org/rfc/rfc9106.html
+//
+// PHC string format
+// https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md
+//
+// 参考实现
+// https://github.com/P-H-C/phc-winner-argon2
+use crate::hash::Blake2b;
+use crate::error::InvalidParams;
+use crate::util::{base64_encode, base64_decode, BASE64_STANDARD};
+
+use subtle;
+
+
+// 1024 Bytes
+const BLOCK_WORDS: usize = 128;
+const SYNC_POINTS: usize = 4;
+const ADDRESSES_IN_BLOCK: usize = BLOCK_WORDS;
+
+const VERSION_10: u32 = 0x10;
+const VERSION_13: u32 = 0x13;
+
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Argon2Kind {
+    ARGON2D,
+    ARGON2I,
+    ARGON2ID,
+}
+
+impl Argon2Kind {
+    fn id(self) -> u32 {
+        match self {
+            Self::ARGON2D  => 0,
+            Self::ARGON2I  => 1,
+            Self::ARGON2ID => 2,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::ARGON2D  => "argon2d",
+            Self::ARGON2I  => "argon2i",
+            Self::ARGON2ID => "argon2id",
+        }
+    }
+}
+
+
+#[derive(Clone, Copy)]
+struct Block([u64; BLOCK_WORDS]);
+
+impl Block {
+    const ZERO: Self = Self([0u64; BLOCK_WORDS]);
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut block = Self::ZERO;
+        for (w, b) in block.0.iter_mut().zip(bytes.chunks_exact(8)) {
+            *w = u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]);
+        }
+        block
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BLOCK_WORDS * 8);
+        for w in self.0.iter() {
+            bytes.extend_from_slice(&w.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn xor(&mut self, other: &Self) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a ^= b;
+        }
+    }
+}
+
+#[inline]
+fn fblamka(x: u64, y: u64) -> u64 {
+    let m = (x & 0xffffffff).wrapping_mul(y & 0xffffffff);
+    x.wrapping_add(y).wrapping_add(m.wrapping_mul(2))
+}
+
+#[inline]
+fn gb(v: &mut [u64; BLOCK_WORDS], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = fblamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = fblamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = fblamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = fblamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+// Permutation P
+#[inline]
+fn permute(v: &mut [u64; BLOCK_WORDS], i: &[usize; 16]) {
+    gb(v, i[0], i[4], i[ 8], i[12]);
+    gb(v, i[1], i[5], i[ 9], i[13]);
+    gb(v, i[2], i[6], i[10], i[14]);
+    gb(v, i[3], i[7], i[11], i[15]);
+
+    gb(v, i[0], i[5], i[10], i[15]);
+    gb(v, i[1], i[6], i[11], i[12]);
+    gb(v, i[2], i[7], i[ 8], i[13]);
+    gb(v, i[3], i[4], i[ 9], i[14]);
+}
+
+// Compression Function G
+fn compress(x: &Block, y: &Block) -> Block {
+    let mut r = *x;
+    r.xor(y);
+
+    let mut q = r;
+    // NOTE: 把 1024 Bytes 的块看作 8x8 的 16 Bytes 寄存器矩阵，先按行再按列做置换。
+    for i in 0..8 {
+        let mut idx = [0usize; 16];
+        for (j, x) in idx.iter_mut().enumerate() {
+            *x = 16 * i + j;
+        }
+        permute(&mut q.0, &idx);
+    }
+    for i in 0..8 {
+        let mut idx = [0usize; 16];
+        for j in 0..8 {
+            idx[2 * j]     = 2 * i + 16 * j;
+            idx[2 * j + 1] = 2 * i + 16 * j + 1;
+        }
+        permute(&mut q.0, &idx);
+    }
+
+    q.xor(&r);
+    q
+}
+
+// Variable-Length Hash Function H'
+fn hprime(out: &mut [u8], input: &[&[u8]]) {
+    let len = (out.len() as u32).to_le_bytes();
+
+    if out.len() <= Blake2b::MAX_DIGEST_LEN {
+        let mut h = Blake2b::new(out.len());
+        h.update(&len);
+        for data in input.iter() {
+            h.update(data);
+        }
+        h.finalize(out);
+        return;
+    }
+
+    // r = ceil(T/32)-2
+    // V_1 = H^(64)(LE32(T)||A)
+    // V_2 = H^(64)(V_1)
+    // ...
+    // V_r = H^(64)(V_{r-1})
+    // V_{r+1} = H^(T-32*r)(V_{r})
+    // H'^T(X) = W_1 || W_2 || ... || W_r || V_{r+1}
+    let mut v = [0u8; Blake2b::MAX_DIGEST_LEN];
+    let mut h = Blake2b::new(Blake2b::MAX_DIGEST_LEN);
+    h.update(&len);
+    for data in input.iter() {
+        h.update(data);
+    }
+    h.finalize(&mut v);
+
+    out[..32].copy_from_slice(&v[..32]);
+    let mut pos = 32;
+
+    while out.len() - pos > Blake2b::MAX_DIGEST_LEN {
+        let mut h = Blake2b::new(Blake2b::MAX_DIGEST_LEN);
+        h.update(&v);
+        h.finalize(&mut v);
+
+        out[pos..pos + 32].copy_from_slice(&v[..32]);
+        pos += 32;
+    }
+
+    let mut h = Blake2b::new(out.len() - pos);
+    h.update(&v);
+    h.finalize(&mut out[pos..]);
+}
+
+
+// NOTE: 同一个 Slice 内，各个 Lane 只写入自己的 Segment，只读取其它 Slice 的数据，
+//       所以多个线程可以同时写入同一块内存的不同位置。
+struct Memory {
+    ptr: *mut Block,
+    len: usize,
+}
+
+unsafe impl Send for Memory { }
+unsafe impl Sync for Memory { }
+
+impl Memory {
+    #[inline]
+    fn get(&self, idx: usize) -> &Block {
+        assert!(idx < self.len);
+        unsafe { &*self.ptr.add(idx) }
+    }
+
+    #[inline]
+    #[allow(clippy::mut_from_ref)]
+    fn get_mut(&self, idx: usize) -> &mut Block {
+        assert!(idx < self.len);
+        unsafe { &mut *self.ptr.add(idx) }
+    }
+}
+
+
+/// Argon2 (RFC 9106)
+///
+/// `m_cost` 以 KiB 为单位，`t_cost` 为迭代次数，`parallelism` 为 Lane 的数量。
+#[derive(Clone)]
+pub struct Argon2 {
+    kind: Argon2Kind,
+    version: u32,
+    m_cost: u32,
+    t_cost: u32,
+    parallelism: u32,
+    threads: u32,
+    secret: Vec<u8>,
+    ad: Vec<u8>,
+}
+
+impl Argon2 {
+    pub const VERSION: u32      = VERSION_13;
+    pub const MIN_SALT_LEN: usize = 8;
+    pub const MIN_TAG_LEN: usize  = 4;
+    /// `hash_phc` 输出的 Tag 长度
+    pub const PHC_TAG_LEN: usize  = 32;
+    pub const MAX_PARALLELISM: u32 = 0xffffff;
+    /// `verify_phc` 的 `max_m_cost` 推荐取值（64 MiB）
+    pub const DEFAULT_MAX_M_COST: u32 = 64 * 1024;
+    /// `verify_phc` 的 `max_t_cost` 推荐取值
+    pub const DEFAULT_MAX_T_COST: u32 = 16;
+
+
+    pub fn new(kind: Argon2Kind, m_cost: u32, t_cost: u32, parallelism: u32) -> Result<Self, InvalidParams> {
+        Self::with_version(kind, Self::VERSION, m_cost, t_cost, parallelism)
+    }
+
+    fn with_version(kind: Argon2Kind, version: u32, m_cost: u32, t_cost: u32, parallelism: u32) -> Result<Self, InvalidParams> {
+        if version != VERSION_10 && version != VERSION_13 {
+            return Err(InvalidParams);
+        }
+        if !(1..=Self::MAX_PARALLELISM).contains(&parallelism) {
+            return Err(InvalidParams);
+        }
+        // m >= 8 * p
+        if (m_cost as u64) < 8 * parallelism as u64 {
+            return Err(InvalidParams);
+        }
+        if t_cost < 1 {
+            return Err(InvalidParams);
+        }
+
+        Ok(Self {
+            kind, version, m_cost, t_cost, parallelism,
+            threads: 1,
+            secret: Vec::new(),
+            ad: Vec::new(),
+        })
+    }
+
+    /// Secret Value K（Pepper）
+    pub fn set_secret(&mut self, secret: &[u8]) {
+        self.secret = secret.to_vec();
+    }
+
+    /// Associated Data X
+    pub fn set_associated_data(&mut self, ad: &[u8]) {
+        self.ad = ad.to_vec();
+    }
+
+    /// 使用多个线程同时计算各个 Lane，线程数不会超过 `parallelism`，不影响输出。
+    pub fn set_threads(&mut self, threads: u32) {
+        self.threads = std::cmp::max(1, std::cmp::min(threads, self.parallelism));
+    }
+
+    pub fn kind(&self) -> Argon2Kind {
+        self.kind
+    }
+
+    pub fn hash(&self, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<(), InvalidParams> {
+        let max_len = u32::MAX as usize;
+        if out.len() < Self::MIN_TAG_LEN || out.len() > max_len {
+            return Err(InvalidParams);
+        }
+        if salt.len() < Self::MIN_SALT_LEN || salt.len() > max_len {
+            return Err(InvalidParams);
+        }
+        if password.len() > max_len || self.secret.len() > max_len || self.ad.len() > max_len {
+            return Err(InvalidParams);
+        }
+
+        let lanes = self.parallelism as usize;
+        // m' = 4 * p * floor (m / 4p)
+        let memory_blocks = (self.m_cost as usize / (SYNC_POINTS * lanes)) * (SYNC_POINTS * lanes);
+        let lane_len = memory_blocks / lanes;
+
+        // NOTE: 内存不足时返回错误，而不是直接终止进程。
+        let mut blocks: Vec<Block> = Vec::new();
+        blocks.try_reserve_exact(memory_blocks).map_err(|_| InvalidParams)?;
+        blocks.resize(memory_blocks, Block::ZERO);
+
+        // H_0 = H^(64)(LE32(p) || LE32(T) || LE32(m) || LE32(t) ||
+        //         LE32(v) || LE32(y) || LE32(length(P)) || P ||
+        //         LE32(length(S)) || S ||  LE32(length(K)) || K ||
+        //         LE32(length(X)) || X)
+        let mut h0 = [0u8; 64];
+        let mut h = Blake2b::new(64);
+        h.update(&self.parallelism.to_le_bytes());
+        h.update(&(out.len() as u32).to_le_bytes());
+        h.update(&self.m_cost.to_le_bytes());
+        h.update(&self.t_cost.to_le_bytes());
+        h.update(&self.version.to_le_bytes());
+        h.update(&self.kind.id().to_le_bytes());
+        for data in [password, salt, &self.secret, &self.ad].iter() {
+            h.update(&(data.len() as u32).to_le_bytes());
+            h.update(data);
+        }
+        h.finalize(&mut h0);
+
+        // B[i][0] = H'^(1024)(H_0 || LE32(0) || LE32(i))
+        // B[i][1] = H'^(1024)(H_0 || LE32(1) || LE32(i))
+        let mut tmp = [0u8; BLOCK_WORDS * 8];
+        for lane in 0..lanes {
+            for i in 0..2u32 {
+                hprime(&mut tmp, &[&h0, &i.to_le_bytes(), &(lane as u32).to_le_bytes()]);
+                blocks[lane * lane_len + i as usize] = Block::from_bytes(&tmp);
+            }
+        }
+
+        let memory = Memory { ptr: blocks.as_mut_ptr(), len: blocks.len() };
+        let threads = self.threads as usize;
+
+        for pass in 0..self.t_cost as usize {
+            for slice in 0..SYNC_POINTS {
+                if threads <= 1 {
+                    for lane in 0..lanes {
+                        self.fill_segment(&memory, memory_blocks, pass, lane, slice);
+                    }
+                    continue;
+                }
+
+                // NOTE: 每个 Slice 结束时是一个同步点，所有 Lane 都完成之后才能进入下一个 Slice。
+                let memory = &memory;
+                std::thread::scope(|s| {
+                    for t in 0..threads {
+                        s.spawn(move || {
+                            for lane in (t..lanes).step_by(threads) {
+                                self.fill_segment(memory, memory_blocks, pass, lane, slice);
+                            }
+                        });
+                    }
+                });
+            }
+        }
+
+        // C = B[0][q-1] XOR B[1][q-1] XOR ... XOR B[p-1][q-1]
+        // Tag = H'^T(C)
+        let mut c = blocks[lane_len - 1];
+        for lane in 1..lanes {
+            c.xor(&blocks[lane * lane_len + lane_len - 1]);
+        }
+        hprime(out, &[&c.to_bytes()]);
+
+        Ok(())
+    }
+
+    fn fill_segment(&self, memory: &Memory, memory_blocks: usize, pass: usize, lane: usize, slice: usize) {
+        let lanes = self.parallelism as usize;
+        let lane_len = memory_blocks / lanes;
+        let segment_len = lane_len / SYNC_POINTS;
+
+        let data_independent = match self.kind {
+            Argon2Kind::ARGON2D  => false,
+            Argon2Kind::ARGON2I  => true,
+            Argon2Kind::ARGON2ID => pass == 0 && slice < SYNC_POINTS / 2,
+        };
+
+        let mut input_block = Block::ZERO;
+        let mut address_block = Block::ZERO;
+        if data_independent {
+            input_block.0[0] = pass as u64;
+            input_block.0[1] = lane as u64;
+            input_block.0[2] = slice as u64;
+            input_block.0[3] = memory_blocks as u64;
+            input_block.0[4] = self.t_cost as u64;
+            input_block.0[5] = self.kind.id() as u64;
+        }
+
+        let next_addresses = |address_block: &mut Block, input_block: &mut Block| {
+            input_block.0[6] += 1;
+            *address_block = compress(&Block::ZERO, &compress(&Block::ZERO, input_block));
+        };
+
+        // NOTE: 第一轮的第一个 Slice 里，前两个块已经在初始化时计算过了。
+        let mut start = 0;
+        if pass == 0 && slice == 0 {
+            start = 2;
+            if data_independent {
+                next_addresses(&mut address_block, &mut input_block);
+            }
+        }
+
+        for index in start..segment_len {
+            let cur = lane * lane_len + slice * segment_len + index;
+            let prev = if cur.is_multiple_of(lane_len) { cur + lane_len - 1 } else { cur - 1 };
+
+            let rand = if data_independent {
+                if index % ADDRESSES_IN_BLOCK == 0 {
+                    next_addresses(&mut address_block, &mut input_block);
+                }
+                address_block.0[index % ADDRESSES_IN_BLOCK]
+            } else {
+                memory.get(prev).0[0]
+            };
+
+            let j1 = rand & 0xffffffff;
+            let j2 = rand >> 32;
+
+            let ref_lane = if pass == 0 && slice == 0 { lane } else { (j2 % lanes as u64) as usize };
+            let same_lane = ref_lane == lane;
+
+            // 计算可以引用的块的数量 |W|
+            let ref_area_size = if pass == 0 {
+                if same_lane {
+                    slice * segment_len + index - 1
+                } else if index == 0 {
+                    slice * segment_len - 1
+                } else {
+                    slice * segment_len
+                }
+            } else if same_lane {
+                lane_len - segment_len + index - 1
+            } else if index == 0 {
+                lane_len - segment_len - 1
+            } else {
+                lane_len - segment_len
+            } as u64;
+
+            // x = J_1^2 / 2^(32)
+            // y = (|W| * x) / 2^(32)
+            // zz = |W| - 1 - y
+            let x = (j1 * j1) >> 32;
+            let y = (ref_area_size * x) >> 32;
+            let zz = (ref_area_size - 1 - y) as usize;
+
+            let start_position = if pass == 0 || slice == SYNC_POINTS - 1 { 0 } else { (slice + 1) * segment_len };
+            let ref_index = (start_position + zz) % lane_len;
+
+            let block = compress(memory.get(prev), memory.get(ref_lane * lane_len + ref_index));
+            if pass == 0 || self.version == VERSION_10 {
+                *memory.get_mut(cur) = block;
+            } else {
+                memory.get_mut(cur).xor(&block);
+            }
+        }
+    }
+
+    /// 输出 PHC 字符串，例如：`$argon2id$v=19$m=65536,t=2,p=4$c29tZXNhbHQ$...`
+    pub fn hash_phc(&self, password: &[u8], salt: &[u8]) -> Result<String, InvalidParams> {
+        let mut tag = [0u8; Self::PHC_TAG_LEN];
+        self.hash(password, salt, &mut tag)?;
+
+        Ok(format!("${}$v={}$m={},t={},p={}${}${}",
+            self.kind.name(), self.version,
+            self.m_cost, self.t_cost, self.parallelism,
+            base64_encode(BASE64_STANDARD, salt),
+            base64_encode(BASE64_STANDARD, &tag)))
+    }
+
+    /// 验证 PHC 字符串。
+    ///
+    /// `max_m_cost`（KiB）和 `max_t_cost` 为调用者允许的开销上限，PHC 字符串里的参数超过时返回 `InvalidParams`，
+    /// 避免不可信的 PHC 字符串耗尽内存或者 CPU。
+    ///
+    /// NOTE: 算法和参数来自 PHC 字符串，Secret、Associated Data 以及线程数沿用 `self` 的设置。
+    pub fn verify_phc(&self, phc: &str, password: &[u8], max_m_cost: u32, max_t_cost: u32) -> Result<bool, InvalidParams> {
+        let (mut m, salt, tag) = parse_phc(phc, max_m_cost, max_t_cost)?;
+        m.secret = self.secret.clone();
+        m.ad = self.ad.clone();
+        m.set_threads(self.threads);
+
+        let mut out = vec![0u8; tag.len()];
+        m.hash(password, &salt, &mut out)?;
+
+        Ok(bool::from(subtle::ConstantTimeEq::ct_eq(&out[..], &tag[..])))
+    }
+}
+
+impl Default for Argon2 {
+    /// Argon2id, m=19456 (19 MiB), t=2, p=1
+    fn default() -> Self {
+        Self::new(Argon2Kind::ARGON2ID, 19 * 1024, 2, 1).unwrap()
+    }
+}
+
+fn parse_decimal(s: &str) -> Result<u32, InvalidParams> {
+    // NOTE: PHC 规定十进制数不能有多余的前导零和符号。
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0')) {
+        return Err(InvalidParams);
+    }
+    s.parse::<u32>().map_err(|_| InvalidParams)
+}
+
+// $<id>[$v=<version>]$m=<m>,t=<t>,p=<p>$<salt>$<hash>
+fn parse_phc(phc: &str, max_m_cost: u32, max_t_cost: u32) -> Result<(Argon2, Vec<u8>, Vec<u8>), InvalidParams> {
+    let mut fields = phc.split('$');
+    if fields.next() != Some("") {
+        return Err(InvalidParams);
+    }
+
+    let kind = match fields.next() {
+        Some("argon2d")  => Argon2Kind::ARGON2D,
+        Some("argon2i")  => Argon2Kind::ARGON2I,
+        Some("argon2id") => Argon2Kind::ARGON2ID,
+        _ => return Err(InvalidParams),
+    };
+
+    let mut field = fields.next().ok_or(InvalidParams)?;
+    // NOTE: 没有版本号的是最早的 0x10 版本。
+    let mut version = VERSION_10;
+    if field.starts_with("v=") {
+        version = parse_decimal(&field[2..])?;
+        field = fields.next().ok_or(InvalidParams)?;
+    }
+
+    let mut params = field.split(',');
+    let mut param = |name: &str| -> Result<u32, InvalidParams> {
+        match params.next() {
+            Some(kv) if kv.starts_with(name) && kv[name.len()..].starts_with('=') => parse_decimal(&kv[name.len() + 1..]),
+            _ => Err(InvalidParams),
+        }
+    };
+    let m_cost = param("m")?;
+    let t_cost = param("t")?;
+    let parallelism = param("p")?;
+    if params.next().is_some() {
+        return Err(InvalidParams);
+    }
+    if m_cost > max_m_cost || t_cost > max_t_cost {
+        return Err(InvalidParams);
+    }
+
+    let salt = base64_decode(BASE64_STANDARD, fields.next().ok_or(InvalidParams)?).ok_or(InvalidParams)?;
+    let tag  = base64_decode(BASE64_STANDARD, fields.next().ok_or(InvalidParams)?).ok_or(InvalidParams)?;
+    if fields.next().is_some() {
+        return Err(InvalidParams);
+    }
+
+    let m = Argon2::with_version(kind, version, m_cost, t_cost, parallelism)?;
+
+    Ok((m, salt, tag))
+}
+
+
+#[test]
+fn test_argon2() {
+    // 5.  Test Vectors
+    // https://www.rfc-editor.org/rfc/rfc9106.html#section-5
+    let password = [1u8; 32];
+    let salt = [2u8; 16];
+    let secret = [3u8; 8];
+    let ad = [4u8; 12];
+
+    let suites = [
+        (Argon2Kind::ARGON2D,  "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb"),
+        (Argon2Kind::ARGON2I,  "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8"),
+        (Argon2Kind::ARGON2ID, "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"),
+    ];
+
+    for (kind, tag) in suites.iter() {
+        let mut m = Argon2::new(*kind, 32, 3, 4).unwrap();
+        m.set_secret(&secret);
+        m.set_associated_data(&ad);
+
+        let mut out = [0u8; 32];
+        m.hash(&password, &salt, &mut out).unwrap();
+        assert_eq!(&out[..], &hex::decode(tag).unwrap()[..]);
+
+        // 多线程计算的结果必须一致
+        m.set_threads(4);
+        let mut out = [0u8; 32];
+        m.hash(&password, &salt, &mut out).unwrap();
+        assert_eq!(&out[..], &hex::decode(tag).unwrap()[..]);
+    }
+
+    // Tag 长度大于 64 Bytes
+    let m = Argon2::new(Argon2Kind::ARGON2D, 64, 1, 1).unwrap();
+    let mut out = [0u8; 100];
+    m.hash(b"password", b"somesalt", &mut out).unwrap();
+    assert_eq!(&out[..], &hex::decode("9f9d2458cd08315ca4280ffd11bcc7e0bd517878874ad68aa830d21402558299\
+c27a0a0bf1454103f15dac85dba522b34b9de953336155784fa8effbf4acb01d\
+269c42f0bb552bc46799c29a765e29fc46ee603b2d2fc63f6bbc3bd368633292\
+aa188fac").unwrap()[..]);
+}
+
+#[test]
+fn test_argon2_phc() {
+    let mut m = Argon2::new(Argon2Kind::ARGON2ID, 256, 2, 2).unwrap();
+    m.set_threads(2);
+    let phc = m.hash_phc(b"password", b"somesalt").unwrap();
+    assert_eq!(phc, "$argon2id$v=19$m=256,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc");
+
+    const MAX_M: u32 = Argon2::DEFAULT_MAX_M_COST;
+    const MAX_T: u32 = Argon2::DEFAULT_MAX_T_COST;
+
+    let m = Argon2::default();
+    assert_eq!(m.verify_phc(&phc, b"password", MAX_M, MAX_T), Ok(true));
+    assert_eq!(m.verify_phc(&phc, b"passw0rd", MAX_M, MAX_T), Ok(false));
+
+    // 0x10 版本
+    let phc = "$argon2i$v=16$m=64,t=2,p=1$c29tZXNhbHQ$SJ1rOgjchmmLy1tjuOdlJNgZY2iCQEce";
+    assert_eq!(m.verify_phc(phc, b"password", MAX_M, MAX_T), Ok(true));
+    let phc = "$argon2i$m=64,t=2,p=1$c29tZXNhbHQ$SJ1rOgjchmmLy1tjuOdlJNgZY2iCQEce";
+    assert_eq!(m.verify_phc(phc, b"password", MAX_M, MAX_T), Ok(true));
+
+    let invalid = [
+        "",
+        "$argon2x$v=19$m=256,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc",
+        "$argon2id$v=18$m=256,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc",
+        "$argon2id$v=19$t=2,m=256,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc",
+        "$argon2id$v=19$m=0256,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc",
+        "$argon2id$v=19$m=8,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc",
+        "$argon2id$v=19$m=256,t=2,p=2$c29tZXNhbHQ=$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc",
+        "$argon2id$v=19$m=256,t=2,p=2$c29tZXNhbHQ",
+    ];
+    for phc in invalid.iter() {
+        assert_eq!(m.verify_phc(phc, b"password", MAX_M, MAX_T), Err(InvalidParams));
+    }
+
+    // NOTE: 超过调用者设置的开销上限时不会进行计算。
+    let phc = "$argon2id$v=19$m=256,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc";
+    assert_eq!(m.verify_phc(phc, b"password", 256, 2), Ok(true));
+    assert_eq!(m.verify_phc(phc, b"password", 255, 2), Err(InvalidParams));
+    assert_eq!(m.verify_phc(phc, b"password", 256, 1), Err(InvalidParams));
+    let phc = "$argon2id$v=19$m=4294967295,t=2,p=2$c29tZXNhbHQ$bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc";
+    assert_eq!(m.verify_phc(phc, b"password", MAX_M, MAX_T), Err(InvalidParams));
+}
+
+#[test]
+fn test_argon2_invalid_params() {
+    assert!(Argon2::new(Argon2Kind::ARGON2ID, 32, 0, 4).is_err());
+    assert!(Argon2::new(Argon2Kind::ARGON2ID, 31, 1, 4).is_err());
+    assert!(Argon2::new(Argon2Kind::ARGON2ID, 32, 1, 0).is_err());
+    assert!(Argon2::new(Argon2Kind::ARGON2ID, u32::MAX, 1, 1 << 24).is_err());
+
+    let m = Argon2::new(Argon2Kind::ARGON2ID, 32, 1, 4).unwrap();
+    assert_eq!(m.hash(b"password", b"short", &mut [0u8; 32]), Err(InvalidParams));
+    assert_eq!(m.hash(b"password", b"somesalt", &mut [0u8; 3]), Err(InvalidParams));
+}
@@ -0,0 +1,287 @@
+// bcrypt
+// https://www.usenix.org/legacy/events/usenix99/provos/provos.pdf
+// https://en.wikipedia.org/wiki/Bcrypt
+//
+// bcrypt_pbkdf (OpenSSH `openssh-key-v1` 私钥格式所使用的 KDF)
+// https://github.com/openbsd/src/blob/master/lib/libutil/bcrypt_pbkdf.c
+//
+// 参考实现
+// https://github.com/openbsd/src/blob/master/lib/libc/crypt/bcrypt.c
+use crate::blockcipher::Blowfish;
+use crate::hash::Sha512;
+use crate::error::InvalidParams;
+use crate::util::{base64_encode, base64_decode, BASE64_BCRYPT};
+
+use subtle;
+
+
+pub const BCRYPT_SALT_LEN: usize = 16;
+pub const BCRYPT_HASH_LEN: usize = 23;
+pub const BCRYPT_MIN_COST: u32   = 4;
+pub const BCRYPT_MAX_COST: u32   = 31;
+// NOTE: 密码（包括结尾的 NUL）超出 72 Bytes 的部分会被忽略。
+pub const BCRYPT_MAX_PASSWORD_LEN: usize = 72;
+
+// "OrpheanBeholderScryDoubt"
+const BCRYPT_CTEXT: [u32; 6] = [
+    0x4f727068, 0x65616e42, 0x65686f6c,
+    0x64657253, 0x63727944, 0x6f756274,
+];
+
+// "OxychromaticBlowfishSwatDynamite"
+const BCRYPT_PBKDF_CTEXT: [u32; 8] = [
+    0x4f787963, 0x68726f6d, 0x61746963, 0x426c6f77,
+    0x66697368, 0x53776174, 0x44796e61, 0x6d697465,
+];
+
+
+// EksBlowfishSetup(cost, salt, key)
+fn eks_blowfish_setup(cost: u32, salt: &[u8], key: &[u8]) -> Blowfish {
+    let mut state = Blowfish::init_state();
+    state.expand_key_with_salt(key, salt);
+
+    let rounds = 1u64 << cost;
+    for _ in 0..rounds {
+        state.expand_key(key);
+        state.expand_key(salt);
+    }
+
+    state
+}
+
+/// bcrypt 原始输出（192 比特输出中的前 23 Bytes）
+pub fn bcrypt(cost: u32, salt: &[u8; BCRYPT_SALT_LEN], password: &[u8]) -> Result<[u8; BCRYPT_HASH_LEN], InvalidParams> {
+    if !(BCRYPT_MIN_COST..=BCRYPT_MAX_COST).contains(&cost) {
+        return Err(InvalidParams);
+    }
+
+    // NOTE: `$2b$` 版本：密码以 NUL 结尾，最长 72 Bytes。
+    let mut key = password.to_vec();
+    key.push(0);
+    key.truncate(BCRYPT_MAX_PASSWORD_LEN);
+
+    let state = eks_blowfish_setup(cost, salt, &key);
+
+    let mut cdata = BCRYPT_CTEXT;
+    for _ in 0..64 {
+        for i in (0..cdata.len()).step_by(2) {
+            let (l, r) = state.encrypt_u32x2(cdata[i], cdata[i + 1]);
+            cdata[i] = l;
+            cdata[i + 1] = r;
+        }
+    }
+
+    let mut output = [0u8; BCRYPT_CTEXT.len() * 4];
+    for (i, w) in cdata.iter().enumerate() {
+        output[i * 4..i * 4 + 4].copy_from_slice(&w.to_be_bytes());
+    }
+
+    let mut hash = [0u8; BCRYPT_HASH_LEN];
+    hash.copy_from_slice(&output[..BCRYPT_HASH_LEN]);
+
+    Ok(hash)
+}
+
+/// 输出 Modular Crypt Format 字符串，例如：`$2b$12$<22 字符的 Salt><31 字符的 Hash>`
+pub fn bcrypt_hash(password: &[u8], cost: u32, salt: &[u8; BCRYPT_SALT_LEN]) -> Result<String, InvalidParams> {
+    let hash = bcrypt(cost, salt, password)?;
+
+    Ok(format!("$2b${:02}${}{}", cost,
+        base64_encode(BASE64_BCRYPT, salt),
+        base64_encode(BASE64_BCRYPT, &hash)))
+}
+
+// NOTE: 最后一个字符中未使用的比特不一定为 0（部分实现生成的 Salt 如此），所以先清零再解码。
+fn decode_lenient(s: &str, unused_bits: u32) -> Option<Vec<u8>> {
+    let mut s = s.as_bytes().to_vec();
+    let last = s.pop()?;
+    let idx = BASE64_BCRYPT.iter().position(|&c| c == last)?;
+    s.push(BASE64_BCRYPT[idx & !((1 << unused_bits) - 1)]);
+
+    base64_decode(BASE64_BCRYPT, std::str::from_utf8(&s).ok()?)
+}
+
+/// 验证 `$2a$`、`$2b$` 以及 `$2y$` 格式的 bcrypt 字符串。
+pub fn bcrypt_verify(password: &[u8], hash: &str) -> Result<bool, InvalidParams> {
+    // $2b$NN$ + 22 + 31
+    if hash.len() != 60 || !hash.is_ascii() {
+        return Err(InvalidParams);
+    }
+    let prefix = &hash[..4];
+    if prefix != "$2a$" && prefix != "$2b$" && prefix != "$2y$" {
+        return Err(InvalidParams);
+    }
+    if &hash[6..7] != "$" || !hash[4..6].bytes().all(|c| c.is_ascii_digit()) {
+        return Err(InvalidParams);
+    }
+    let cost = hash[4..6].parse::<u32>().map_err(|_| InvalidParams)?;
+
+    let salt = decode_lenient(&hash[7..29], 4).ok_or(InvalidParams)?;
+    let expected = decode_lenient(&hash[29..], 2).ok_or(InvalidParams)?;
+
+    let mut s = [0u8; BCRYPT_SALT_LEN];
+    s.copy_from_slice(&salt);
+    let output = bcrypt(cost, &s, password)?;
+
+    Ok(bool::from(subtle::ConstantTimeEq::ct_eq(&output[..], &expected[..])))
+}
+
+
+// bcrypt_hash(sha2pass, sha2salt)
+fn bcrypt_pbkdf_hash(sha2pass: &[u8], sha2salt: &[u8]) -> [u8; 32] {
+    // key expansion
+    let mut state = Blowfish::init_state();
+    state.expand_key_with_salt(sha2pass, sha2salt);
+    for _ in 0..64 {
+        state.expand_key(sha2salt);
+        state.expand_key(sha2pass);
+    }
+
+    // encryption
+    let mut cdata = BCRYPT_PBKDF_CTEXT;
+    for _ in 0..64 {
+        for i in (0..cdata.len()).step_by(2) {
+            let (l, r) = state.encrypt_u32x2(cdata[i], cdata[i + 1]);
+            cdata[i] = l;
+            cdata[i + 1] = r;
+        }
+    }
+
+    // NOTE: 这里的输出是小端序。
+    let mut out = [0u8; 32];
+    for (i, w) in cdata.iter().enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&w.to_le_bytes());
+    }
+    out
+}
+
+/// bcrypt_pbkdf (OpenSSH)
+///
+/// 输出长度由 `out.len()` 决定，最长 1024 Bytes。
+pub fn bcrypt_pbkdf(password: &[u8], salt: &[u8], rounds: u32, out: &mut [u8]) -> Result<(), InvalidParams> {
+    const HASH_LEN: usize = 32;
+
+    if rounds < 1 || password.is_empty() || salt.is_empty() || salt.len() > 1 << 20 {
+        return Err(InvalidParams);
+    }
+    if out.is_empty() || out.len() > HASH_LEN * HASH_LEN {
+        return Err(InvalidParams);
+    }
+
+    // NOTE: 输出并不是按顺序拼接，而是把每一轮的输出交错地写入（stride）。
+    let stride = out.len().div_ceil(HASH_LEN);
+    let amt = out.len().div_ceil(stride);
+
+    let sha2pass = Sha512::oneshot(password);
+
+    let mut countsalt = salt.to_vec();
+    countsalt.extend_from_slice(&[0u8; 4]);
+
+    let mut count = 1u32;
+    let mut remaining = out.len();
+    while remaining > 0 {
+        let n = countsalt.len();
+        countsalt[n - 4..].copy_from_slice(&count.to_be_bytes());
+
+        // first round, salt is salt
+        let sha2salt = Sha512::oneshot(&countsalt);
+        let mut tmpout = bcrypt_pbkdf_hash(&sha2pass, &sha2salt);
+        let mut output = tmpout;
+
+        for _ in 1..rounds {
+            // subsequent rounds, salt is previous output
+            let sha2salt = Sha512::oneshot(tmpout);
+            tmpout = bcrypt_pbkdf_hash(&sha2pass, &sha2salt);
+            for (a, b) in output.iter_mut().zip(tmpout.iter()) {
+                *a ^= b;
+            }
+        }
+
+        let amt = std::cmp::min(amt, remaining);
+        let mut i = 0;
+        while i < amt {
+            let dest = i * stride + (count as usize - 1);
+            if dest >= out.len() {
+                break;
+            }
+            out[dest] = output[i];
+            i += 1;
+        }
+        remaining -= i;
+        count += 1;
+    }
+
+    Ok(())
+}
+
+
+#[test]
+fn test_bcrypt() {
+    let salt = [
+        38, 113, 212, 141, 108, 213, 195, 166,
+        201, 38, 20, 13, 47, 40, 104, 18,
+    ];
+    assert_eq!(bcrypt_hash(b"My S3cre7 P@55w0rd!", 5, &salt).unwrap(),
+        "$2b$05$HlFShUxTu4ZHHfOLJwfmCeDj/kuKFKboanXtDJXxCC7aIPTUgxNDe");
+
+    assert_eq!(bcrypt_hash(b"hunter2", 12, &[0u8; 16]).unwrap(),
+        "$2b$12$......................21jzCB1r6pN6rp5O2Ev0ejjTAboskKm");
+
+    let suites: &[(&[u8], &str)] = &[
+        (b"password", "$2a$04$UuTkLRZZ6QofpDOlMz32MuuxEHA43WOemOYHPz6.SjsVsyO1tDU96"),
+        (b"correctbatteryhorsestapler", "$2b$04$EGdrhbKUv8Oc9vGiXX0HQOxSg445d458Muh7DAHskb6QbtCvdxcie"),
+        (b"correctbatteryhorsestapler", "$2a$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi"),
+        (b"hunter2", "$2y$12$......................21jzCB1r6pN6rp5O2Ev0ejjTAboskKm"),
+        // 超出 72 Bytes 的部分会被忽略
+        (&[b'x'; 100], "$2a$05$......................YgIDy4hFBdVlc/6LHnD9mX488r9cLd2"),
+        (&[b'x'; 72], "$2a$05$......................YgIDy4hFBdVlc/6LHnD9mX488r9cLd2"),
+    ];
+    for (password, hash) in suites.iter() {
+        assert_eq!(bcrypt_verify(password, hash), Ok(true));
+    }
+
+    let hash = "$2b$04$EGdrhbKUv8Oc9vGiXX0HQOxSg445d458Muh7DAHskb6QbtCvdxcie";
+    assert_eq!(bcrypt_verify(b"wrong", hash), Ok(false));
+    assert_eq!(bcrypt_verify(b"correctbatteryhorsestapler\0", hash), Ok(false));
+
+    let invalid = [
+        "$2a$04$tjARW6ZON3PhrAIRW2LG/u9a.",
+        "$2a$04$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk$5bektyVVa5xnIi",
+        "$2a$ab$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi",
+        "$2a$03$n4Uy0eSnMfvnESYL.bLwuuj0U/ETSsoTpRT9GVk5bektyVVa5xnIi",
+        "$2x$12$......................21jzCB1r6pN6rp5O2Ev0ejjTAboskKm",
+    ];
+    for hash in invalid.iter() {
+        assert_eq!(bcrypt_verify(b"correctbatteryhorsestapler", hash), Err(InvalidParams));
+    }
+}
+
+#[test]
+fn test_bcrypt_pbkdf() {
+    // https://github.com/openbsd/src/blob/master/regress/lib/libutil/bcrypt_pbkdf/bcrypt_pbkdf_test.c
+    let suites: &[(&[u8], &[u8], u32, &str)] = &[
+        (b"password", b"salt", 4, "5bbf0cc293587f1c3635555c27796598d47e579071bf427e9d8fbe842aba34d9"),
+        (b"password", b"\0", 4, "c12b566235eee04c212598970a579a67"),
+        (b"\0", b"salt", 4, "6051be18c2f4f82cbf0efee5471b4bb9"),
+        (b"password\0", b"salt\0", 4, "7410e44cf4fa07bfaac8a928b1727fac001375e7bf7384370f48efd121743050"),
+        (b"pass\0wor", b"sa\0l", 4, "c2bffd9db38f6569efef4372f4de83c0"),
+        (b"pass\0word", b"sa\0lt", 4, "4ba4ac3925c0e8d7f0cdb6bb1684a56f"),
+        (b"password", b"salt", 8,
+            "e1367ec5151a33faac4cc1c144cd23fa15d5548493ecc99b9b5d9c0d3b27bec7\
+6227ea66088b849b20ab7aa478010246e74bba51723fefa9f9474d6508845e8d"),
+        (b"password", b"salt", 42, "833cf0dcf56db65608e8f0dc0ce882bd"),
+    ];
+
+    for (password, salt, rounds, key) in suites.iter() {
+        let key = hex::decode(key).unwrap();
+        let mut out = vec![0u8; key.len()];
+        bcrypt_pbkdf(password, salt, *rounds, &mut out).unwrap();
+        assert_eq!(out, key);
+    }
+
+    let mut out = [0u8; 32];
+    assert_eq!(bcrypt_pbkdf(b"", b"salt", 4, &mut out), Err(InvalidParams));
+    assert_eq!(bcrypt_pbkdf(b"password", b"", 4, &mut out), Err(InvalidParams));
+    assert_eq!(bcrypt_pbkdf(b"password", b"salt", 0, &mut out), Err(InvalidParams));
+    assert_eq!(bcrypt_pbkdf(b"password", b"salt", 4, &mut [0u8; 1025]), Err(InvalidParams));
+}
@@ -4,12 +4,14 @@ mod sm4;
 mod aes;
 mod aria;
 mod camellia;
+mod blowfish;
 
 pub use self::rc2::*;
 pub use self::sm4::*;
 pub use self::aes::*;
 pub use self::aria::*;
 pub use self::camellia::*;
+pub use self::blowfish::*;
 
 
 #[allow(non_camel_case_types)]
@@ -17,6 +19,7 @@ pub use self::camellia::*;
 pub enum BlockCipherKind {
     SM4,
     RC2_FIXED_SIZE,
+    BLOWFISH_FIXED_SIZE,
 
     AES128,
     AES192,
@@ -80,6 +83,7 @@ macro_rules! impl_block_cipher {
 }
 
 impl_block_cipher!(Rc2FixedSize, RC2_FIXED_SIZE);
+impl_block_cipher!(BlowfishFixedSize, BLOWFISH_FIXED_SIZE);
 impl_block_cipher!(Sm4, SM4);
 
 impl_block_cipher!(Aes128, AES128);
@@ -13,7 +13,7 @@
 //      需要自己手动为不定长数据按照块密码算法的块大小做对齐工作。
 // 
 use crate::blockcipher::{
-    Rc2FixedSize, Sm4,
+    Rc2FixedSize, BlowfishFixedSize, Sm4,
     Aes128, Aes192, Aes256,
     Camellia128, Camellia192, Camellia256,
     Aria128, Aria192, Aria256,
@@ -96,6 +96,7 @@ macro_rules! impl_block_cipher_with_cbc_mode {
 
 
 impl_block_cipher_with_cbc_mode!(Rc2FixedSizeCbc, Rc2FixedSize);
+impl_block_cipher_with_cbc_mode!(BlowfishFixedSizeCbc, BlowfishFixedSize);
 impl_block_cipher_with_cbc_mode!(Sm4Cbc, Sm4);
 impl_block_cipher_with_cbc_mode!(Aria128Cbc, Aria128);
 impl_block_cipher_with_cbc_mode!(Aria192Cbc, Aria192);
@@ -172,4 +173,21 @@ fn test_aes128_cbc_dec() {
 ae2d8a571e03ac9c9eb76fac45af8e51\
 30c81c46a35ce411e5fbc1191a0a52ef\
 f69f2445df4f9b17ad2b417be66c3710").unwrap()[..]);
-}
\ No newline at end of file
+}
+#[test]
+fn test_blowfish_cbc() {
+    // Test vectors by Eric Young (CBC)
+    // https://www.schneier.com/wp-content/uploads/2015/12/vectors-2.txt
+    let key   = hex::decode("0123456789abcdeff0e1d2c3b4a59687").unwrap();
+    let nonce = hex::decode("fedcba9876543210").unwrap();
+    let plaintext = hex::decode("\
+37363534333231204e6f77206973207468652074696d6520666f722000000000").unwrap();
+
+    let mut ciphertext = plaintext.clone();
+    BlowfishFixedSizeCbc::new(&key, &nonce).encrypt(&mut ciphertext);
+    assert_eq!(&ciphertext[..], &hex::decode("\
+6b77b4d63006dee605b156e27403979358deb9e7154616d959f1652bd5ff92cc").unwrap()[..]);
+
+    BlowfishFixedSizeCbc::new(&key, &nonce).decrypt(&mut ciphertext);
+    assert_eq!(&ciphertext[..], &plaintext[..]);
+}
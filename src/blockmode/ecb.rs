@@ -7,7 +7,7 @@
 // 
 
 use crate::blockcipher::{
-    Rc2FixedSize, Sm4,
+    Rc2FixedSize, BlowfishFixedSize, Sm4,
     Aes128, Aes192, Aes256,
     Camellia128, Camellia192, Camellia256,
 };
@@ -70,6 +70,7 @@ impl_block_cipher_with_ecb_mode!(Camellia192Ecb, Camellia192);
 impl_block_cipher_with_ecb_mode!(Camellia256Ecb, Camellia256);
 
 impl_block_cipher_with_ecb_mode!(Rc2FixedSizeEcb, Rc2FixedSize);
+impl_block_cipher_with_ecb_mode!(BlowfishFixedSizeEcb, BlowfishFixedSize);
 
 impl_block_cipher_with_ecb_mode!(Sm4Ecb, Sm4);
 
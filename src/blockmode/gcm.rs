@@ -221,8 +221,8 @@ macro_rules! impl_block_cipher_with_gcm_mode {
                 let mut tag = [0u8; Self::TAG_LEN];
                 tag[..Self::TAG_LEN].copy_from_slice(&self.base_ectr[..Self::TAG_LEN]);
 
-                octets[0.. 8].copy_from_slice(&clen_bits.to_le_bytes());
-                octets[8..16].copy_from_slice(&alen_bits.to_le_bytes());
+                octets[0.. 8].copy_from_slice(&alen_bits.to_be_bytes());
+                octets[8..16].copy_from_slice(&clen_bits.to_be_bytes());
 
                 mac.update(&octets);
                 let buf = mac.finalize();
@@ -393,4 +393,84 @@ e3aa212f2c02a4e035c17e2329aca12e\
 21d514b25466931c7d8f6a5aac84aa05\
 1ba30b396a0aac973d58e091").unwrap()[..]);
     assert_eq!(&plaintext_and_ciphertext[plen..], &hex::decode("5bc94fbc3221a5db94fae95ae7121a47").unwrap()[..]);
-}
\ No newline at end of file
+
+    let mut cipher = Aes128Gcm::new(&key, &iv);
+    assert!(cipher.aead_decrypt(&aad, &mut plaintext_and_ciphertext));
+    assert_eq!(&plaintext_and_ciphertext[..plen], &plaintext[..]);
+}
+
+#[test]
+fn test_aes_gcm_decrypt() {
+    // B   AES Test Vectors, (Page-29)
+    // https://csrc.nist.rip/groups/ST/toolkit/BCM/documents/proposedmodes/gcm/gcm-spec.pdf
+    //
+    // NOTE: 解密时的长度块为 len(A) || len(C)（64 位大端序），与加密时相同。
+    let k1 = "feffe9928665731c6d6a8f9467308308";
+    let k2 = "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308";
+    let iv = "cafebabefacedbaddecaf888";
+    let p = "d9313225f88406e5a55909c5aff5269a\
+86a7a9531534f7da2e4c303d8a318a72\
+1c3c0c95956809532fcf0e2449a6b525\
+b16aedf5aa0de657ba637b39";
+    let a = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+
+    // (Key, IV, AAD, Plaintext, Ciphertext || Tag)
+    let suites = [
+        // Test  Case  2
+        ("00000000000000000000000000000000", "000000000000000000000000", "",
+            "00000000000000000000000000000000".to_string(),
+            "0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf"),
+        // Test  Case  3
+        (k1, iv, "",
+            format!("{}1aafd255", p),
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985\
+4d5c2af327cd64a62cf35abd2ba6fab4"),
+        // Test  Case  4
+        (k1, iv, a,
+            p.to_string(),
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091\
+5bc94fbc3221a5db94fae95ae7121a47"),
+        // Test  Case  15
+        (k2, iv, "",
+            format!("{}1aafd255", p),
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662898015ad\
+b094dac5d93471bdec1a502270e3cc6c"),
+        // Test  Case  16
+        (k2, iv, a,
+            p.to_string(),
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662\
+76fc6ece0f4e1768cddf8853bb2d551b"),
+    ];
+
+    for (key, iv, aad, plaintext, ciphertext_and_tag) in suites.iter() {
+        let key = hex::decode(key).unwrap();
+        let iv = hex::decode(iv).unwrap();
+        let aad = hex::decode(aad).unwrap();
+        let plaintext = hex::decode(plaintext).unwrap();
+        let ciphertext_and_tag = hex::decode(ciphertext_and_tag).unwrap();
+        let plen = plaintext.len();
+
+        let mut buf = ciphertext_and_tag.clone();
+        let ok = if key.len() == Aes128Gcm::KEY_LEN {
+            Aes128Gcm::new(&key, &iv).aead_decrypt(&aad, &mut buf)
+        } else {
+            Aes256Gcm::new(&key, &iv).aead_decrypt(&aad, &mut buf)
+        };
+        assert!(ok);
+        assert_eq!(&buf[..plen], &plaintext[..]);
+
+        // 篡改 Tag
+        let mut buf = ciphertext_and_tag.clone();
+        buf[plen] ^= 1;
+        let ok = if key.len() == Aes128Gcm::KEY_LEN {
+            Aes128Gcm::new(&key, &iv).aead_decrypt(&aad, &mut buf)
+        } else {
+            Aes256Gcm::new(&key, &iv).aead_decrypt(&aad, &mut buf)
+        };
+        assert!(!ok);
+    }
+}
@@ -2,7 +2,7 @@
 // https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38a.pdf
 
 use crate::blockcipher::{
-    Rc2FixedSize, Sm4,
+    Rc2FixedSize, BlowfishFixedSize, Sm4,
     Aes128, Aes192, Aes256,
     Camellia128, Camellia192, Camellia256,
     Aria128, Aria192, Aria256,
@@ -70,6 +70,7 @@ macro_rules! impl_block_cipher_with_ofb_mode {
 
 impl_block_cipher_with_ofb_mode!(Sm4Ofb, Sm4);
 impl_block_cipher_with_ofb_mode!(Rc2FixedSizeOfb, Rc2FixedSize);
+impl_block_cipher_with_ofb_mode!(BlowfishFixedSizeOfb, BlowfishFixedSize);
 impl_block_cipher_with_ofb_mode!(Aes128Ofb, Aes128);
 impl_block_cipher_with_ofb_mode!(Aes192Ofb, Aes192);
 impl_block_cipher_with_ofb_mode!(Aes256Ofb, Aes256);
@@ -164,4 +165,21 @@ fn test_aes128_ofb_dec() {
 ae2d8a571e03ac9c9eb76fac45af8e51\
 30c81c46a35ce411e5fbc1191a0a52ef\
 f69f2445df4f9b17ad2b417be66c3710").unwrap()[..]);
-}
\ No newline at end of file
+}
+#[test]
+fn test_blowfish_ofb() {
+    // Test vectors by Eric Young (OFB64)
+    // https://www.schneier.com/wp-content/uploads/2015/12/vectors-2.txt
+    let key   = hex::decode("0123456789abcdeff0e1d2c3b4a59687").unwrap();
+    let nonce = hex::decode("fedcba9876543210").unwrap();
+    let plaintext = hex::decode("\
+37363534333231204e6f77206973207468652074696d6520666f722000").unwrap();
+
+    let mut ciphertext = plaintext.clone();
+    BlowfishFixedSizeOfb::new(&key, &nonce).encrypt(&mut ciphertext);
+    assert_eq!(&ciphertext[..], &hex::decode("\
+e73214a2822139ca62b343cc5b65587310dd908d0c241b2263c2cf80da").unwrap()[..]);
+
+    BlowfishFixedSizeOfb::new(&key, &nonce).decrypt(&mut ciphertext);
+    assert_eq!(&ciphertext[..], &plaintext[..]);
+}
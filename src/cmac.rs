@@ -0,0 +1,153 @@
+// NIST Special Publication 800-38B
+// Recommendation for Block Cipher Modes of Operation: The CMAC Mode for Authentication
+// https://nvlpubs.nist.gov/nistpubs/specialpublications/nist.sp.800-38b.pdf
+//
+// The AES-CMAC Algorithm
+// https://tools.ietf.org/html/rfc4493
+use crate::blockcipher::BlockCipher;
+use crate::blockmode::dbl;
+
+use subtle;
+
+
+/// 适用于任意分组长度为 16 Bytes 的分组密码的 CMAC
+#[derive(Clone)]
+pub struct Cmac<C: BlockCipher> {
+    cipher: C,
+    k1: [u8; 16],
+    k2: [u8; 16],
+    x: [u8; 16],
+    buffer: [u8; 16],
+    offset: usize,
+}
+
+impl<C: BlockCipher> Cmac<C> {
+    pub const KEY_LEN: usize   = C::KEY_LEN;
+    pub const BLOCK_LEN: usize = 16;
+    pub const TAG_LEN: usize   = 16;
+
+
+    pub fn new(key: &[u8]) -> Self {
+        assert_eq!(C::BLOCK_LEN, Self::BLOCK_LEN);
+        assert_eq!(key.len(), Self::KEY_LEN);
+
+        let mut cipher = C::new(key);
+
+        // 2.3.  Subkey Generation Algorithm
+        // https://tools.ietf.org/html/rfc4493#section-2.3
+        let mut zeros = [0u8; 16];
+        cipher.encrypt_block(&mut zeros);
+
+        let k1 = dbl(u128::from_be_bytes(zeros)).to_be_bytes();
+        let k2 = dbl(u128::from_be_bytes(k1)).to_be_bytes();
+
+        Self { cipher, k1, k2, x: [0u8; 16], buffer: [0u8; 16], offset: 0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+
+        // NOTE: 最后一个块需要和 K1/K2 异或，所以只有在确定后面还有数据时才处理缓冲区里的块。
+        while !data.is_empty() {
+            if self.offset == Self::BLOCK_LEN {
+                for i in 0..Self::BLOCK_LEN {
+                    self.x[i] ^= self.buffer[i];
+                }
+                self.cipher.encrypt_block(&mut self.x);
+                self.offset = 0;
+            }
+
+            let n = std::cmp::min(Self::BLOCK_LEN - self.offset, data.len());
+            self.buffer[self.offset..self.offset + n].copy_from_slice(&data[..n]);
+            self.offset += n;
+            data = &data[n..];
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 16] {
+        // 2.4.  MAC Generation Algorithm
+        // https://tools.ietf.org/html/rfc4493#section-2.4
+        if self.offset == Self::BLOCK_LEN {
+            for i in 0..Self::BLOCK_LEN {
+                self.x[i] ^= self.buffer[i] ^ self.k1[i];
+            }
+        } else {
+            self.buffer[self.offset] = 0x80;
+            for i in self.offset + 1..Self::BLOCK_LEN {
+                self.buffer[i] = 0;
+            }
+            for i in 0..Self::BLOCK_LEN {
+                self.x[i] ^= self.buffer[i] ^ self.k2[i];
+            }
+        }
+
+        self.cipher.encrypt_block(&mut self.x);
+        self.x
+    }
+
+    /// 以常量时间比较 Tag（允许截断的 Tag）。
+    pub fn verify(self, tag: &[u8]) -> bool {
+        if tag.is_empty() || tag.len() > Self::TAG_LEN {
+            return false;
+        }
+
+        let output = self.finalize();
+        bool::from(subtle::ConstantTimeEq::ct_eq(&output[..tag.len()], tag))
+    }
+
+    pub fn oneshot(key: &[u8], m: &[u8]) -> [u8; 16] {
+        let mut mac = Self::new(key);
+        mac.update(m);
+        mac.finalize()
+    }
+}
+
+
+#[test]
+fn test_cmac_aes128() {
+    use crate::blockcipher::Aes128;
+
+    // 4.  Test Vectors
+    // https://tools.ietf.org/html/rfc4493#section-4
+    let key = hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+    let m = hex::decode("6bc1bee22e409f96e93d7e117393172a\
+ae2d8a571e03ac9c9eb76fac45af8e51\
+30c81c46a35ce411e5fbc1191a0a52ef\
+f69f2445df4f9b17ad2b417be66c3710").unwrap();
+
+    let suites = [
+        (0, "bb1d6929e95937287fa37d129b756746"),
+        (16, "070a16b46b4d4144f79bdd9dd04a287c"),
+        (40, "dfa66747de9ae63030ca32611497c827"),
+        (64, "51f0bebf7e3b9d92fc49741779363cfe"),
+    ];
+
+    for (len, tag) in suites.iter() {
+        let tag = hex::decode(tag).unwrap();
+        assert_eq!(&Cmac::<Aes128>::oneshot(&key, &m[..*len])[..], &tag[..]);
+
+        // 分多次输入
+        let mut mac = Cmac::<Aes128>::new(&key);
+        for chunk in m[..*len].chunks(7) {
+            mac.update(chunk);
+        }
+        assert!(mac.clone().verify(&tag));
+        assert!(mac.clone().verify(&tag[..8]));
+        assert_eq!(&mac.finalize()[..], &tag[..]);
+    }
+}
+
+#[test]
+fn test_cmac_aes256() {
+    use crate::blockcipher::Aes256;
+
+    // D.3 CMAC-AES256
+    // https://nvlpubs.nist.gov/nistpubs/specialpublications/nist.sp.800-38b.pdf
+    let key = hex::decode("603deb1015ca71be2b73aef0857d7781\
+1f352c073b6108d72d9810a30914dff4").unwrap();
+    let m = hex::decode("6bc1bee22e409f96e93d7e117393172a\
+ae2d8a571e03ac9c9eb76fac45af8e51\
+30c81c46a35ce411").unwrap();
+    assert_eq!(&Cmac::<Aes256>::oneshot(&key, &m)[..], &hex::decode("aaf3d8f1de5640c232f5b169b9c911e6").unwrap()[..]);
+    assert_eq!(&Cmac::<Aes256>::oneshot(&key, &[])[..], &hex::decode("028962f61b7bf89efc6b551f4667d983").unwrap()[..]);
+}
@@ -0,0 +1,364 @@
+// 10.2 DRBG Mechanism Based on Block Ciphers (CTR_DRBG)
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90Ar1.pdf#page=56
+use super::{RESEED_INTERVAL, MAX_BYTES_PER_REQUEST};
+use crate::error::DrbgError;
+use crate::blockcipher::BlockCipher;
+
+
+const BLOCK_LEN: usize = 16;
+
+
+// ctr_len = blocklen
+fn incr(v: &mut [u8; BLOCK_LEN]) {
+    let n = u128::from_be_bytes(*v).wrapping_add(1);
+    *v = n.to_be_bytes();
+}
+
+
+/// CTR_DRBG（分组密码需为 128 位分组，例如 AES-128、AES-256）
+pub struct CtrDrbg<C: BlockCipher> {
+    key: Vec<u8>,
+    v: [u8; BLOCK_LEN],
+    reseed_counter: u64,
+    use_df: bool,
+    _cipher: std::marker::PhantomData<C>,
+}
+
+impl<C: BlockCipher> CtrDrbg<C> {
+    pub const SEED_LEN: usize = C::KEY_LEN + BLOCK_LEN;
+
+
+    fn empty(use_df: bool) -> Self {
+        assert_eq!(C::BLOCK_LEN, BLOCK_LEN);
+
+        Self {
+            key: vec![0u8; C::KEY_LEN],
+            v: [0u8; BLOCK_LEN],
+            reseed_counter: 1,
+            use_df,
+            _cipher: std::marker::PhantomData,
+        }
+    }
+
+    /// 10.2.1.3.2 Instantiation When a Derivation Function is Used
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Self {
+        let seed_material = Self::block_cipher_df(&[entropy, nonce, personalization], Self::SEED_LEN);
+
+        let mut m = Self::empty(true);
+        m.update(&seed_material);
+        m
+    }
+
+    /// 10.2.1.3.1 Instantiation When a Derivation Function is Not Used
+    ///
+    /// NOTE: `entropy` 的长度必须为 `SEED_LEN`，`personalization` 不能超过 `SEED_LEN`，
+    ///       否则返回 `DrbgError::InvalidInputLen`。
+    pub fn new_without_df(entropy: &[u8], personalization: &[u8]) -> Result<Self, DrbgError> {
+        let seed_material = Self::xor_seed(entropy, personalization)?;
+
+        let mut m = Self::empty(false);
+        m.update(&seed_material);
+        Ok(m)
+    }
+
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn xor_seed(entropy: &[u8], input: &[u8]) -> Result<Vec<u8>, DrbgError> {
+        if entropy.len() != Self::SEED_LEN || input.len() > Self::SEED_LEN {
+            return Err(DrbgError::InvalidInputLen);
+        }
+
+        let mut seed_material = entropy.to_vec();
+        for i in 0..input.len() {
+            seed_material[i] ^= input[i];
+        }
+        Ok(seed_material)
+    }
+
+    // 10.2.1.2 The Update Function (CTR_DRBG_Update)
+    fn update(&mut self, provided_data: &[u8]) {
+        debug_assert_eq!(provided_data.len(), Self::SEED_LEN);
+
+        let mut cipher = C::new(&self.key);
+        let mut temp = Vec::with_capacity(Self::SEED_LEN + BLOCK_LEN);
+        while temp.len() < Self::SEED_LEN {
+            incr(&mut self.v);
+            let mut block = self.v;
+            cipher.encrypt_block(&mut block);
+            temp.extend_from_slice(&block);
+        }
+        temp.truncate(Self::SEED_LEN);
+
+        for i in 0..Self::SEED_LEN {
+            temp[i] ^= provided_data[i];
+        }
+
+        self.key.copy_from_slice(&temp[..C::KEY_LEN]);
+        self.v.copy_from_slice(&temp[C::KEY_LEN..]);
+    }
+
+    // 10.3.3 BCC Function
+    fn bcc(cipher: &mut C, iv: &[u8; BLOCK_LEN], data: &[u8]) -> [u8; BLOCK_LEN] {
+        debug_assert_eq!(data.len() % BLOCK_LEN, 0);
+
+        let mut chaining_value = [0u8; BLOCK_LEN];
+        for block in std::iter::once(&iv[..]).chain(data.chunks(BLOCK_LEN)) {
+            for i in 0..BLOCK_LEN {
+                chaining_value[i] ^= block[i];
+            }
+            cipher.encrypt_block(&mut chaining_value);
+        }
+        chaining_value
+    }
+
+    // 10.3.2 Derivation Function Using a Block Cipher Algorithm (Block_Cipher_df)
+    fn block_cipher_df(input: &[&[u8]], len: usize) -> Vec<u8> {
+        let input_len: usize = input.iter().map(|d| d.len()).sum();
+
+        // S = L || N || input_string || 0x80，再补零至分组长度的整数倍。
+        let mut s = Vec::with_capacity(8 + input_len + BLOCK_LEN);
+        s.extend_from_slice(&(input_len as u32).to_be_bytes());
+        s.extend_from_slice(&(len as u32).to_be_bytes());
+        for d in input.iter() {
+            s.extend_from_slice(d);
+        }
+        s.push(0x80);
+        while s.len() % BLOCK_LEN != 0 {
+            s.push(0x00);
+        }
+
+        let key = (0u8..C::KEY_LEN as u8).collect::<Vec<u8>>();
+        let mut cipher = C::new(&key);
+
+        let mut temp = Vec::with_capacity(Self::SEED_LEN + BLOCK_LEN);
+        let mut i = 0u32;
+        while temp.len() < Self::SEED_LEN {
+            let mut iv = [0u8; BLOCK_LEN];
+            iv[..4].copy_from_slice(&i.to_be_bytes());
+            temp.extend_from_slice(&Self::bcc(&mut cipher, &iv, &s));
+            i += 1;
+        }
+
+        let mut cipher = C::new(&temp[..C::KEY_LEN]);
+        let mut x = [0u8; BLOCK_LEN];
+        x.copy_from_slice(&temp[C::KEY_LEN..C::KEY_LEN + BLOCK_LEN]);
+
+        let mut output = Vec::with_capacity(len + BLOCK_LEN);
+        while output.len() < len {
+            cipher.encrypt_block(&mut x);
+            output.extend_from_slice(&x);
+        }
+        output.truncate(len);
+        output
+    }
+
+    /// 10.2.1.4 Reseeding a CTR_DRBG Instantiation
+    ///
+    /// NOTE: 不使用派生函数时，输入长度的要求和 `new_without_df` 相同。
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        let seed_material = if self.use_df {
+            Self::block_cipher_df(&[entropy, additional_input], Self::SEED_LEN)
+        } else {
+            Self::xor_seed(entropy, additional_input)?
+        };
+
+        self.update(&seed_material);
+        self.reseed_counter = 1;
+
+        Ok(())
+    }
+
+    /// 10.2.1.5 Generating Pseudorandom Bits Using CTR_DRBG
+    pub fn generate(&mut self, additional_input: &[u8], output: &mut [u8]) -> Result<(), DrbgError> {
+        if output.len() > MAX_BYTES_PER_REQUEST {
+            return Err(DrbgError::RequestTooLarge);
+        }
+
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+
+        if !self.use_df && additional_input.len() > Self::SEED_LEN {
+            return Err(DrbgError::InvalidInputLen);
+        }
+
+        let has_additional_input = !additional_input.is_empty();
+        let additional_input = if !has_additional_input {
+            vec![0u8; Self::SEED_LEN]
+        } else if self.use_df {
+            Self::block_cipher_df(&[additional_input], Self::SEED_LEN)
+        } else {
+            let mut v = additional_input.to_vec();
+            v.resize(Self::SEED_LEN, 0);
+            v
+        };
+        if has_additional_input {
+            self.update(&additional_input);
+        }
+
+        let mut cipher = C::new(&self.key);
+        for chunk in output.chunks_mut(BLOCK_LEN) {
+            incr(&mut self.v);
+            let mut block = self.v;
+            cipher.encrypt_block(&mut block);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+
+        self.update(&additional_input);
+        self.reseed_counter += 1;
+
+        Ok(())
+    }
+}
+
+
+#[test]
+fn test_ctr_drbg_df() {
+    use crate::blockcipher::{Aes128, Aes256};
+
+    // NOTE: 以下结果与 OpenSSL 3 的 EVP_RAND（CTR-DRBG，以 TEST-RAND 提供熵）一致，
+    //       测试流程和 CAVP 相同：Instantiate、（Reseed）、Generate 两次，检查第二次的输出。
+    let entropy = (0x00u8..0x20).collect::<Vec<u8>>();
+    let nonce = (0x20u8..0x30).collect::<Vec<u8>>();
+    let personalization = (0x40u8..0x60).collect::<Vec<u8>>();
+    let entropy_reseed = (0x80u8..0xa0).collect::<Vec<u8>>();
+    let add_reseed = (0xc0u8..0xe0).collect::<Vec<u8>>();
+    let add1 = (0x60u8..0x80).collect::<Vec<u8>>();
+    let add2 = (0xa0u8..0xc0).collect::<Vec<u8>>();
+
+    let mut output = [0u8; 64];
+
+    let mut drbg = CtrDrbg::<Aes128>::new(&entropy, &nonce, &[]);
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("0568974fd3f92488caf3f5096981a5ec88ea062d4f16bb42b25e2a4aadd3036b\
+cf096395f65c065bf7a2f99b97c5ebfab1eff7997234dde6f1c03f49fef93356").unwrap()[..]);
+
+    let mut drbg = CtrDrbg::<Aes128>::new(&entropy, &nonce, &personalization);
+    drbg.reseed(&entropy_reseed, &add_reseed).unwrap();
+    drbg.generate(&add1, &mut output).unwrap();
+    drbg.generate(&add2, &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("aa849ac0e5106577f1b24f4e1d142f6a567ad0d5d1068c62b0b7cd3f46bbe412\
+88b880de747e410754162ef606399beef2f1d5313abc18a5e030d8f06b3a1933").unwrap()[..]);
+
+    let mut drbg = CtrDrbg::<Aes256>::new(&entropy, &nonce, &[]);
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("c5b1ae8dbc23056b19cf88b1997e8498b4b394c0db9760a3704b0c1d6a4c926e\
+5bfe234afb31b498a30810bdb8d3542b5530849f8b9b8bea8cad70e633f32a24").unwrap()[..]);
+
+    let mut drbg = CtrDrbg::<Aes256>::new(&entropy, &nonce, &personalization);
+    drbg.reseed(&entropy_reseed, &add_reseed).unwrap();
+    drbg.generate(&add1, &mut output).unwrap();
+    drbg.generate(&add2, &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("cfc4e3bebe323375120c3bf1d6570ca8a5eca3f019744cb3c7d86f6bcb0c2c82\
+11ee45e838df1b96135f2b069620983ae827276b4e96d0f92046c05c2e54c7ae").unwrap()[..]);
+}
+
+#[test]
+fn test_ctr_drbg_no_df() {
+    use crate::blockcipher::{Aes128, Aes256};
+
+    let mut output = [0u8; 64];
+
+    let entropy = (0x00u8..0x20).collect::<Vec<u8>>();
+    let mut drbg = CtrDrbg::<Aes128>::new_without_df(&entropy, &[]).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("796037fe48c39bf610f8a85a98565d96094b2d53595ffe0fc61be739c21d9394\
+18c5b8c55816d23aeadeee4cef57b30e543d58712f7c891721a1233da10cd90b").unwrap()[..]);
+
+    let entropy = (0x00u8..0x30).collect::<Vec<u8>>();
+    let personalization = (0x40u8..0x60).collect::<Vec<u8>>();
+    let entropy_reseed = (0x80u8..0xb0).collect::<Vec<u8>>();
+    let add_reseed = (0xc0u8..0xe0).collect::<Vec<u8>>();
+    let add1 = (0x60u8..0x80).collect::<Vec<u8>>();
+    let add2 = (0xa0u8..0xc0).collect::<Vec<u8>>();
+
+    let mut drbg = CtrDrbg::<Aes256>::new_without_df(&entropy, &personalization).unwrap();
+    drbg.reseed(&entropy_reseed, &add_reseed).unwrap();
+    drbg.generate(&add1, &mut output).unwrap();
+    drbg.generate(&add2, &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("7baa8933e4026b024bc736e955f6e4899ba0870f4ca980c7d838b6357d914a63\
+96625bc29295470f11204093df0ac6ef236a0d0f505b662df460ada7ddfa261b").unwrap()[..]);
+
+    // NOTE: 不使用派生函数时，长度不符合要求的输入返回错误而不是 panic。
+    assert_eq!(CtrDrbg::<Aes256>::new_without_df(&entropy[1..], &[]).err(), Some(DrbgError::InvalidInputLen));
+    assert_eq!(CtrDrbg::<Aes256>::new_without_df(&entropy, &[0u8; 0x31]).err(), Some(DrbgError::InvalidInputLen));
+    assert_eq!(drbg.reseed(&entropy_reseed[1..], &[]), Err(DrbgError::InvalidInputLen));
+    assert_eq!(drbg.reseed(&entropy_reseed, &[0u8; 0x31]), Err(DrbgError::InvalidInputLen));
+    assert_eq!(drbg.generate(&[0u8; 0x31], &mut output), Err(DrbgError::InvalidInputLen));
+    assert!(drbg.generate(&[0u8; 0x30], &mut output).is_ok());
+}
+
+
+#[test]
+fn test_ctr_drbg_cavp() {
+    use crate::blockcipher::{Aes128, Aes256};
+
+    // CAVP CTR_DRBG.rsp COUNT = 0
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Algorithm-Validation-Program/documents/drbg/drbgtestvectors.zip
+    let mut output = [0u8; 64];
+
+    // [AES-128 use df] (drbgvectors_no_reseed)
+    let entropy = hex::decode("890eb067acf7382eff80b0c73bc872c6").unwrap();
+    let nonce = hex::decode("aad471ef3ef1d203").unwrap();
+    let mut drbg = CtrDrbg::<Aes128>::new(&entropy, &nonce, &[]);
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("a5514ed7095f64f3d0d3a5760394ab42062f373a25072a6ea6bcfd8489e94af6\
+cf18659fea22ed1ca0a9e33f718b115ee536b12809c31b72b08ddd8be1910fa3").unwrap()[..]);
+
+    // [AES-128 use df] [PredictionResistance = False] (drbgvectors_pr_false)
+    let entropy = hex::decode("0f65da13dca407999d4773c2b4a11d85").unwrap();
+    let nonce = hex::decode("5209e5b4ed82a234").unwrap();
+    let entropy_reseed = hex::decode("1dea0a12c52bf64339dd291c80d8ca89").unwrap();
+    let mut drbg = CtrDrbg::<Aes128>::new(&entropy, &nonce, &[]);
+    drbg.reseed(&entropy_reseed, &[]).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("2859cc468a76b08661ffd23b28547ffd0997ad526a0f51261b99ed3a37bd407b\
+f418dbe6c6c3e26ed0ddefcb7474d899bd99f3655427519fc5b4057bcaf306d4").unwrap()[..]);
+
+    // [AES-256 use df] (drbgvectors_no_reseed)
+    let entropy = hex::decode("36401940fa8b1fba91a1661f211d78a0b9389a74e5bccfece8d766af1a6d3b14").unwrap();
+    let nonce = hex::decode("496f25b0f1301b4f501be30380a137eb").unwrap();
+    let mut drbg = CtrDrbg::<Aes256>::new(&entropy, &nonce, &[]);
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("5862eb38bd558dd978a696e6df164782ddd887e7e9a6c9f3f1fbafb78941b535\
+a64912dfd224c6dc7454e5250b3d97165e16260c2faf1cc7735cb75fb4f07e1d").unwrap()[..]);
+
+    // [AES-128 no df] (drbgvectors_no_reseed)
+    let entropy = hex::decode("ce50f33da5d4c1d3d4004eb35244b7f2cd7f2e5076fbf6780a7ff634b249a5fc").unwrap();
+    let mut drbg = CtrDrbg::<Aes128>::new_without_df(&entropy, &[]).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("6545c0529d372443b392ceb3ae3a99a30f963eaf313280f1d1a1e87f9db373d3\
+61e75d18018266499cccd64d9bbb8de0185f213383080faddec46bae1f784e5a").unwrap()[..]);
+
+    // [AES-256 no df] (drbgvectors_no_reseed)
+    let entropy = hex::decode("df5d73faa468649edda33b5cca79b0b05600419ccb7a879ddfec9db32ee494e5\
+531b51de16a30f769262474c73bec010").unwrap();
+    let mut drbg = CtrDrbg::<Aes256>::new_without_df(&entropy, &[]).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("d1c07cd95af8a7f11012c84ce48bb8cb87189e99d40fccb1771c619bdf82ab22\
+80b1dc2f2581f39164f7ac0c510494b3a43c41b7db17514c87b107ae793e01c5").unwrap()[..]);
+
+    let mut output = vec![0u8; MAX_BYTES_PER_REQUEST + 1];
+    assert_eq!(drbg.generate(&[], &mut output), Err(DrbgError::RequestTooLarge));
+}
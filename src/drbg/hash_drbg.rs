@@ -0,0 +1,202 @@
+// 10.1.1 Hash_DRBG
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90Ar1.pdf#page=39
+use super::{RESEED_INTERVAL, MAX_BYTES_PER_REQUEST};
+use crate::error::DrbgError;
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
+
+
+// V = (V + x) mod 2^seedlen，x 以大端序右对齐。
+fn add_be(v: &mut [u8], x: &[u8]) {
+    debug_assert!(x.len() <= v.len());
+
+    let mut carry = 0u16;
+    let offset = v.len() - x.len();
+    for i in (0..v.len()).rev() {
+        let mut n = v[i] as u16 + carry;
+        if i >= offset {
+            n += x[i - offset] as u16;
+        }
+        v[i] = n as u8;
+        carry = n >> 8;
+    }
+}
+
+
+/// Hash_DRBG
+pub struct HashDrbg<H: BuildCryptoHasher + CryptoHasher> {
+    v: Vec<u8>,
+    c: Vec<u8>,
+    reseed_counter: u64,
+    _hasher: std::marker::PhantomData<H>,
+}
+
+impl<H: BuildCryptoHasher + CryptoHasher> HashDrbg<H> {
+    const OUTLEN: usize = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+    // 10.1 Table 2: SHA-1/224/256/512_224/512_256 为 440 bits，SHA-384/512 为 888 bits。
+    const SEEDLEN: usize = if Self::OUTLEN <= 32 { 55 } else { 111 };
+
+
+    /// 10.1.1.2 Instantiation of Hash_DRBG
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Self {
+        let v = Self::hash_df(&[entropy, nonce, personalization]);
+        let c = Self::hash_df(&[&[0x00], &v]);
+
+        Self { v, c, reseed_counter: 1, _hasher: std::marker::PhantomData }
+    }
+
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn hash(data: &[&[u8]]) -> <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::Output {
+        let mut h = H::build_hasher();
+        for d in data.iter() {
+            h.write(d);
+        }
+        h.digest()
+    }
+
+    // 10.3.1 Derivation Function Using a Hash Function (Hash_df)
+    fn hash_df(input: &[&[u8]]) -> Vec<u8> {
+        let no_of_bits_to_return = (Self::SEEDLEN as u32 * 8).to_be_bytes();
+
+        let mut temp = Vec::with_capacity(Self::SEEDLEN + Self::OUTLEN);
+        let mut counter = 1u8;
+        while temp.len() < Self::SEEDLEN {
+            let counter_octets = [counter];
+            let mut data: Vec<&[u8]> = vec![&counter_octets[..], &no_of_bits_to_return[..]];
+            data.extend_from_slice(input);
+            temp.extend_from_slice(Self::hash(&data).array_as_slice());
+            counter += 1;
+        }
+        temp.truncate(Self::SEEDLEN);
+        temp
+    }
+
+    /// 10.1.1.3 Reseeding a Hash_DRBG Instantiation
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) {
+        self.v = Self::hash_df(&[&[0x01], &self.v, entropy, additional_input]);
+        self.c = Self::hash_df(&[&[0x00], &self.v]);
+        self.reseed_counter = 1;
+    }
+
+    // 10.1.1.4 Hashgen
+    fn hashgen(&self, output: &mut [u8]) {
+        let mut data = self.v.clone();
+        for chunk in output.chunks_mut(Self::OUTLEN) {
+            let w = Self::hash(&[&data]);
+            chunk.copy_from_slice(&w.array_as_slice()[..chunk.len()]);
+            add_be(&mut data, &[1]);
+        }
+    }
+
+    /// 10.1.1.4 Generating Pseudorandom Bits Using Hash_DRBG
+    pub fn generate(&mut self, additional_input: &[u8], output: &mut [u8]) -> Result<(), DrbgError> {
+        if output.len() > MAX_BYTES_PER_REQUEST {
+            return Err(DrbgError::RequestTooLarge);
+        }
+
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+
+        if !additional_input.is_empty() {
+            let w = Self::hash(&[&[0x02], &self.v, additional_input]);
+            add_be(&mut self.v, w.array_as_slice());
+        }
+
+        self.hashgen(output);
+
+        let h = Self::hash(&[&[0x03], &self.v]);
+        add_be(&mut self.v, h.array_as_slice());
+        let c = self.c.clone();
+        add_be(&mut self.v, &c);
+        add_be(&mut self.v, &self.reseed_counter.to_be_bytes());
+        self.reseed_counter += 1;
+
+        Ok(())
+    }
+}
+
+
+#[test]
+fn test_hash_drbg() {
+    use crate::hash::{Sha1, Sha256, Sha512};
+
+    // NOTE: 以下结果与 OpenSSL 3 的 EVP_RAND（HASH-DRBG，以 TEST-RAND 提供熵）一致，
+    //       测试流程和 CAVP 相同：Instantiate、（Reseed）、Generate 两次，检查第二次的输出。
+    let entropy = (0x00u8..0x20).collect::<Vec<u8>>();
+    let nonce = (0x20u8..0x30).collect::<Vec<u8>>();
+
+    let mut drbg = HashDrbg::<Sha256>::new(&entropy, &nonce, &[]);
+    let mut output = [0u8; 128];
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("48f1bd755b6b0625155a440483340d86901795fb5f804e0e5e2720d8c1692912\
+44c2a80194c4b56ee9f1585389a28382a8a66e4be9fdb48934a5688e2df1a2b1\
+621f231f3e81645cf0baf1e2072ae2e27bd1638c560cf702f09588c211528560\
+e0640c8597b4580621df98278fbc4ec69732e23070e58f549b3c5077214ce68a").unwrap()[..]);
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("27a3342a35d4bbb8e1dcd8ec0fc1a0d1a25cf906f0445d3b974dbddf4a3ba34e\
+073302ab655234a703381741af7b15191a96164cc087ad1ef8360960b94dfba7\
+451ade5f57ff6f74afeb737f8f539304c1ce58a98f3ad4b852b4cec0aceffb2b\
+d5f153f9395b593dc8d890c6d9cc570107b36cfd4b7081c42102efd89752a1de").unwrap()[..]);
+
+    let mut drbg = HashDrbg::<Sha1>::new(&entropy, &nonce, &[]);
+    let mut output = [0u8; 80];
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("f0de844a38912eb6d1e4642578a2e6390fea943bf50d5f33561eb0f6f77d6a1a\
+d06b6fc30d5d17b414af801d06e7b362f45c368451f52181ad0231e69ef3e461\
+c5b513723404fe4647c7621579073c6a").unwrap()[..]);
+
+    // SHA-512（seedlen = 888 bits）
+    let personalization = (0x40u8..0x60).collect::<Vec<u8>>();
+    let entropy_reseed = (0x80u8..0xa0).collect::<Vec<u8>>();
+    let add_reseed = (0xc0u8..0xe0).collect::<Vec<u8>>();
+    let add1 = (0x60u8..0x80).collect::<Vec<u8>>();
+    let add2 = (0xa0u8..0xc0).collect::<Vec<u8>>();
+
+    let mut drbg = HashDrbg::<Sha512>::new(&entropy, &nonce, &personalization);
+    drbg.reseed(&entropy_reseed, &add_reseed);
+    let mut output = [0u8; 256];
+    drbg.generate(&add1, &mut output).unwrap();
+    drbg.generate(&add2, &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("4461cd2ee0f6b75229a55d2414a23b33230dd43a622deda0c7f75a0fb2c5e8d8\
+2baa66e754b5267c2632f59bf0c486606aca28905aba6be9690ceb534f756dec\
+5bd1c14e87045d949ac6320da83e400ebf2018266e67e1539ca51e5e1275f6c4\
+0a043108c60099ba1777aec8044b74b31869dea5bd5c6a748353bf798833c51b\
+f69a85751bba0aaf0a3ae3cd0c9b797e722d52b49af635b6a9400a7aed7ce128\
+4c6e38a0bab16fb4d307821076b6f6d16be7d7d66609cb53f16b7e9845fac69d\
+5d012742387849b06f7b57cd4c29a13a89579621b41cf7768720369d2731d220\
+c09ada99b644a6945ef7c6994d4c78fd2cd3d7f0c9cdfa529abe78bfa61c52f5").unwrap()[..]);
+    assert_eq!(drbg.reseed_counter(), 3);
+}
+
+
+#[test]
+fn test_hash_drbg_cavp() {
+    use crate::hash::Sha256;
+
+    // CAVP Hash_DRBG.rsp [SHA-256] (drbgvectors_no_reseed) COUNT = 0
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Algorithm-Validation-Program/documents/drbg/drbgtestvectors.zip
+    let entropy = hex::decode("a65ad0f345db4e0effe875c3a2e71f42c7129d620ff5c119a9ef55f05185e0fb").unwrap();
+    let nonce = hex::decode("8581f9317517276e06e9607ddbcbcc2e").unwrap();
+
+    let mut drbg = HashDrbg::<Sha256>::new(&entropy, &nonce, &[]);
+    let mut output = [0u8; 128];
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("d3e160c35b99f340b2628264d1751060e0045da383ff57a57d73a673d2b8d80d\
+aaf6a6c35a91bb4579d73fd0c8fed111b0391306828adfed528f018121b3febd\
+c343e797b87dbb63db1333ded9d1ece177cfa6b71fe8ab1da46624ed6415e51c\
+cde2c7ca86e283990eeaeb91120415528b2295910281b02dd431f4c9f70427df").unwrap()[..]);
+
+    let mut output = vec![0u8; MAX_BYTES_PER_REQUEST + 1];
+    assert_eq!(drbg.generate(&[], &mut output), Err(DrbgError::RequestTooLarge));
+    assert_eq!(drbg.reseed_counter(), 3);
+}
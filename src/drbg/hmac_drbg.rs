@@ -0,0 +1,168 @@
+// 10.1.2 HMAC_DRBG
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90Ar1.pdf#page=44
+use super::{RESEED_INTERVAL, MAX_BYTES_PER_REQUEST};
+use crate::error::DrbgError;
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
+use crate::hmac::{Hmac, HmacState};
+
+
+/// HMAC_DRBG
+pub struct HmacDrbg<H: Hmac> {
+    k: Vec<u8>,
+    v: Vec<u8>,
+    reseed_counter: u64,
+    _hasher: std::marker::PhantomData<H>,
+}
+
+impl<H: Hmac> HmacDrbg<H> {
+    const OUTLEN: usize = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+
+
+    /// 10.1.2.3 Instantiation of HMAC_DRBG
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Self {
+        let mut m = Self {
+            k: vec![0x00; Self::OUTLEN],
+            v: vec![0x01; Self::OUTLEN],
+            reseed_counter: 1,
+            _hasher: std::marker::PhantomData,
+        };
+        m.update(&[entropy, nonce, personalization]);
+        m
+    }
+
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn hmac(key: &[u8], data: &[&[u8]]) -> Vec<u8> {
+        let mut h = HmacState::<H>::new(key);
+        for d in data.iter() {
+            h.update(d);
+        }
+        h.finalize().array_as_slice().to_vec()
+    }
+
+    // 10.1.2.2 HMAC_DRBG Update Process
+    fn update(&mut self, provided_data: &[&[u8]]) {
+        let is_empty = provided_data.iter().all(|d| d.is_empty());
+
+        for &sep in [0x00u8, 0x01].iter() {
+            if sep == 0x01 && is_empty {
+                break;
+            }
+
+            let sep = [sep];
+            let mut data: Vec<&[u8]> = vec![&self.v[..], &sep[..]];
+            data.extend_from_slice(provided_data);
+            self.k = Self::hmac(&self.k, &data);
+            self.v = Self::hmac(&self.k, &[&self.v]);
+        }
+    }
+
+    /// 10.1.2.4 Reseeding an HMAC_DRBG Instantiation
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) {
+        self.update(&[entropy, additional_input]);
+        self.reseed_counter = 1;
+    }
+
+    /// 10.1.2.5 Generating Pseudorandom Bits Using HMAC_DRBG
+    pub fn generate(&mut self, additional_input: &[u8], output: &mut [u8]) -> Result<(), DrbgError> {
+        if output.len() > MAX_BYTES_PER_REQUEST {
+            return Err(DrbgError::RequestTooLarge);
+        }
+
+        if self.reseed_counter > RESEED_INTERVAL {
+            return Err(DrbgError::ReseedRequired);
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+
+        for chunk in output.chunks_mut(Self::OUTLEN) {
+            self.v = Self::hmac(&self.k, &[&self.v]);
+            chunk.copy_from_slice(&self.v[..chunk.len()]);
+        }
+
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+
+        Ok(())
+    }
+}
+
+
+#[test]
+fn test_hmac_drbg_sha256() {
+    use crate::hash::Sha256;
+
+    // CAVP HMAC_DRBG.rsp [SHA-256] [PredictionResistance = False]
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Algorithm-Validation-Program/documents/drbg/drbgtestvectors.zip
+    let entropy = hex::decode("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488").unwrap();
+    let nonce = hex::decode("659ba96c601dc69fc902940805ec0ca8").unwrap();
+
+    let mut drbg = HmacDrbg::<Sha256>::new(&entropy, &nonce, &[]);
+    let mut output = [0u8; 128];
+    drbg.generate(&[], &mut output).unwrap();
+    drbg.generate(&[], &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89\
+d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc1\
+07694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668\
+961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8").unwrap()[..]);
+    assert_eq!(drbg.reseed_counter(), 3);
+
+    let mut big = vec![0u8; MAX_BYTES_PER_REQUEST + 1];
+    assert_eq!(drbg.generate(&[], &mut big), Err(DrbgError::RequestTooLarge));
+    assert_eq!(drbg.reseed_counter(), 3);
+
+    // AdditionalInputLen = 256
+    let entropy = hex::decode("d3cc4d1acf3dde0c4bd2290d262337042dc632948223d3a2eaab87da44295fbd").unwrap();
+    let nonce = hex::decode("0109b0e729f457328aa18569a9224921").unwrap();
+    let add1 = hex::decode("3c311848183c9a212a26f27f8c6647e40375e466a0857cc39c4e47575d53f1f6").unwrap();
+    let add2 = hex::decode("fcb9abd19ccfbccef88c9c39bfb3dd7b1c12266c9808992e305bc3cff566e4e4").unwrap();
+
+    let mut drbg = HmacDrbg::<Sha256>::new(&entropy, &nonce, &[]);
+    drbg.generate(&add1, &mut output).unwrap();
+    drbg.generate(&add2, &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("9c7b758b212cd0fcecd5daa489821712e3cdea4467b560ef5ddc24ab47749a1f\
+1ffdbbb118f4e62fcfca3371b8fbfc5b0646b83e06bfbbab5fac30ea09ea2bc7\
+6f1ea568c9be0444b2cc90517b20ca825f2d0eccd88e7175538b85d90ab39018\
+3ca6395535d34473af6b5a5b88f5a59ee7561573337ea819da0dcc3573a22974").unwrap()[..]);
+}
+
+#[test]
+fn test_hmac_drbg_reseed() {
+    use crate::hash::{Sha1, Sha256};
+
+    // NOTE: 以下结果与 OpenSSL 3 的 EVP_RAND（HMAC-DRBG，以 TEST-RAND 提供熵）一致。
+    let entropy = (0x00u8..0x20).collect::<Vec<u8>>();
+    let nonce = (0x20u8..0x30).collect::<Vec<u8>>();
+    let personalization = (0x40u8..0x60).collect::<Vec<u8>>();
+    let entropy_reseed = (0x80u8..0xa0).collect::<Vec<u8>>();
+    let add_reseed = (0xc0u8..0xe0).collect::<Vec<u8>>();
+    let add1 = (0x60u8..0x80).collect::<Vec<u8>>();
+    let add2 = (0xa0u8..0xc0).collect::<Vec<u8>>();
+
+    let mut drbg = HmacDrbg::<Sha256>::new(&entropy, &nonce, &personalization);
+    drbg.reseed(&entropy_reseed, &add_reseed);
+    let mut output = [0u8; 128];
+    drbg.generate(&add1, &mut output).unwrap();
+    drbg.generate(&add2, &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("ea1e9731285d92d79629f5136218a7cf49889bfc014f04e346c3cb292225ae42\
+7d1ede8c769ce7ce6071c86fa7500201b269b3c5edfd4eeaa5ae5df45334bad3\
+fd6070abd9855131541ac3be8900826862139e0a123a402beeb4b35c9ca7e4d5\
+84c913a937983d56acb9861e7ee92c768298044553d6677d78a5a524ba347097").unwrap()[..]);
+
+    let mut drbg = HmacDrbg::<Sha1>::new(&entropy, &nonce, &personalization);
+    drbg.reseed(&entropy_reseed, &add_reseed);
+    let mut output = [0u8; 80];
+    drbg.generate(&add1, &mut output).unwrap();
+    drbg.generate(&add2, &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("69e03b30f24af9c7ff91e83aa98756dfb842d891c8ba016f699a72fefc8a9d97\
+ded62227155c5a7616a69ec8ab01e67a0b8d2537b5abae8f574322016af10af3\
+90f8c4ef12827f643eaccd129327ff41").unwrap()[..]);
+}
@@ -0,0 +1,22 @@
+// Recommendation for Random Number Generation Using Deterministic Random Bit Generators
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90Ar1.pdf
+//
+// CAVP Testing: Random Number Generators
+// https://csrc.nist.gov/projects/cryptographic-algorithm-validation-program/random-number-generators
+//
+// NOTE: DRBG 本身并不收集熵，`entropy`、`nonce` 需要由调用者提供（例如来自操作系统）。
+//       同样的输入总是得到同样的输出，可用于 RFC 6979 的确定性 Nonce 以及可复现的测试。
+mod hmac_drbg;
+mod hash_drbg;
+mod ctr_drbg;
+
+pub use self::hmac_drbg::*;
+pub use self::hash_drbg::*;
+pub use self::ctr_drbg::*;
+
+
+// 10.1 Table 2 / 10.2.1 Table 3
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90Ar1.pdf#page=38
+const RESEED_INTERVAL: u64 = 1 << 48;
+// max_number_of_bits_per_request: 2^19 bits
+const MAX_BYTES_PER_REQUEST: usize = 1 << 16;
@@ -26,6 +26,53 @@ impl std::fmt::Display for InvalidNonceLen {
 }
 impl std::error::Error for InvalidNonceLen { }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct InvalidOutputLen;
+impl std::fmt::Display for InvalidOutputLen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid Output Len")
+    }
+}
+impl std::error::Error for InvalidOutputLen { }
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct InvalidHashState;
+impl std::fmt::Display for InvalidHashState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid Hash State")
+    }
+}
+impl std::error::Error for InvalidHashState { }
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DrbgError {
+    /// 已达到 reseed_interval，需要调用 `reseed`
+    ReseedRequired,
+    /// 单次请求超过 max_number_of_bits_per_request
+    RequestTooLarge,
+    /// 输入长度无效（不使用派生函数时，entropy 必须为 seedlen，其它输入不能超过 seedlen）
+    InvalidInputLen,
+}
+impl std::fmt::Display for DrbgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrbgError::ReseedRequired => write!(f, "Reseed Required"),
+            DrbgError::RequestTooLarge => write!(f, "Request Too Large"),
+            DrbgError::InvalidInputLen => write!(f, "Invalid Input Len"),
+        }
+    }
+}
+impl std::error::Error for DrbgError { }
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct InvalidParams;
+impl std::fmt::Display for InvalidParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid Params")
+    }
+}
+impl std::error::Error for InvalidParams { }
+
 // UnexpectedEof
 // Exhausted
 
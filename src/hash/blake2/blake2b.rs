@@ -0,0 +1,345 @@
+// The BLAKE2 Cryptographic Hash and Message Authentication Code (MAC)
+// https://tools.ietf.org/html/rfc7693
+//
+// https://www.blake2.net/blake2.pdf
+use super::SIGMA;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::{InvalidHashState, InvalidParams};
+
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+
+/// BLAKE2b-512
+pub fn blake2b_512<T: AsRef<[u8]>>(data: T) -> [u8; Blake2b512::DIGEST_LEN] {
+    Blake2b512::oneshot(data)
+}
+
+
+// 3.1.  Mixing Function G
+// https://tools.ietf.org/html/rfc7693#section-3.1
+macro_rules! G {
+    ($v:expr, $a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $y:expr) => {
+        $v[$a] = $v[$a].wrapping_add($v[$b]).wrapping_add($x);
+        $v[$d] = ($v[$d] ^ $v[$a]).rotate_right(32);
+        $v[$c] = $v[$c].wrapping_add($v[$d]);
+        $v[$b] = ($v[$b] ^ $v[$c]).rotate_right(24);
+        $v[$a] = $v[$a].wrapping_add($v[$b]).wrapping_add($y);
+        $v[$d] = ($v[$d] ^ $v[$a]).rotate_right(16);
+        $v[$c] = $v[$c].wrapping_add($v[$d]);
+        $v[$b] = ($v[$b] ^ $v[$c]).rotate_right(63);
+    }
+}
+
+// 3.2.  Compression Function F
+// https://tools.ietf.org/html/rfc7693#section-3.2
+#[inline]
+fn transform(state: &mut [u64; 8], block: &[u8], counter: u128, last: bool) {
+    debug_assert_eq!(block.len(), Blake2b::BLOCK_LEN);
+
+    let mut m = [0u64; 16];
+    for (w, chunk) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *w = u64::from_le_bytes([
+            chunk[0], chunk[1], chunk[2], chunk[3],
+            chunk[4], chunk[5], chunk[6], chunk[7],
+        ]);
+    }
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(&state[..]);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+
+    v[12] ^= counter as u64;
+    v[13] ^= (counter >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+
+    for i in 0..12 {
+        let s = &SIGMA[i % 10];
+        G!(v, 0, 4,  8, 12, m[s[ 0]], m[s[ 1]]);
+        G!(v, 1, 5,  9, 13, m[s[ 2]], m[s[ 3]]);
+        G!(v, 2, 6, 10, 14, m[s[ 4]], m[s[ 5]]);
+        G!(v, 3, 7, 11, 15, m[s[ 6]], m[s[ 7]]);
+        G!(v, 0, 5, 10, 15, m[s[ 8]], m[s[ 9]]);
+        G!(v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        G!(v, 2, 7,  8, 13, m[s[12]], m[s[13]]);
+        G!(v, 3, 4,  9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        state[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+
+/// BLAKE2b（输出长度可变，支持 Key、Salt 以及 Personalization 参数）
+#[derive(Clone)]
+pub struct Blake2b {
+    buffer: [u8; Self::BLOCK_LEN],
+    offset: usize,
+    state: [u64; 8],
+    counter: u128,    // in bytes.
+    digest_len: usize,
+}
+
+impl Blake2b {
+    pub const BLOCK_LEN: usize        = 128;
+    pub const MAX_DIGEST_LEN: usize   =  64;
+    pub const MAX_KEY_LEN: usize      =  64;
+    pub const SALT_LEN: usize         =  16;
+    pub const PERSONAL_LEN: usize     =  16;
+
+
+    /// `digest_len` 必须在 `1..=MAX_DIGEST_LEN` 之间，否则会 panic。
+    pub fn new(digest_len: usize) -> Self {
+        assert!((1..=Self::MAX_DIGEST_LEN).contains(&digest_len));
+
+        Self::init(digest_len, &[], &[], &[])
+    }
+
+    /// Keyed hashing (MAC)
+    pub fn with_key(digest_len: usize, key: &[u8]) -> Result<Self, InvalidParams> {
+        Self::with_params(digest_len, key, &[], &[])
+    }
+
+    // NOTE: Salt 和 Personalization 不足 16 Bytes 时，在后面补零。
+    pub fn with_params(digest_len: usize, key: &[u8], salt: &[u8], personal: &[u8]) -> Result<Self, InvalidParams> {
+        if !(1..=Self::MAX_DIGEST_LEN).contains(&digest_len)
+            || key.len() > Self::MAX_KEY_LEN
+            || salt.len() > Self::SALT_LEN
+            || personal.len() > Self::PERSONAL_LEN
+        {
+            return Err(InvalidParams);
+        }
+
+        Ok(Self::init(digest_len, key, salt, personal))
+    }
+
+    fn init(digest_len: usize, key: &[u8], salt: &[u8], personal: &[u8]) -> Self {
+        // Parameter block
+        let mut p = [0u8; 64];
+        p[0] = digest_len as u8;
+        p[1] = key.len() as u8;
+        p[2] = 1; // fanout
+        p[3] = 1; // depth
+        p[32..32 + salt.len()].copy_from_slice(salt);
+        p[48..48 + personal.len()].copy_from_slice(personal);
+
+        let mut state = BLAKE2B_IV;
+        for (w, chunk) in state.iter_mut().zip(p.chunks_exact(8)) {
+            *w ^= u64::from_le_bytes([
+                chunk[0], chunk[1], chunk[2], chunk[3],
+                chunk[4], chunk[5], chunk[6], chunk[7],
+            ]);
+        }
+
+        let mut buffer = [0u8; Self::BLOCK_LEN];
+        let mut offset = 0usize;
+        if !key.is_empty() {
+            // NOTE: Key 会被补零至一个完整的块，作为第一个消息块。
+            buffer[..key.len()].copy_from_slice(key);
+            offset = Self::BLOCK_LEN;
+        }
+
+        Self { buffer, offset, state, counter: 0, digest_len }
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.digest_len
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+
+        // NOTE: 最后一个块需要在 `finalize` 时做特殊处理，
+        //       所以只有在确定后面还有数据时才压缩缓冲区里的块。
+        while !data.is_empty() {
+            if self.offset == Self::BLOCK_LEN {
+                self.counter += Self::BLOCK_LEN as u128;
+                transform(&mut self.state, &self.buffer, self.counter, false);
+                self.offset = 0;
+            }
+
+            if self.offset == 0 && data.len() > Self::BLOCK_LEN {
+                self.counter += Self::BLOCK_LEN as u128;
+                transform(&mut self.state, &data[..Self::BLOCK_LEN], self.counter, false);
+                data = &data[Self::BLOCK_LEN..];
+                continue;
+            }
+
+            let n = std::cmp::min(Self::BLOCK_LEN - self.offset, data.len());
+            self.buffer[self.offset..self.offset + n].copy_from_slice(&data[..n]);
+            self.offset += n;
+            data = &data[n..];
+        }
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u64s(&self.state);
+        w.write_u128(self.counter);
+        w.write_usize(self.digest_len);
+        w.write_usize(self.offset);
+        w.write_bytes(&self.buffer[..self.offset]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut state = [0u64; 8];
+        r.read_u64s(&mut state)?;
+        let counter = r.read_u128()?;
+        let digest_len = r.read_usize()?;
+        let offset = r.read_usize()?;
+        if !(1..=Self::MAX_DIGEST_LEN).contains(&digest_len) || offset > Self::BLOCK_LEN {
+            return Err(InvalidHashState);
+        }
+        let mut buffer = [0u8; Self::BLOCK_LEN];
+        buffer[..offset].copy_from_slice(r.read_bytes(offset)?);
+
+        Ok(Self { buffer, offset, state, counter, digest_len })
+    }
+
+    fn finalize_state(&mut self) {
+        self.counter += self.offset as u128;
+        for i in self.offset..Self::BLOCK_LEN {
+            self.buffer[i] = 0;
+        }
+        transform(&mut self.state, &self.buffer, self.counter, true);
+    }
+
+    pub fn finalize(mut self, output: &mut [u8]) {
+        assert_eq!(output.len(), self.digest_len);
+
+        self.finalize_state();
+
+        let mut digest = [0u8; Self::MAX_DIGEST_LEN];
+        for i in 0..8 {
+            digest[i*8..i*8 + 8].copy_from_slice(&self.state[i].to_le_bytes());
+        }
+        output.copy_from_slice(&digest[..self.digest_len]);
+    }
+
+    pub fn state(&self) -> &[u64; 8] {
+        &self.state
+    }
+}
+
+impl_hash_state!(Blake2b, "BLAKE2b");
+
+
+/// BLAKE2b-512
+#[derive(Clone)]
+pub struct Blake2b512 {
+    inner: Blake2b,
+}
+
+impl Blake2b512 {
+    pub const BLOCK_LEN: usize  = Blake2b::BLOCK_LEN;
+    pub const DIGEST_LEN: usize = 64;
+
+    pub fn new() -> Self {
+        Self { inner: Blake2b::new(Self::DIGEST_LEN) }
+    }
+
+    pub fn with_key(key: &[u8]) -> Result<Self, InvalidParams> {
+        Blake2b::with_key(Self::DIGEST_LEN, key).map(|inner| Self { inner })
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(&mut self) {
+        self.inner.finalize_state();
+    }
+
+    pub fn state(&self) -> &[u64; 8] {
+        &self.inner.state
+    }
+
+    pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+        let mut output = [0u8; Self::DIGEST_LEN];
+        for i in 0..8 {
+            output[i*8..i*8 + 8].copy_from_slice(&self.inner.state[i].to_le_bytes());
+        }
+        output
+    }
+
+    pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new();
+        m.update(data.as_ref());
+        m.finalize();
+        m.output()
+    }
+}
+
+impl Default for Blake2b512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_hash_state!(Blake2b512, "BLAKE2b-512", inner: Blake2b);
+
+
+#[test]
+fn test_blake2b_512() {
+    // Appendix A.  Example of BLAKE2b Computation
+    // https://tools.ietf.org/html/rfc7693#appendix-A
+    assert_eq!(&blake2b_512("abc")[..],
+        &hex::decode("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap()[..]);
+    assert_eq!(&blake2b_512("")[..],
+        &hex::decode("786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419\
+d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce").unwrap()[..]);
+
+    let msg = vec![0u8; 1000_000];
+    let mut m = Blake2b512::new();
+    for chunk in msg.chunks(1000 - 1) {
+        m.update(chunk);
+    }
+    m.finalize();
+    assert_eq!(&m.output()[..],
+        &hex::decode("9ef8b51be521c6e33abb22d6a69363902b6d7eb67ca1364ebc87a64d5a36ec5e\
+749e5c9e7029a85b0008e46cff24281e87500886818dbe79dc8e094f119bbeb8").unwrap()[..]);
+}
+
+#[test]
+fn test_blake2b_keyed() {
+    // https://github.com/BLAKE2/BLAKE2/blob/master/testvectors/blake2b-kat.txt
+    let key = (0u8..64).collect::<Vec<u8>>();
+    let msg = (0u8..255).collect::<Vec<u8>>();
+
+    let mut m = Blake2b512::with_key(&key).unwrap();
+    m.update(&msg);
+    m.finalize();
+    assert_eq!(&m.output()[..],
+        &hex::decode("142709d62e28fcccd0af97fad0f8465b971e82201dc51070faa0372aa43e9248\
+4be1c1e73ba10906d5d1853db6a4106e0a7bf9800d373d6dee2d46d62ef2a461").unwrap()[..]);
+
+    // NOTE: 空消息时，Key 所在的块就是最后一个块。
+    let mut output = [0u8; 64];
+    Blake2b::with_key(64, b"k").unwrap().finalize(&mut output);
+    assert_eq!(&output[..],
+        &hex::decode("a393a0e4093eea8bfd03ebe262849654a10fbf67afc7f4f533efc0f992b33cbc\
+574f32066446c2447ef23d5e86fabfd213b9eed79173ee8900909f2da52269cc").unwrap()[..]);
+}
+
+#[test]
+fn test_blake2b_params() {
+    let mut m = Blake2b::with_params(32, b"secret", b"0123456789abcdef", b"MyApp personal!!").unwrap();
+    m.update(b"hello");
+    let mut output = [0u8; 32];
+    m.finalize(&mut output);
+    assert_eq!(&output[..],
+        &hex::decode("2a8247f27c4d1034ee22a3d5efb99db5c78b5206f82340503cc2d4eb097cb959").unwrap()[..]);
+
+    // NOTE: 超出范围的参数返回错误而不是 panic。
+    assert!(Blake2b::with_params(0, &[], &[], &[]).is_err());
+    assert!(Blake2b::with_params(65, &[], &[], &[]).is_err());
+    assert!(Blake2b::with_key(64, &[0u8; 65]).is_err());
+    assert!(Blake2b::with_params(64, &[], &[0u8; 17], &[]).is_err());
+    assert!(Blake2b::with_params(64, &[], &[], &[0u8; 17]).is_err());
+}
@@ -0,0 +1,326 @@
+// The BLAKE2 Cryptographic Hash and Message Authentication Code (MAC)
+// https://tools.ietf.org/html/rfc7693
+//
+// https://www.blake2.net/blake2.pdf
+use super::SIGMA;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::{InvalidHashState, InvalidParams};
+
+
+const BLAKE2S_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+    0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+
+/// BLAKE2s-256
+pub fn blake2s_256<T: AsRef<[u8]>>(data: T) -> [u8; Blake2s256::DIGEST_LEN] {
+    Blake2s256::oneshot(data)
+}
+
+
+// 3.1.  Mixing Function G
+// https://tools.ietf.org/html/rfc7693#section-3.1
+macro_rules! G {
+    ($v:expr, $a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $y:expr) => {
+        $v[$a] = $v[$a].wrapping_add($v[$b]).wrapping_add($x);
+        $v[$d] = ($v[$d] ^ $v[$a]).rotate_right(16);
+        $v[$c] = $v[$c].wrapping_add($v[$d]);
+        $v[$b] = ($v[$b] ^ $v[$c]).rotate_right(12);
+        $v[$a] = $v[$a].wrapping_add($v[$b]).wrapping_add($y);
+        $v[$d] = ($v[$d] ^ $v[$a]).rotate_right(8);
+        $v[$c] = $v[$c].wrapping_add($v[$d]);
+        $v[$b] = ($v[$b] ^ $v[$c]).rotate_right(7);
+    }
+}
+
+// 3.2.  Compression Function F
+// https://tools.ietf.org/html/rfc7693#section-3.2
+#[inline]
+fn transform(state: &mut [u32; 8], block: &[u8], counter: u64, last: bool) {
+    debug_assert_eq!(block.len(), Blake2s::BLOCK_LEN);
+
+    let mut m = [0u32; 16];
+    for (w, chunk) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *w = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(&state[..]);
+    v[8..].copy_from_slice(&BLAKE2S_IV);
+
+    v[12] ^= counter as u32;
+    v[13] ^= (counter >> 32) as u32;
+    if last {
+        v[14] = !v[14];
+    }
+
+    for s in SIGMA.iter() {
+        G!(v, 0, 4,  8, 12, m[s[ 0]], m[s[ 1]]);
+        G!(v, 1, 5,  9, 13, m[s[ 2]], m[s[ 3]]);
+        G!(v, 2, 6, 10, 14, m[s[ 4]], m[s[ 5]]);
+        G!(v, 3, 7, 11, 15, m[s[ 6]], m[s[ 7]]);
+        G!(v, 0, 5, 10, 15, m[s[ 8]], m[s[ 9]]);
+        G!(v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        G!(v, 2, 7,  8, 13, m[s[12]], m[s[13]]);
+        G!(v, 3, 4,  9, 14, m[s[14]], m[s[15]]);
+    }
+
+    for i in 0..8 {
+        state[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+
+/// BLAKE2s（输出长度可变，支持 Key、Salt 以及 Personalization 参数）
+#[derive(Clone)]
+pub struct Blake2s {
+    buffer: [u8; Self::BLOCK_LEN],
+    offset: usize,
+    state: [u32; 8],
+    counter: u64,    // in bytes.
+    digest_len: usize,
+}
+
+impl Blake2s {
+    pub const BLOCK_LEN: usize        = 64;
+    pub const MAX_DIGEST_LEN: usize   = 32;
+    pub const MAX_KEY_LEN: usize      = 32;
+    pub const SALT_LEN: usize         =  8;
+    pub const PERSONAL_LEN: usize     =  8;
+
+
+    /// `digest_len` 必须在 `1..=MAX_DIGEST_LEN` 之间，否则会 panic。
+    pub fn new(digest_len: usize) -> Self {
+        assert!((1..=Self::MAX_DIGEST_LEN).contains(&digest_len));
+
+        Self::init(digest_len, &[], &[], &[])
+    }
+
+    /// Keyed hashing (MAC)
+    pub fn with_key(digest_len: usize, key: &[u8]) -> Result<Self, InvalidParams> {
+        Self::with_params(digest_len, key, &[], &[])
+    }
+
+    // NOTE: Salt 和 Personalization 不足 8 Bytes 时，在后面补零。
+    pub fn with_params(digest_len: usize, key: &[u8], salt: &[u8], personal: &[u8]) -> Result<Self, InvalidParams> {
+        if !(1..=Self::MAX_DIGEST_LEN).contains(&digest_len)
+            || key.len() > Self::MAX_KEY_LEN
+            || salt.len() > Self::SALT_LEN
+            || personal.len() > Self::PERSONAL_LEN
+        {
+            return Err(InvalidParams);
+        }
+
+        Ok(Self::init(digest_len, key, salt, personal))
+    }
+
+    fn init(digest_len: usize, key: &[u8], salt: &[u8], personal: &[u8]) -> Self {
+        // Parameter block
+        let mut p = [0u8; 32];
+        p[0] = digest_len as u8;
+        p[1] = key.len() as u8;
+        p[2] = 1; // fanout
+        p[3] = 1; // depth
+        p[16..16 + salt.len()].copy_from_slice(salt);
+        p[24..24 + personal.len()].copy_from_slice(personal);
+
+        let mut state = BLAKE2S_IV;
+        for (w, chunk) in state.iter_mut().zip(p.chunks_exact(4)) {
+            *w ^= u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+
+        let mut buffer = [0u8; Self::BLOCK_LEN];
+        let mut offset = 0usize;
+        if !key.is_empty() {
+            // NOTE: Key 会被补零至一个完整的块，作为第一个消息块。
+            buffer[..key.len()].copy_from_slice(key);
+            offset = Self::BLOCK_LEN;
+        }
+
+        Self { buffer, offset, state, counter: 0, digest_len }
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.digest_len
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+
+        // NOTE: 最后一个块需要在 `finalize` 时做特殊处理，
+        //       所以只有在确定后面还有数据时才压缩缓冲区里的块。
+        while !data.is_empty() {
+            if self.offset == Self::BLOCK_LEN {
+                self.counter += Self::BLOCK_LEN as u64;
+                transform(&mut self.state, &self.buffer, self.counter, false);
+                self.offset = 0;
+            }
+
+            if self.offset == 0 && data.len() > Self::BLOCK_LEN {
+                self.counter += Self::BLOCK_LEN as u64;
+                transform(&mut self.state, &data[..Self::BLOCK_LEN], self.counter, false);
+                data = &data[Self::BLOCK_LEN..];
+                continue;
+            }
+
+            let n = std::cmp::min(Self::BLOCK_LEN - self.offset, data.len());
+            self.buffer[self.offset..self.offset + n].copy_from_slice(&data[..n]);
+            self.offset += n;
+            data = &data[n..];
+        }
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32s(&self.state);
+        w.write_u64(self.counter);
+        w.write_usize(self.digest_len);
+        w.write_usize(self.offset);
+        w.write_bytes(&self.buffer[..self.offset]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut state = [0u32; 8];
+        r.read_u32s(&mut state)?;
+        let counter = r.read_u64()?;
+        let digest_len = r.read_usize()?;
+        let offset = r.read_usize()?;
+        if !(1..=Self::MAX_DIGEST_LEN).contains(&digest_len) || offset > Self::BLOCK_LEN {
+            return Err(InvalidHashState);
+        }
+        let mut buffer = [0u8; Self::BLOCK_LEN];
+        buffer[..offset].copy_from_slice(r.read_bytes(offset)?);
+
+        Ok(Self { buffer, offset, state, counter, digest_len })
+    }
+
+    fn finalize_state(&mut self) {
+        self.counter += self.offset as u64;
+        for i in self.offset..Self::BLOCK_LEN {
+            self.buffer[i] = 0;
+        }
+        transform(&mut self.state, &self.buffer, self.counter, true);
+    }
+
+    pub fn finalize(mut self, output: &mut [u8]) {
+        assert_eq!(output.len(), self.digest_len);
+
+        self.finalize_state();
+
+        let mut digest = [0u8; Self::MAX_DIGEST_LEN];
+        for i in 0..8 {
+            digest[i*4..i*4 + 4].copy_from_slice(&self.state[i].to_le_bytes());
+        }
+        output.copy_from_slice(&digest[..self.digest_len]);
+    }
+
+    pub fn state(&self) -> &[u32; 8] {
+        &self.state
+    }
+}
+
+impl_hash_state!(Blake2s, "BLAKE2s");
+
+
+/// BLAKE2s-256
+#[derive(Clone)]
+pub struct Blake2s256 {
+    inner: Blake2s,
+}
+
+impl Blake2s256 {
+    pub const BLOCK_LEN: usize  = Blake2s::BLOCK_LEN;
+    pub const DIGEST_LEN: usize = 32;
+
+    pub fn new() -> Self {
+        Self { inner: Blake2s::new(Self::DIGEST_LEN) }
+    }
+
+    pub fn with_key(key: &[u8]) -> Result<Self, InvalidParams> {
+        Blake2s::with_key(Self::DIGEST_LEN, key).map(|inner| Self { inner })
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(&mut self) {
+        self.inner.finalize_state();
+    }
+
+    pub fn state(&self) -> &[u32; 8] {
+        &self.inner.state
+    }
+
+    pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+        let mut output = [0u8; Self::DIGEST_LEN];
+        for i in 0..8 {
+            output[i*4..i*4 + 4].copy_from_slice(&self.inner.state[i].to_le_bytes());
+        }
+        output
+    }
+
+    pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new();
+        m.update(data.as_ref());
+        m.finalize();
+        m.output()
+    }
+}
+
+impl Default for Blake2s256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_hash_state!(Blake2s256, "BLAKE2s-256", inner: Blake2s);
+
+
+#[test]
+fn test_blake2s_256() {
+    // Appendix B.  Example of BLAKE2s Computation
+    // https://tools.ietf.org/html/rfc7693#appendix-B
+    assert_eq!(&blake2s_256("abc")[..],
+        &hex::decode("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982").unwrap()[..]);
+    assert_eq!(&blake2s_256("")[..],
+        &hex::decode("69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9").unwrap()[..]);
+
+    let msg = vec![0u8; 1000_000];
+    let mut m = Blake2s256::new();
+    for chunk in msg.chunks(1000 - 1) {
+        m.update(chunk);
+    }
+    m.finalize();
+    assert_eq!(&m.output()[..],
+        &hex::decode("cc07784ef067dd3e05f2d0720933ef177846b9719b1e0741c607aca3ff7a38ae").unwrap()[..]);
+}
+
+#[test]
+fn test_blake2s_keyed() {
+    // https://github.com/BLAKE2/BLAKE2/blob/master/testvectors/blake2s-kat.txt
+    let key = (0u8..32).collect::<Vec<u8>>();
+    let msg = (0u8..255).collect::<Vec<u8>>();
+
+    let mut m = Blake2s256::with_key(&key).unwrap();
+    m.update(&msg);
+    m.finalize();
+    assert_eq!(&m.output()[..],
+        &hex::decode("3fb735061abc519dfe979e54c1ee5bfad0a9d858b3315bad34bde999efd724dd").unwrap()[..]);
+}
+
+#[test]
+fn test_blake2s_params() {
+    let mut m = Blake2s::with_params(20, b"secret", b"01234567", b"MyApp!!!").unwrap();
+    m.update(b"hello");
+    let mut output = [0u8; 20];
+    m.finalize(&mut output);
+    assert_eq!(&output[..], &hex::decode("4f256f5e2c0f49c8d2778e238c60ec42582adf2c").unwrap()[..]);
+
+    // NOTE: 超出范围的参数返回错误而不是 panic。
+    assert!(Blake2s::with_params(0, &[], &[], &[]).is_err());
+    assert!(Blake2s::with_params(33, &[], &[], &[]).is_err());
+    assert!(Blake2s::with_key(32, &[0u8; 33]).is_err());
+    assert!(Blake2s::with_params(32, &[], &[0u8; 9], &[]).is_err());
+    assert!(Blake2s::with_params(32, &[], &[], &[0u8; 9]).is_err());
+}
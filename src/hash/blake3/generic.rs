@@ -0,0 +1,11 @@
+use super::{hash_chunk, CHUNK_LEN};
+
+
+// 依次计算多个完整 Chunk 的链值。
+pub fn hash_many(input: &[u8], key: &[u32; 8], counter: u64, flags: u32, out: &mut [[u32; 8]]) {
+    debug_assert_eq!(input.len(), out.len() * CHUNK_LEN);
+
+    for (i, chunk) in input.chunks(CHUNK_LEN).enumerate() {
+        out[i] = hash_chunk(chunk, key, counter + i as u64, flags);
+    }
+}
@@ -0,0 +1,672 @@
+// BLAKE3 — one function, fast everywhere
+// https://github.com/BLAKE3-team/BLAKE3-specs/blob/master/blake3.pdf
+//
+// Reference implementation
+// https://github.com/BLAKE3-team/BLAKE3/blob/master/reference_impl/reference_impl.rs
+//
+// NOTE: 输入被切分成 1024 Bytes 的 Chunk，每个 Chunk 的链值（Chaining Value）相互独立，
+//       最后再按二叉树两两合并。因此多个 Chunk 可以用 SIMD 同时计算（见 `hash_many`），
+//       较大的子树也可以交给多个线程（见 `Blake3::update_parallel`）。
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2",
+))]
+#[path = "./x86.rs"]
+mod platform;
+
+#[cfg(not(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2",
+)))]
+#[path = "./generic.rs"]
+mod platform;
+
+use self::platform::hash_many;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
+
+
+const IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A,
+    0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+// Domain separation flags
+const CHUNK_START: u32         = 1 << 0;
+const CHUNK_END: u32           = 1 << 1;
+const PARENT: u32              = 1 << 2;
+const ROOT: u32                = 1 << 3;
+const KEYED_HASH: u32          = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32  = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+// NOTE: 2^54 * CHUNK_LEN = 2^64 Bytes，树的高度不会超过 54。
+const MAX_DEPTH: usize = 54;
+// NOTE: 不超过这个大小的子树直接用 `hash_many` 计算所有 Chunk，不再继续拆分。
+const MAX_LEAF_CHUNKS: usize = 16;
+// NOTE: 小于这个大小的子树不值得再开一个线程。
+const MIN_PARALLEL_LEN: usize = 128 * CHUNK_LEN;
+
+
+/// BLAKE3
+pub fn blake3<T: AsRef<[u8]>>(data: T) -> [u8; Blake3::DIGEST_LEN] {
+    Blake3::oneshot(data)
+}
+
+
+macro_rules! G {
+    ($v:expr, $a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $y:expr) => {
+        $v[$a] = $v[$a].wrapping_add($v[$b]).wrapping_add($x);
+        $v[$d] = ($v[$d] ^ $v[$a]).rotate_right(16);
+        $v[$c] = $v[$c].wrapping_add($v[$d]);
+        $v[$b] = ($v[$b] ^ $v[$c]).rotate_right(12);
+        $v[$a] = $v[$a].wrapping_add($v[$b]).wrapping_add($y);
+        $v[$d] = ($v[$d] ^ $v[$a]).rotate_right(8);
+        $v[$c] = $v[$c].wrapping_add($v[$d]);
+        $v[$b] = ($v[$b] ^ $v[$c]).rotate_right(7);
+    }
+}
+
+#[inline]
+fn compress(cv: &[u32; 8], block: &[u32; 16], counter: u64, block_len: u32, flags: u32) -> [u32; 16] {
+    let mut v = [
+        cv[0], cv[1], cv[2], cv[3],
+        cv[4], cv[5], cv[6], cv[7],
+        IV[0], IV[1], IV[2], IV[3],
+        counter as u32, (counter >> 32) as u32, block_len, flags,
+    ];
+    let mut m = *block;
+
+    for round in 0..7 {
+        G!(v, 0, 4,  8, 12, m[ 0], m[ 1]);
+        G!(v, 1, 5,  9, 13, m[ 2], m[ 3]);
+        G!(v, 2, 6, 10, 14, m[ 4], m[ 5]);
+        G!(v, 3, 7, 11, 15, m[ 6], m[ 7]);
+        G!(v, 0, 5, 10, 15, m[ 8], m[ 9]);
+        G!(v, 1, 6, 11, 12, m[10], m[11]);
+        G!(v, 2, 7,  8, 13, m[12], m[13]);
+        G!(v, 3, 4,  9, 14, m[14], m[15]);
+
+        if round < 6 {
+            let mut permuted = [0u32; 16];
+            for i in 0..16 {
+                permuted[i] = m[MSG_PERMUTATION[i]];
+            }
+            m = permuted;
+        }
+    }
+
+    for i in 0..8 {
+        v[i] ^= v[i + 8];
+        v[i + 8] ^= cv[i];
+    }
+    v
+}
+
+#[inline]
+fn words_from_le_bytes(block: &[u8]) -> [u32; 16] {
+    debug_assert_eq!(block.len(), BLOCK_LEN);
+
+    let mut m = [0u32; 16];
+    for (w, chunk) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *w = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    m
+}
+
+#[inline]
+fn first_8_words(words: [u32; 16]) -> [u32; 8] {
+    [words[0], words[1], words[2], words[3], words[4], words[5], words[6], words[7]]
+}
+
+// 计算一个完整（且不是根节点）的 Chunk 的链值。
+fn hash_chunk(chunk: &[u8], key: &[u32; 8], counter: u64, flags: u32) -> [u32; 8] {
+    debug_assert_eq!(chunk.len(), CHUNK_LEN);
+
+    let mut cv = *key;
+    for (i, block) in chunk.chunks(BLOCK_LEN).enumerate() {
+        let mut block_flags = flags;
+        if i == 0 {
+            block_flags |= CHUNK_START;
+        }
+        if i == CHUNK_LEN / BLOCK_LEN - 1 {
+            block_flags |= CHUNK_END;
+        }
+        cv = first_8_words(compress(&cv, &words_from_le_bytes(block), counter, BLOCK_LEN as u32, block_flags));
+    }
+    cv
+}
+
+fn parent_output(left: &[u32; 8], right: &[u32; 8], key: &[u32; 8], flags: u32) -> Output {
+    let mut block = [0u32; 16];
+    block[..8].copy_from_slice(left);
+    block[8..].copy_from_slice(right);
+
+    Output { cv: *key, block, counter: 0, block_len: BLOCK_LEN as u32, flags: flags | PARENT }
+}
+
+fn parent_cv(left: &[u32; 8], right: &[u32; 8], key: &[u32; 8], flags: u32) -> [u32; 8] {
+    parent_output(left, right, key, flags).chaining_value()
+}
+
+// 计算一棵完整子树（Chunk 数量为 2 的幂）的链值。
+fn compress_subtree(input: &[u8], key: &[u32; 8], counter: u64, flags: u32, threads: usize) -> [u32; 8] {
+    let chunks = input.len() / CHUNK_LEN;
+    debug_assert!(chunks.is_power_of_two() && input.len().is_multiple_of(CHUNK_LEN));
+
+    if chunks <= MAX_LEAF_CHUNKS {
+        let mut cvs = [[0u32; 8]; MAX_LEAF_CHUNKS];
+        hash_many(input, key, counter, flags, &mut cvs[..chunks]);
+
+        let mut n = chunks;
+        while n > 1 {
+            for i in 0..n / 2 {
+                cvs[i] = parent_cv(&cvs[2 * i], &cvs[2 * i + 1], key, flags);
+            }
+            n /= 2;
+        }
+        return cvs[0];
+    }
+
+    let (left, right) = compress_subtree_children(input, key, counter, flags, threads);
+    parent_cv(&left, &right, key, flags)
+}
+
+// 分别计算一棵完整子树左右两个孩子的链值。
+fn compress_subtree_children(input: &[u8], key: &[u32; 8], counter: u64, flags: u32, threads: usize) -> ([u32; 8], [u32; 8]) {
+    let (left, right) = input.split_at(input.len() / 2);
+    let right_counter = counter + (left.len() / CHUNK_LEN) as u64;
+
+    if threads > 1 && input.len() >= MIN_PARALLEL_LEN {
+        std::thread::scope(|s| {
+            let handle = s.spawn(|| compress_subtree(right, key, right_counter, flags, threads / 2));
+            let left_cv = compress_subtree(left, key, counter, flags, threads - threads / 2);
+            (left_cv, handle.join().unwrap())
+        })
+    } else {
+        (compress_subtree(left, key, counter, flags, 1), compress_subtree(right, key, right_counter, flags, 1))
+    }
+}
+
+
+// 尚未决定是否为根节点的压缩函数输入。
+#[derive(Clone)]
+struct Output {
+    cv: [u32; 8],
+    block: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8_words(compress(&self.cv, &self.block, self.counter, self.block_len, self.flags))
+    }
+
+    // NOTE: 根节点的输出可以无限延长，第 N 个 64 Bytes 输出块使用 N 作为计数器。
+    fn root_output_block(&self, output_block_counter: u64) -> [u8; BLOCK_LEN] {
+        let words = compress(&self.cv, &self.block, output_block_counter, self.block_len, self.flags | ROOT);
+
+        let mut block = [0u8; BLOCK_LEN];
+        for i in 0..16 {
+            block[i*4..i*4 + 4].copy_from_slice(&words[i].to_le_bytes());
+        }
+        block
+    }
+}
+
+
+#[derive(Clone)]
+struct ChunkState {
+    cv: [u32; 8],
+    chunk_counter: u64,
+    buffer: [u8; BLOCK_LEN],
+    buffer_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key: &[u32; 8], chunk_counter: u64, flags: u32) -> Self {
+        Self {
+            cv: *key,
+            chunk_counter,
+            buffer: [0u8; BLOCK_LEN],
+            buffer_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.buffer_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 { CHUNK_START } else { 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+
+        // NOTE: 最后一个块需要带上 CHUNK_END，所以只有在确定后面还有数据时才压缩缓冲区里的块。
+        while !data.is_empty() {
+            if self.buffer_len == BLOCK_LEN {
+                let block = words_from_le_bytes(&self.buffer);
+                let flags = self.flags | self.start_flag();
+                self.cv = first_8_words(compress(&self.cv, &block, self.chunk_counter, BLOCK_LEN as u32, flags));
+                self.blocks_compressed += 1;
+                self.buffer = [0u8; BLOCK_LEN];
+                self.buffer_len = 0;
+            }
+
+            let n = std::cmp::min(BLOCK_LEN - self.buffer_len, data.len());
+            self.buffer[self.buffer_len..self.buffer_len + n].copy_from_slice(&data[..n]);
+            self.buffer_len += n;
+            data = &data[n..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            cv: self.cv,
+            block: words_from_le_bytes(&self.buffer),
+            counter: self.chunk_counter,
+            block_len: self.buffer_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+
+/// BLAKE3（支持 Keyed Hash、Derive Key 以及可扩展输出）
+#[derive(Clone)]
+pub struct Blake3 {
+    key: [u32; 8],
+    chunk_state: ChunkState,
+    cv_stack: [[u32; 8]; MAX_DEPTH],
+    cv_stack_len: usize,
+    flags: u32,
+}
+
+impl Blake3 {
+    pub const BLOCK_LEN: usize  = BLOCK_LEN;
+    pub const CHUNK_LEN: usize  = CHUNK_LEN;
+    pub const KEY_LEN: usize    = 32;
+    pub const DIGEST_LEN: usize = 32;
+
+
+    fn with_key_words(key: [u32; 8], flags: u32) -> Self {
+        Self {
+            key,
+            chunk_state: ChunkState::new(&key, 0, flags),
+            cv_stack: [[0u32; 8]; MAX_DEPTH],
+            cv_stack_len: 0,
+            flags,
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::with_key_words(IV, 0)
+    }
+
+    /// Keyed hashing (MAC)
+    pub fn new_keyed(key: &[u8]) -> Self {
+        assert_eq!(key.len(), Self::KEY_LEN);
+
+        let mut block = [0u8; BLOCK_LEN];
+        block[..Self::KEY_LEN].copy_from_slice(key);
+        Self::with_key_words(first_8_words(words_from_le_bytes(&block)), KEYED_HASH)
+    }
+
+    /// Key derivation
+    ///
+    /// NOTE: `context` 应当是硬编码的、全局唯一的字符串，而不是密钥或者随机数据。
+    pub fn new_derive_key(context: &str) -> Self {
+        let mut m = Self::with_key_words(IV, DERIVE_KEY_CONTEXT);
+        m.update(context.as_bytes());
+        let context_key = m.output();
+
+        let mut block = [0u8; BLOCK_LEN];
+        block[..Self::KEY_LEN].copy_from_slice(&context_key);
+        Self::with_key_words(first_8_words(words_from_le_bytes(&block)), DERIVE_KEY_MATERIAL)
+    }
+
+    fn push_cv(&mut self, cv: &[u32; 8], chunk_counter: u64) {
+        self.merge_cv_stack(chunk_counter);
+        self.cv_stack[self.cv_stack_len] = *cv;
+        self.cv_stack_len += 1;
+    }
+
+    // NOTE: 合并是“延迟”进行的：栈顶的链值可能正是根节点的孩子，
+    //       只有在确定后面还有数据时才能把它们合并成父节点。
+    fn merge_cv_stack(&mut self, total_chunks: u64) {
+        let post_merge_stack_len = total_chunks.count_ones() as usize;
+        while self.cv_stack_len > post_merge_stack_len {
+            let right = self.cv_stack[self.cv_stack_len - 1];
+            let left  = self.cv_stack[self.cv_stack_len - 2];
+            self.cv_stack[self.cv_stack_len - 2] = parent_cv(&left, &right, &self.key, self.flags);
+            self.cv_stack_len -= 1;
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.update_with_threads(data, 1);
+    }
+
+    /// 和 `update` 相同，但会把较大的子树分给多个线程计算，适合一次性写入很大的输入。
+    pub fn update_parallel(&mut self, data: &[u8]) {
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        self.update_with_threads(data, threads);
+    }
+
+    fn update_with_threads(&mut self, data: &[u8], threads: usize) {
+        let mut data = data;
+
+        // 先填满当前的 Chunk。
+        if self.chunk_state.len() > 0 {
+            let n = std::cmp::min(CHUNK_LEN - self.chunk_state.len(), data.len());
+            self.chunk_state.update(&data[..n]);
+            data = &data[n..];
+
+            if data.is_empty() {
+                return;
+            }
+
+            let cv = self.chunk_state.output().chaining_value();
+            let chunk_counter = self.chunk_state.chunk_counter;
+            self.push_cv(&cv, chunk_counter);
+            self.chunk_state = ChunkState::new(&self.key, chunk_counter + 1, self.flags);
+        }
+
+        // NOTE: 每次取出尽可能大的一棵完整子树，子树的大小是 2 的幂，
+        //       并且它的起始位置必须和它的大小对齐。
+        //       至少留下一个 Chunk 给 `chunk_state`，因为最后一个 Chunk 可能就是根节点。
+        while data.len() > CHUNK_LEN {
+            let mut subtree_len = 1usize << (usize::BITS - 1 - data.len().leading_zeros());
+            let count_so_far = self.chunk_state.chunk_counter * CHUNK_LEN as u64;
+            while (subtree_len as u64 - 1) & count_so_far != 0 {
+                subtree_len /= 2;
+            }
+
+            let chunk_counter = self.chunk_state.chunk_counter;
+            let subtree_chunks = (subtree_len / CHUNK_LEN) as u64;
+            if subtree_chunks == 1 {
+                let cv = hash_chunk(&data[..CHUNK_LEN], &self.key, chunk_counter, self.flags);
+                self.push_cv(&cv, chunk_counter);
+            } else {
+                // NOTE: 左右孩子分别入栈，而不是直接入栈它们的父节点，因为这个父节点有可能就是根节点。
+                let (left, right) = compress_subtree_children(&data[..subtree_len], &self.key, chunk_counter, self.flags, threads);
+                self.push_cv(&left, chunk_counter);
+                self.push_cv(&right, chunk_counter + subtree_chunks / 2);
+            }
+
+            self.chunk_state.chunk_counter += subtree_chunks;
+            data = &data[subtree_len..];
+        }
+
+        if !data.is_empty() {
+            self.chunk_state.update(data);
+            self.merge_cv_stack(self.chunk_state.chunk_counter);
+        }
+    }
+
+    fn root_output(&self) -> Output {
+        if self.cv_stack_len == 0 {
+            return self.chunk_state.output();
+        }
+
+        let mut output;
+        let mut n = self.cv_stack_len;
+        if self.chunk_state.len() > 0 {
+            output = self.chunk_state.output();
+        } else {
+            // NOTE: 当前 Chunk 为空时，栈里至少有两个链值。
+            output = parent_output(&self.cv_stack[n - 2], &self.cv_stack[n - 1], &self.key, self.flags);
+            n -= 2;
+        }
+
+        while n > 0 {
+            output = parent_output(&self.cv_stack[n - 1], &output.chaining_value(), &self.key, self.flags);
+            n -= 1;
+        }
+        output
+    }
+
+    // NOTE: BLAKE3 的结束阶段并不修改内部状态，这里只是为了和其它哈希函数保持一致的接口。
+    pub fn finalize(&mut self) { }
+
+    pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+        let block = self.root_output().root_output_block(0);
+
+        let mut output = [0u8; Self::DIGEST_LEN];
+        output.copy_from_slice(&block[..Self::DIGEST_LEN]);
+        output
+    }
+
+    /// 返回可以持续读取任意长度输出的 `Blake3Reader`。
+    pub fn finalize_xof(self) -> Blake3Reader {
+        Blake3Reader { inner: self.root_output(), position: 0 }
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32s(&self.key);
+        w.write_u32(self.flags);
+        w.write_usize(self.cv_stack_len);
+        for cv in self.cv_stack[..self.cv_stack_len].iter() {
+            w.write_u32s(cv);
+        }
+
+        w.write_u32s(&self.chunk_state.cv);
+        w.write_u64(self.chunk_state.chunk_counter);
+        w.write_usize(self.chunk_state.blocks_compressed);
+        w.write_usize(self.chunk_state.buffer_len);
+        w.write_bytes(&self.chunk_state.buffer[..self.chunk_state.buffer_len]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut key = [0u32; 8];
+        r.read_u32s(&mut key)?;
+        let flags = r.read_u32()?;
+
+        let mut m = Self::with_key_words(key, flags);
+        m.cv_stack_len = r.read_usize()?;
+        if m.cv_stack_len > MAX_DEPTH {
+            return Err(InvalidHashState);
+        }
+        for i in 0..m.cv_stack_len {
+            r.read_u32s(&mut m.cv_stack[i])?;
+        }
+
+        r.read_u32s(&mut m.chunk_state.cv)?;
+        m.chunk_state.chunk_counter = r.read_u64()?;
+        m.chunk_state.blocks_compressed = r.read_usize()?;
+        m.chunk_state.buffer_len = r.read_usize()?;
+        if m.chunk_state.blocks_compressed >= CHUNK_LEN / BLOCK_LEN || m.chunk_state.buffer_len > BLOCK_LEN {
+            return Err(InvalidHashState);
+        }
+        // NOTE: 每次写入结束时栈都已经合并完毕，此时栈里的链值个数等于已完成的 Chunk 数量的二进制中 1 的个数。
+        if m.cv_stack_len != m.chunk_state.chunk_counter.count_ones() as usize {
+            return Err(InvalidHashState);
+        }
+        // NOTE: 当前 Chunk 为空时，栈里要么没有链值，要么至少有两个（见 `root_output`）。
+        if m.chunk_state.len() == 0 && m.cv_stack_len == 1 {
+            return Err(InvalidHashState);
+        }
+        let n = m.chunk_state.buffer_len;
+        m.chunk_state.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+
+        Ok(m)
+    }
+
+    pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new();
+        m.update(data.as_ref());
+        m.finalize();
+        m.output()
+    }
+
+    pub fn keyed_hash<T: AsRef<[u8]>>(key: &[u8], data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new_keyed(key);
+        m.update(data.as_ref());
+        m.output()
+    }
+
+    pub fn derive_key<T: AsRef<[u8]>>(context: &str, material: T) -> [u8; Self::KEY_LEN] {
+        let mut m = Self::new_derive_key(context);
+        m.update(material.as_ref());
+        m.output()
+    }
+}
+
+impl Default for Blake3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_hash_state!(Blake3, "BLAKE3");
+
+
+/// BLAKE3 的可扩展输出（XOF），可以分多次读取，也可以跳到任意位置开始读取。
+#[derive(Clone)]
+pub struct Blake3Reader {
+    inner: Output,
+    position: u64,
+}
+
+impl Blake3Reader {
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    pub fn read(&mut self, output: &mut [u8]) {
+        let mut output = output;
+
+        while !output.is_empty() {
+            let block = self.inner.root_output_block(self.position / BLOCK_LEN as u64);
+            let offset = (self.position % BLOCK_LEN as u64) as usize;
+            let n = std::cmp::min(BLOCK_LEN - offset, output.len());
+
+            output[..n].copy_from_slice(&block[offset..offset + n]);
+            self.position += n as u64;
+            output = &mut output[n..];
+        }
+    }
+}
+
+
+#[cfg(test)]
+fn test_input(len: usize) -> Vec<u8> {
+    // NOTE: 官方测试向量使用的输入：0, 1, 2, ..., 250, 0, 1, ...
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn test_blake3() {
+    // https://github.com/BLAKE3-team/BLAKE3/blob/master/test_vectors/test_vectors.json
+    assert_eq!(&blake3("")[..],
+        &hex::decode("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262").unwrap()[..]);
+    assert_eq!(&blake3("abc")[..],
+        &hex::decode("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85").unwrap()[..]);
+
+    let tests: &[(usize, &str)] = &[
+        (     1, "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213"),
+        (  1023, "10108970eeda3eb932baac1428c7a2163b0e924c9a9e25b35bba72b28f70bd11"),
+        (  1024, "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7"),
+        (  1025, "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444"),
+        (  2048, "e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a"),
+        (  2049, "5f4d72f40d7a5f82b15ca2b2e44b1de3c2ef86c426c95c1af0b6879522563030"),
+        (  3072, "b98cb0ff3623be03326b373de6b9095218513e64f1ee2edd2525c7ad1e5cffd2"),
+        (  3073, "7124b49501012f81cc7f11ca069ec9226cecb8a2c850cfe644e327d22d3e1cd3"),
+        (  4096, "015094013f57a5277b59d8475c0501042c0b642e531b0a1c8f58d2163229e969"),
+        (  5121, "628bd2cb2004694adaab7bbd778a25df25c47b9d4155a55f8fbd79f2fe154cff"),
+        (  8193, "bab6c09cb8ce8cf459261398d2e7aef35700bf488116ceb94a36d0f5f1b7bc3b"),
+        ( 31744, "62b6960e1a44bcc1eb1a611a8d6235b6b4b78f32e7abc4fb4c6cdcce94895c47"),
+        (102400, "bc3e3d41a1146b069abffad3c0d44860cf664390afce4d9661f7902e7943e085"),
+    ];
+    for &(len, digest) in tests.iter() {
+        let input = test_input(len);
+        let digest = hex::decode(digest).unwrap();
+        assert_eq!(&blake3(&input)[..], &digest[..]);
+
+        // NOTE: 分段写入（包括不与 Chunk 对齐的分段）的结果应当和一次性写入相同。
+        for &step in [1usize, 63, 1000, 1024, 4097].iter() {
+            let mut m = Blake3::new();
+            for chunk in input.chunks(step) {
+                m.update(chunk);
+            }
+            assert_eq!(&m.output()[..], &digest[..]);
+        }
+    }
+}
+
+#[test]
+fn test_blake3_parallel() {
+    let input = test_input(1 << 20 | 1);
+
+    let mut m = Blake3::new();
+    m.update(&input[..77]);
+    m.update_parallel(&input[77..]);
+    assert_eq!(&m.output()[..], &blake3(&input)[..]);
+}
+
+#[test]
+fn test_blake3_hash_many() {
+    let input = test_input(7 * CHUNK_LEN);
+    let key = [1u32, 2, 3, 4, 5, 6, 7, 8];
+
+    let mut cvs = [[0u32; 8]; 7];
+    hash_many(&input, &key, 1 << 32, KEYED_HASH, &mut cvs);
+    for (i, chunk) in input.chunks(CHUNK_LEN).enumerate() {
+        assert_eq!(cvs[i], hash_chunk(chunk, &key, (1 << 32) + i as u64, KEYED_HASH));
+    }
+}
+
+#[test]
+fn test_blake3_keyed_hash() {
+    let key = (0u8..32).collect::<Vec<u8>>();
+    assert_eq!(&Blake3::keyed_hash(&key, test_input(2049))[..],
+        &hex::decode("5442eec85e3fd173dcff07c39cd8cff9689f17224471e655618ed728cf03b056").unwrap()[..]);
+}
+
+#[test]
+fn test_blake3_derive_key() {
+    let context = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+    assert_eq!(&Blake3::derive_key(context, "")[..],
+        &hex::decode("2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d").unwrap()[..]);
+    assert_eq!(&Blake3::derive_key(context, test_input(1025))[..],
+        &hex::decode("effaa245f065fbf82ac186839a249707c3bddf6d3fdda22d1b95a3c970379bcb").unwrap()[..]);
+}
+
+#[test]
+fn test_blake3_xof() {
+    let mut m = Blake3::new();
+    m.update(&test_input(3073));
+    let mut reader = m.finalize_xof();
+
+    let mut output = [0u8; 131];
+    for chunk in output.chunks_mut(7) {
+        reader.read(chunk);
+    }
+    assert_eq!(&output[..32],
+        &hex::decode("7124b49501012f81cc7f11ca069ec9226cecb8a2c850cfe644e327d22d3e1cd3").unwrap()[..]);
+    assert_eq!(&output[64..],
+        &hex::decode("f35b639b7fccbb985b6f2fa56aea0c18f531203497b8bbd3a07ceb5926f1cab7\
+4d14bd66486d9a91eba99059a98bd1cd25876b2af5a76c3e9eed554ed72ea952b603bf").unwrap()[..]);
+
+    // NOTE: 跳到任意位置读取。
+    let mut tail = [0u8; 67];
+    reader.set_position(64);
+    reader.read(&mut tail);
+    assert_eq!(&tail[..], &output[64..]);
+}
@@ -0,0 +1,155 @@
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::{hash_chunk, IV, MSG_PERMUTATION, BLOCK_LEN, CHUNK_LEN, CHUNK_START, CHUNK_END};
+
+
+// NOTE: SSE2 版本一次计算 4 个 Chunk，每个 128 位寄存器的 4 个 32 位通道分别对应一个 Chunk。
+const DEGREE: usize = 4;
+
+
+#[inline]
+unsafe fn rot16(x: __m128i) -> __m128i {
+    _mm_shufflehi_epi16(_mm_shufflelo_epi16(x, 0xB1), 0xB1)
+}
+
+#[inline]
+unsafe fn rot12(x: __m128i) -> __m128i {
+    _mm_or_si128(_mm_srli_epi32(x, 12), _mm_slli_epi32(x, 20))
+}
+
+#[inline]
+unsafe fn rot8(x: __m128i) -> __m128i {
+    _mm_or_si128(_mm_srli_epi32(x, 8), _mm_slli_epi32(x, 24))
+}
+
+#[inline]
+unsafe fn rot7(x: __m128i) -> __m128i {
+    _mm_or_si128(_mm_srli_epi32(x, 7), _mm_slli_epi32(x, 25))
+}
+
+macro_rules! G {
+    ($v:expr, $a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $y:expr) => {
+        $v[$a] = _mm_add_epi32(_mm_add_epi32($v[$a], $v[$b]), $x);
+        $v[$d] = rot16(_mm_xor_si128($v[$d], $v[$a]));
+        $v[$c] = _mm_add_epi32($v[$c], $v[$d]);
+        $v[$b] = rot12(_mm_xor_si128($v[$b], $v[$c]));
+        $v[$a] = _mm_add_epi32(_mm_add_epi32($v[$a], $v[$b]), $y);
+        $v[$d] = rot8(_mm_xor_si128($v[$d], $v[$a]));
+        $v[$c] = _mm_add_epi32($v[$c], $v[$d]);
+        $v[$b] = rot7(_mm_xor_si128($v[$b], $v[$c]));
+    }
+}
+
+// 4x4 转置：输入为 4 行，输出为 4 列。
+#[inline]
+unsafe fn transpose(rows: &mut [__m128i]) {
+    let t0 = _mm_unpacklo_epi32(rows[0], rows[1]);
+    let t1 = _mm_unpacklo_epi32(rows[2], rows[3]);
+    let t2 = _mm_unpackhi_epi32(rows[0], rows[1]);
+    let t3 = _mm_unpackhi_epi32(rows[2], rows[3]);
+
+    rows[0] = _mm_unpacklo_epi64(t0, t1);
+    rows[1] = _mm_unpackhi_epi64(t0, t1);
+    rows[2] = _mm_unpacklo_epi64(t2, t3);
+    rows[3] = _mm_unpackhi_epi64(t2, t3);
+}
+
+#[inline]
+unsafe fn hash4(input: &[u8], key: &[u32; 8], counter: u64, flags: u32, out: &mut [[u32; 8]]) {
+    debug_assert_eq!(input.len(), DEGREE * CHUNK_LEN);
+    debug_assert_eq!(out.len(), DEGREE);
+
+    let mut h = [_mm_setzero_si128(); 8];
+    for i in 0..8 {
+        h[i] = _mm_set1_epi32(key[i] as i32);
+    }
+
+    let counter_lo = _mm_setr_epi32(
+        counter as i32, (counter + 1) as i32,
+        (counter + 2) as i32, (counter + 3) as i32,
+    );
+    let counter_hi = _mm_setr_epi32(
+        (counter >> 32) as i32, ((counter + 1) >> 32) as i32,
+        ((counter + 2) >> 32) as i32, ((counter + 3) >> 32) as i32,
+    );
+
+    for block in 0..CHUNK_LEN / BLOCK_LEN {
+        let mut block_flags = flags;
+        if block == 0 {
+            block_flags |= CHUNK_START;
+        }
+        if block == CHUNK_LEN / BLOCK_LEN - 1 {
+            block_flags |= CHUNK_END;
+        }
+
+        // 载入 4 个 Chunk 中相同位置的消息块，并转置成按字排列。
+        let mut m = [_mm_setzero_si128(); 16];
+        for j in 0..4 {
+            for k in 0..DEGREE {
+                let offset = k * CHUNK_LEN + block * BLOCK_LEN + j * 16;
+                m[j * 4 + k] = _mm_loadu_si128(input[offset..offset + 16].as_ptr() as *const __m128i);
+            }
+            transpose(&mut m[j * 4..j * 4 + 4]);
+        }
+
+        let mut v = [
+            h[0], h[1], h[2], h[3],
+            h[4], h[5], h[6], h[7],
+            _mm_set1_epi32(IV[0] as i32), _mm_set1_epi32(IV[1] as i32),
+            _mm_set1_epi32(IV[2] as i32), _mm_set1_epi32(IV[3] as i32),
+            counter_lo, counter_hi,
+            _mm_set1_epi32(BLOCK_LEN as i32), _mm_set1_epi32(block_flags as i32),
+        ];
+
+        for round in 0..7 {
+            G!(v, 0, 4,  8, 12, m[ 0], m[ 1]);
+            G!(v, 1, 5,  9, 13, m[ 2], m[ 3]);
+            G!(v, 2, 6, 10, 14, m[ 4], m[ 5]);
+            G!(v, 3, 7, 11, 15, m[ 6], m[ 7]);
+            G!(v, 0, 5, 10, 15, m[ 8], m[ 9]);
+            G!(v, 1, 6, 11, 12, m[10], m[11]);
+            G!(v, 2, 7,  8, 13, m[12], m[13]);
+            G!(v, 3, 4,  9, 14, m[14], m[15]);
+
+            if round < 6 {
+                let mut permuted = [_mm_setzero_si128(); 16];
+                for i in 0..16 {
+                    permuted[i] = m[MSG_PERMUTATION[i]];
+                }
+                m = permuted;
+            }
+        }
+
+        for i in 0..8 {
+            h[i] = _mm_xor_si128(v[i], v[i + 8]);
+        }
+    }
+
+    // 转置回每个 Chunk 的链值。
+    transpose(&mut h[..4]);
+    transpose(&mut h[4..]);
+    for k in 0..DEGREE {
+        _mm_storeu_si128(out[k][..4].as_mut_ptr() as *mut __m128i, h[k]);
+        _mm_storeu_si128(out[k][4..].as_mut_ptr() as *mut __m128i, h[k + 4]);
+    }
+}
+
+// 计算多个完整 Chunk 的链值，每 4 个一组并行计算，剩余的逐个计算。
+pub fn hash_many(input: &[u8], key: &[u32; 8], counter: u64, flags: u32, out: &mut [[u32; 8]]) {
+    debug_assert_eq!(input.len(), out.len() * CHUNK_LEN);
+
+    let mut i = 0usize;
+    while out.len() - i >= DEGREE {
+        unsafe {
+            hash4(&input[i * CHUNK_LEN..(i + DEGREE) * CHUNK_LEN], key, counter + i as u64, flags, &mut out[i..i + DEGREE]);
+        }
+        i += DEGREE;
+    }
+
+    for j in i..out.len() {
+        out[j] = hash_chunk(&input[j * CHUNK_LEN..(j + 1) * CHUNK_LEN], key, counter + j as u64, flags);
+    }
+}
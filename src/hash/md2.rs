@@ -1,5 +1,7 @@
 // The MD2 Message-Digest Algorithm
 // https://tools.ietf.org/html/rfc1319
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
 
 
 // The S-table's values are derived from Pi
@@ -123,6 +125,23 @@ impl Md2 {
         output
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_bytes(&self.state);
+        w.write_usize(self.offset);
+        w.write_bytes(&self.buffer[..self.offset]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        m.state.copy_from_slice(r.read_bytes(64)?);
+        m.offset = r.read_usize()?;
+        if m.offset >= Self::BLOCK_LEN {
+            return Err(InvalidHashState);
+        }
+        m.buffer[..m.offset].copy_from_slice(r.read_bytes(m.offset)?);
+        Ok(m)
+    }
+
     pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
         let mut m = Self::new();
         m.update(data.as_ref());
@@ -131,6 +150,8 @@ impl Md2 {
     }
 }
 
+impl_hash_state!(Md2, "MD2");
+
 
 #[test]
 fn test_md2() {
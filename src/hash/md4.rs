@@ -2,6 +2,8 @@
 // https://tools.ietf.org/html/rfc1320
 // 
 use std::convert::TryFrom;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
 
 
 const INITIAL_STATE: [u32; 4] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476];
@@ -60,6 +62,7 @@ impl Md4 {
                 if data.len() > 0 {
                     return self.update(data);
                 }
+                return ();
             }
         }
 
@@ -125,6 +128,21 @@ impl Md4 {
         output
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32s(&self.state);
+        w.write_usize(self.len);
+        w.write_bytes(&self.buffer[..self.len % Self::BLOCK_LEN]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        r.read_u32s(&mut m.state)?;
+        m.len = r.read_usize()?;
+        let n = m.len % Self::BLOCK_LEN;
+        m.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+        Ok(m)
+    }
+
     pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
         let mut m = Self::new();
         m.update(data.as_ref());
@@ -133,6 +151,8 @@ impl Md4 {
     }
 }
 
+impl_hash_state!(Md4, "MD4");
+
 
 macro_rules! F {
     ($x:expr, $y:expr, $z:expr) => (
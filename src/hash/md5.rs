@@ -7,6 +7,8 @@
 // ‼️ MD5算法在2004年被证实无法防止碰撞攻击，因此不适用于安全性认证。
 // 
 use std::convert::TryFrom;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
 
 // Use binary integer part of the sines of integers (Radians) as constants:
 //    for i from 0 to 63 do
@@ -117,6 +119,7 @@ impl Md5 {
                 if data.len() > 0 {
                     return self.update(data);
                 }
+                return ();
             }
         }
 
@@ -181,6 +184,21 @@ impl Md5 {
         output
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32s(&self.state);
+        w.write_usize(self.len);
+        w.write_bytes(&self.buffer[..self.len % Self::BLOCK_LEN]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        r.read_u32s(&mut m.state)?;
+        m.len = r.read_usize()?;
+        let n = m.len % Self::BLOCK_LEN;
+        m.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+        Ok(m)
+    }
+
     pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
         let mut m = Self::new();
         m.update(data.as_ref());
@@ -189,6 +207,8 @@ impl Md5 {
     }
 }
 
+impl_hash_state!(Md5, "MD5");
+
 
 macro_rules! F {
     ($b:expr, $c:expr, $d:expr) => (
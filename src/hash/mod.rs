@@ -1,11 +1,18 @@
+#[macro_use]
+mod state;
+
 mod md2;
 mod md4;
 mod md5;
 mod sm3;
 mod sha1;
 mod sha2;
-// TODO: 暂未实现
 mod sha3;
+mod blake2;
+mod blake3;
+mod ripemd;
+mod streebog;
+mod whirlpool;
 
 pub use self::md2::*;
 pub use self::md4::*;
@@ -14,6 +21,13 @@ pub use self::sm3::*;
 pub use self::sha1::*;
 pub use self::sha2::*;
 pub use self::sha3::*;
+pub use self::blake2::*;
+pub use self::blake3::*;
+pub use self::ripemd::*;
+pub use self::streebog::*;
+pub use self::whirlpool::*;
+
+use crate::error::InvalidHashState;
 
 
 // NOTE: 等待 std::array::FixedSizeArray 稳定后，即可替换。
@@ -52,6 +66,7 @@ array_impls! {
 // TODO: multihash
 // https://github.com/multiformats/multicodec/blob/master/table.csv
 
+#[allow(non_camel_case_types)]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum CryptoHashKind {
     MD2,
@@ -59,9 +74,25 @@ pub enum CryptoHashKind {
     MD5,
     SM3,
     SHA1,
+    SHA2_224,
     SHA2_256,
     SHA2_384,
     SHA2_512,
+    SHA2_512_224,
+    SHA2_512_256,
+    SHA3_224,
+    SHA3_256,
+    SHA3_384,
+    SHA3_512,
+    BLAKE2B_512,
+    BLAKE2S_256,
+    BLAKE3,
+    RIPEMD160,
+    RIPEMD256,
+    RIPEMD320,
+    STREEBOG_256,
+    STREEBOG_512,
+    WHIRLPOOL,
 }
 
 pub trait CryptoHasher {
@@ -93,6 +124,18 @@ pub trait CryptoHasher {
     fn oneshot<T: AsRef<[u8]>>(data: T) -> Self::Output;
 }
 
+/// 可以导出和恢复中间状态的 Hasher
+///
+/// NOTE: 与 `CryptoHasher` 分开，实现自定义的 Hasher 时不需要支持状态的序列化。
+pub trait ResumableHasher: CryptoHasher {
+    /// 导出尚未结束的完整中间状态，可用于断点续算或者预计算 HMAC 的 ipad/opad 状态。
+    fn export_state(&self) -> Vec<u8>;
+
+    fn import_state(state: &[u8]) -> Result<Self, InvalidHashState>
+    where
+        Self: Sized;
+}
+
 
 pub trait BuildCryptoHasher {
     type Hasher: CryptoHasher;
@@ -149,6 +192,16 @@ macro_rules! impl_crypto_hasher {
                 Self::oneshot(data)
             }
         }
+
+        impl ResumableHasher for $name {
+            fn export_state(&self) -> Vec<u8> {
+                self.export_state()
+            }
+
+            fn import_state(state: &[u8]) -> Result<Self, InvalidHashState> {
+                Self::import_state(state)
+            }
+        }
     }
 }
 
@@ -176,15 +229,94 @@ impl_build_crypto_hasher!(Sm3);
 impl_build_crypto_hasher!(Sha1);
 
 // SHA-2
+impl_crypto_hasher!(Sha224);
 impl_crypto_hasher!(Sha256);
 impl_crypto_hasher!(Sha384);
 impl_crypto_hasher!(Sha512);
+impl_crypto_hasher!(Sha512_224);
+impl_crypto_hasher!(Sha512_256);
+impl_build_crypto_hasher!(Sha224);
 impl_build_crypto_hasher!(Sha256);
 impl_build_crypto_hasher!(Sha384);
 impl_build_crypto_hasher!(Sha512);
+impl_build_crypto_hasher!(Sha512_224);
+impl_build_crypto_hasher!(Sha512_256);
 
 // SHA-3
+impl_crypto_hasher!(Sha3_224);
+impl_crypto_hasher!(Sha3_256);
+impl_crypto_hasher!(Sha3_384);
+impl_crypto_hasher!(Sha3_512);
+impl_build_crypto_hasher!(Sha3_224);
+impl_build_crypto_hasher!(Sha3_256);
+impl_build_crypto_hasher!(Sha3_384);
+impl_build_crypto_hasher!(Sha3_512);
+
+// BLAKE2
+impl_crypto_hasher!(Blake2b512);
+impl_crypto_hasher!(Blake2s256);
+impl_build_crypto_hasher!(Blake2b512);
+impl_build_crypto_hasher!(Blake2s256);
+
+// BLAKE3
+impl_crypto_hasher!(Blake3);
+impl_build_crypto_hasher!(Blake3);
+
+// RIPEMD
+impl_crypto_hasher!(Ripemd160);
+impl_crypto_hasher!(Ripemd256);
+impl_crypto_hasher!(Ripemd320);
+impl_build_crypto_hasher!(Ripemd160);
+impl_build_crypto_hasher!(Ripemd256);
+impl_build_crypto_hasher!(Ripemd320);
+
+// GOST R 34.11-2012
+impl_crypto_hasher!(Streebog256);
+impl_crypto_hasher!(Streebog512);
+impl_build_crypto_hasher!(Streebog256);
+impl_build_crypto_hasher!(Streebog512);
+
+// Whirlpool
+impl_crypto_hasher!(Whirlpool);
+impl_build_crypto_hasher!(Whirlpool);
+
 
+#[cfg(test)]
+fn check_update_split<H: BuildCryptoHasher<Hasher = H> + CryptoHasher>() {
+    let data = (0..600usize).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let expected = H::oneshot(&data);
+
+    // NOTE: 包括恰好填满缓冲区中剩余部分的写入。
+    for &n in [1usize, 7, 32, 63, 64, 65, 100, 128, 200].iter() {
+        let mut m = H::build_hasher();
+        for chunk in data.chunks(n) {
+            m.write(chunk);
+        }
+        assert_eq!(m.digest().array_as_slice(), expected.array_as_slice());
+    }
+}
+
+#[test]
+fn test_update_split() {
+    check_update_split::<Md2>();
+    check_update_split::<Md4>();
+    check_update_split::<Md5>();
+    check_update_split::<Sm3>();
+    check_update_split::<Sha1>();
+    check_update_split::<Sha224>();
+    check_update_split::<Sha256>();
+    check_update_split::<Sha384>();
+    check_update_split::<Sha512>();
+    check_update_split::<Sha512_224>();
+    check_update_split::<Sha512_256>();
+    check_update_split::<Sha3_256>();
+    check_update_split::<Blake2b512>();
+    check_update_split::<Blake2s256>();
+    check_update_split::<Blake3>();
+    check_update_split::<Ripemd160>();
+    check_update_split::<Streebog512>();
+    check_update_split::<Whirlpool>();
+}
 
 
 #[cfg(test)]
@@ -268,3 +400,93 @@ fn bench_sha512(b: &mut test::Bencher) {
         sha512(&data)
     });
 }
+
+#[cfg(test)]
+#[bench]
+fn bench_sha512_256(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        sha512_256(&data)
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_sha3_256(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        sha3_256(&data)
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_blake2b_512(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        blake2b_512(&data)
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_blake2s_256(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        blake2s_256(&data)
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_blake3(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        blake3(&data)
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_blake3_16k(b: &mut test::Bencher) {
+    let data = [1u8; 16 * 1024];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        blake3(&data[..])
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_ripemd160(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        ripemd160(&data)
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_streebog512(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        streebog512(&data)
+    });
+}
+
+#[cfg(test)]
+#[bench]
+fn bench_whirlpool(b: &mut test::Bencher) {
+    let data = [1u8; 64];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        whirlpool(&data)
+    });
+}
@@ -0,0 +1,363 @@
+// RIPEMD-160: A Strengthened Version of RIPEMD
+// https://homes.esat.kuleuven.be/~bosselae/ripemd160/pdf/AB-9601/AB-9601.pdf
+//
+// The hash function RIPEMD-160
+// https://homes.esat.kuleuven.be/~bosselae/ripemd160.html
+//
+// NOTE: RIPEMD-256 和 RIPEMD-320 只是分别把 RIPEMD-128 和 RIPEMD-160 的两条并行线路
+//       的结果不再合并，输出长度加倍，但安全强度并没有提高。
+use std::convert::TryFrom;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
+
+
+// Selection of message word
+const R1: [usize; 80] = [
+     0,  1,  2,  3,  4,  5,  6,  7,  8,  9, 10, 11, 12, 13, 14, 15,
+     7,  4, 13,  1, 10,  6, 15,  3, 12,  0,  9,  5,  2, 14, 11,  8,
+     3, 10, 14,  4,  9, 15,  8,  1,  2,  7,  0,  6, 13, 11,  5, 12,
+     1,  9, 11, 10,  0,  8, 12,  4, 13,  3,  7, 15, 14,  5,  6,  2,
+     4,  0,  5,  9,  7, 12,  2, 10, 14,  1,  3,  8, 11,  6, 15, 13,
+];
+const R2: [usize; 80] = [
+     5, 14,  7,  0,  9,  2, 11,  4, 13,  6, 15,  8,  1, 10,  3, 12,
+     6, 11,  3,  7,  0, 13,  5, 10, 14, 15,  8, 12,  4,  9,  1,  2,
+    15,  5,  1,  3,  7, 14,  6,  9, 11,  8, 12,  2, 10,  0,  4, 13,
+     8,  6,  4,  1,  3, 11, 15,  0,  5, 12,  2, 13,  9,  7, 10, 14,
+    12, 15, 10,  4,  1,  5,  8,  7,  6,  2, 13, 14,  0,  3,  9, 11,
+];
+
+// Amount for rotate left (rol)
+const S1: [u32; 80] = [
+    11, 14, 15, 12,  5,  8,  7,  9, 11, 13, 14, 15,  6,  7,  9,  8,
+     7,  6,  8, 13, 11,  9,  7, 15,  7, 12, 15,  9, 11,  7, 13, 12,
+    11, 13,  6,  7, 14,  9, 13, 15, 14,  8, 13,  6,  5, 12,  7,  5,
+    11, 12, 14, 15, 14, 15,  9,  8,  9, 14,  5,  6,  8,  6,  5, 12,
+     9, 15,  5, 11,  6,  8, 13, 12,  5, 12, 13, 14, 11,  8,  5,  6,
+];
+const S2: [u32; 80] = [
+     8,  9,  9, 11, 13, 15, 15,  5,  7,  7,  8, 11, 14, 14, 12,  6,
+     9, 13, 15,  7, 12,  8,  9, 11,  7,  7, 12,  7,  6, 15, 13, 11,
+     9,  7, 15, 11,  8,  6,  6, 14, 12, 13,  5, 14, 13, 13,  7,  5,
+    15,  5,  8, 11, 14, 14,  6, 14,  6,  9, 12,  9, 12,  5, 15,  8,
+     8,  5, 12,  9, 12,  5, 14,  6,  8, 13,  6,  5, 15, 13, 11, 11,
+];
+
+// Added constants
+const K1_160: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const K2_160: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+const K1_128: [u32; 4] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc];
+const K2_128: [u32; 4] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x00000000];
+
+const RIPEMD160_INITIAL_STATE: [u32; 5] = [
+    0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0,
+];
+const RIPEMD256_INITIAL_STATE: [u32; 8] = [
+    0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476,
+    0x76543210, 0xfedcba98, 0x89abcdef, 0x01234567,
+];
+const RIPEMD320_INITIAL_STATE: [u32; 10] = [
+    0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0,
+    0x76543210, 0xfedcba98, 0x89abcdef, 0x01234567, 0x3c2d1e0f,
+];
+
+
+// Nonlinear functions at bit level
+#[inline]
+fn f(j: usize, x: u32, y: u32, z: u32) -> u32 {
+    match j / 16 {
+        0 => x ^ y ^ z,
+        1 => (x & y) | (!x & z),
+        2 => (x | !y) ^ z,
+        3 => (x & z) | (y & !z),
+        4 => x ^ (y | !z),
+        _ => unreachable!(),
+    }
+}
+
+#[inline]
+fn load_block(block: &[u8]) -> [u32; 16] {
+    let mut x = [0u32; 16];
+    for (w, chunk) in x.iter_mut().zip(block.chunks_exact(4)) {
+        *w = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    x
+}
+
+#[inline]
+fn transform160(state: &mut [u32; 5], block: &[u8]) {
+    let x = load_block(block);
+
+    let (mut a1, mut b1, mut c1, mut d1, mut e1) = (state[0], state[1], state[2], state[3], state[4]);
+    let (mut a2, mut b2, mut c2, mut d2, mut e2) = (state[0], state[1], state[2], state[3], state[4]);
+
+    for j in 0..80 {
+        let t = a1.wrapping_add(f(j, b1, c1, d1)).wrapping_add(x[R1[j]]).wrapping_add(K1_160[j / 16])
+            .rotate_left(S1[j]).wrapping_add(e1);
+        a1 = e1; e1 = d1; d1 = c1.rotate_left(10); c1 = b1; b1 = t;
+
+        let t = a2.wrapping_add(f(79 - j, b2, c2, d2)).wrapping_add(x[R2[j]]).wrapping_add(K2_160[j / 16])
+            .rotate_left(S2[j]).wrapping_add(e2);
+        a2 = e2; e2 = d2; d2 = c2.rotate_left(10); c2 = b2; b2 = t;
+    }
+
+    let t    = state[1].wrapping_add(c1).wrapping_add(d2);
+    state[1] = state[2].wrapping_add(d1).wrapping_add(e2);
+    state[2] = state[3].wrapping_add(e1).wrapping_add(a2);
+    state[3] = state[4].wrapping_add(a1).wrapping_add(b2);
+    state[4] = state[0].wrapping_add(b1).wrapping_add(c2);
+    state[0] = t;
+}
+
+#[inline]
+fn transform256(state: &mut [u32; 8], block: &[u8]) {
+    let x = load_block(block);
+
+    let (mut a1, mut b1, mut c1, mut d1) = (state[0], state[1], state[2], state[3]);
+    let (mut a2, mut b2, mut c2, mut d2) = (state[4], state[5], state[6], state[7]);
+
+    for j in 0..64 {
+        let t = a1.wrapping_add(f(j, b1, c1, d1)).wrapping_add(x[R1[j]]).wrapping_add(K1_128[j / 16])
+            .rotate_left(S1[j]);
+        a1 = d1; d1 = c1; c1 = b1; b1 = t;
+
+        let t = a2.wrapping_add(f(63 - j, b2, c2, d2)).wrapping_add(x[R2[j]]).wrapping_add(K2_128[j / 16])
+            .rotate_left(S2[j]);
+        a2 = d2; d2 = c2; c2 = b2; b2 = t;
+
+        // NOTE: 每一轮结束后，交换两条线路中的一个字。
+        match j {
+            15 => std::mem::swap(&mut a1, &mut a2),
+            31 => std::mem::swap(&mut b1, &mut b2),
+            47 => std::mem::swap(&mut c1, &mut c2),
+            63 => std::mem::swap(&mut d1, &mut d2),
+            _ => { },
+        }
+    }
+
+    state[0] = state[0].wrapping_add(a1);
+    state[1] = state[1].wrapping_add(b1);
+    state[2] = state[2].wrapping_add(c1);
+    state[3] = state[3].wrapping_add(d1);
+    state[4] = state[4].wrapping_add(a2);
+    state[5] = state[5].wrapping_add(b2);
+    state[6] = state[6].wrapping_add(c2);
+    state[7] = state[7].wrapping_add(d2);
+}
+
+#[inline]
+fn transform320(state: &mut [u32; 10], block: &[u8]) {
+    let x = load_block(block);
+
+    let (mut a1, mut b1, mut c1, mut d1, mut e1) = (state[0], state[1], state[2], state[3], state[4]);
+    let (mut a2, mut b2, mut c2, mut d2, mut e2) = (state[5], state[6], state[7], state[8], state[9]);
+
+    for j in 0..80 {
+        let t = a1.wrapping_add(f(j, b1, c1, d1)).wrapping_add(x[R1[j]]).wrapping_add(K1_160[j / 16])
+            .rotate_left(S1[j]).wrapping_add(e1);
+        a1 = e1; e1 = d1; d1 = c1.rotate_left(10); c1 = b1; b1 = t;
+
+        let t = a2.wrapping_add(f(79 - j, b2, c2, d2)).wrapping_add(x[R2[j]]).wrapping_add(K2_160[j / 16])
+            .rotate_left(S2[j]).wrapping_add(e2);
+        a2 = e2; e2 = d2; d2 = c2.rotate_left(10); c2 = b2; b2 = t;
+
+        match j {
+            15 => std::mem::swap(&mut b1, &mut b2),
+            31 => std::mem::swap(&mut d1, &mut d2),
+            47 => std::mem::swap(&mut a1, &mut a2),
+            63 => std::mem::swap(&mut c1, &mut c2),
+            79 => std::mem::swap(&mut e1, &mut e2),
+            _ => { },
+        }
+    }
+
+    state[0] = state[0].wrapping_add(a1);
+    state[1] = state[1].wrapping_add(b1);
+    state[2] = state[2].wrapping_add(c1);
+    state[3] = state[3].wrapping_add(d1);
+    state[4] = state[4].wrapping_add(e1);
+    state[5] = state[5].wrapping_add(a2);
+    state[6] = state[6].wrapping_add(b2);
+    state[7] = state[7].wrapping_add(c2);
+    state[8] = state[8].wrapping_add(d2);
+    state[9] = state[9].wrapping_add(e2);
+}
+
+
+macro_rules! impl_ripemd {
+    ($name:tt, $fname:tt, $words:tt, $init:tt, $transform:tt, $doc:expr) => {
+        #[doc = $doc]
+        pub fn $fname<T: AsRef<[u8]>>(data: T) -> [u8; $name::DIGEST_LEN] {
+            $name::oneshot(data)
+        }
+
+        #[doc = $doc]
+        #[derive(Clone)]
+        pub struct $name {
+            buffer: [u8; 64],
+            state: [u32; $words],
+            len: usize,      // in bytes.
+        }
+
+        impl $name {
+            pub const BLOCK_LEN: usize  = 64;
+            pub const DIGEST_LEN: usize = $words * 4;
+
+            pub fn new() -> Self {
+                Self {
+                    buffer: [0u8; 64],
+                    state: $init,
+                    len: 0,
+                }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                let mut data = data;
+
+                let n = self.len % Self::BLOCK_LEN;
+                if n != 0 {
+                    let amt = std::cmp::min(Self::BLOCK_LEN - n, data.len());
+                    self.buffer[n..n + amt].copy_from_slice(&data[..amt]);
+                    self.len += amt;
+                    data = &data[amt..];
+
+                    if self.len % Self::BLOCK_LEN != 0 {
+                        return ();
+                    }
+                    $transform(&mut self.state, &self.buffer);
+                }
+
+                while data.len() >= Self::BLOCK_LEN {
+                    $transform(&mut self.state, &data[..Self::BLOCK_LEN]);
+                    self.len += Self::BLOCK_LEN;
+                    data = &data[Self::BLOCK_LEN..];
+                }
+
+                if data.len() > 0 {
+                    self.buffer[..data.len()].copy_from_slice(data);
+                    self.len += data.len();
+                }
+            }
+
+            pub fn finalize(&mut self) {
+                // NOTE: 和 MD4/MD5 一样，消息长度以小端序填充。
+                let len_bits = u64::try_from(self.len).unwrap() * 8;
+                let n = self.len % Self::BLOCK_LEN;
+
+                self.buffer[n] = 0x80;
+                for i in n+1..64 {
+                    self.buffer[i] = 0;
+                }
+                if 64 - n - 1 < 8 {
+                    $transform(&mut self.state, &self.buffer);
+                    self.buffer = [0u8; 64];
+                }
+                self.buffer[56..].copy_from_slice(&len_bits.to_le_bytes());
+                $transform(&mut self.state, &self.buffer);
+            }
+
+            pub fn state(&self) -> &[u32; $words] {
+                &self.state
+            }
+
+            pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+                let mut output = [0u8; Self::DIGEST_LEN];
+                for i in 0..$words {
+                    output[i*4..i*4 + 4].copy_from_slice(&self.state[i].to_le_bytes());
+                }
+                output
+            }
+
+            fn save_state(&self, w: &mut StateWriter) {
+                w.write_u32s(&self.state);
+                w.write_usize(self.len);
+                w.write_bytes(&self.buffer[..self.len % Self::BLOCK_LEN]);
+            }
+
+            fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+                let mut m = Self::new();
+                r.read_u32s(&mut m.state)?;
+                m.len = r.read_usize()?;
+                let n = m.len % Self::BLOCK_LEN;
+                m.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+                Ok(m)
+            }
+
+            pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+                let mut m = Self::new();
+                m.update(data.as_ref());
+                m.finalize();
+                m.output()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl_hash_state!($name, $doc);
+    }
+}
+
+impl_ripemd!(Ripemd160, ripemd160,  5, RIPEMD160_INITIAL_STATE, transform160, "RIPEMD-160");
+impl_ripemd!(Ripemd256, ripemd256,  8, RIPEMD256_INITIAL_STATE, transform256, "RIPEMD-256");
+impl_ripemd!(Ripemd320, ripemd320, 10, RIPEMD320_INITIAL_STATE, transform320, "RIPEMD-320");
+
+
+
+#[test]
+fn test_ripemd160() {
+    // Test vectors
+    // https://homes.esat.kuleuven.be/~bosselae/ripemd160.html
+    assert_eq!(&ripemd160(""),
+        &hex::decode("9c1185a5c5e9fc54612808977ee8f548b2258d31").unwrap()[..]);
+    assert_eq!(&ripemd160("a"),
+        &hex::decode("0bdc9d2d256b3ee9daae347be6f4dc835a467ffe").unwrap()[..]);
+    assert_eq!(&ripemd160("abc"),
+        &hex::decode("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc").unwrap()[..]);
+    assert_eq!(&ripemd160("message digest"),
+        &hex::decode("5d0689ef49d2fae572b881b123a85ffa21595f36").unwrap()[..]);
+    assert_eq!(&ripemd160("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        &hex::decode("12a053384a9c0c88e405a06c27dcf49ada62eb2b").unwrap()[..]);
+    assert_eq!(&ripemd160("12345678901234567890123456789012345678901234567890123456789012345678901234567890"),
+        &hex::decode("9b752e45573d4b39f4dbd3323cab82bf63326bfb").unwrap()[..]);
+
+    let msg = vec![b'a'; 1000_000];
+    let mut m = Ripemd160::new();
+    for chunk in msg.chunks(1000 - 1) {
+        m.update(chunk);
+    }
+    m.finalize();
+    assert_eq!(&m.output()[..],
+        &hex::decode("52783243c1697bdbe16d37f97f68f08325dc1528").unwrap()[..]);
+}
+
+#[test]
+fn test_ripemd256() {
+    assert_eq!(&ripemd256(""),
+        &hex::decode("02ba4c4e5f8ecd1877fc52d64d30e37a2d9774fb1e5d026380ae0168e3c5522d").unwrap()[..]);
+    assert_eq!(&ripemd256("abc"),
+        &hex::decode("afbd6e228b9d8cbbcef5ca2d03e6dba10ac0bc7dcbe4680e1e42d2e975459b65").unwrap()[..]);
+    assert_eq!(&ripemd256("message digest"),
+        &hex::decode("87e971759a1ce47a514d5c914c392c9018c7c46bc14465554afcdf54a5070c0e").unwrap()[..]);
+}
+
+#[test]
+fn test_ripemd320() {
+    assert_eq!(&ripemd320(""),
+        &hex::decode("22d65d5661536cdc75c1fdf5c6de7b41b9f27325ebc61e8557177d705a0ec880151c3a32a00899b8").unwrap()[..]);
+    assert_eq!(&ripemd320("abc"),
+        &hex::decode("de4c01b3054f8930a79d09ae738e92301e5a17085beffdc1b8d116713e74f82fa942d64cdbc4682d").unwrap()[..]);
+    assert_eq!(&ripemd320("message digest"),
+        &hex::decode("3a8e28502ed45d422f68844f9dd316e7b98533fa3f2a91d29f84d425c88d6b4eff727df66a7c0197").unwrap()[..]);
+}
+
+#[test]
+fn test_ripemd160_sha256() {
+    // NOTE: Bitcoin 地址使用的 HASH160 = RIPEMD160(SHA256(x))，这里以 secp256k1 的生成元（压缩公钥）为例。
+    let pubkey = hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap();
+    assert_eq!(&ripemd160(&crate::hash::sha256(&pubkey)),
+        &hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()[..]);
+}
@@ -7,6 +7,8 @@
 // ‼️ SHA1算法在2017年被证实无法防止碰撞攻击，因此不适用于安全性认证。
 
 use std::convert::TryFrom;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
 
 // NOTE: 虽然在 X86 和 AArch64 架构上，有很多款芯片都支持对 SHA1 加速，
 //       但是考虑到 SHA1 已经被证实存在弱点，所以这里不再对 SHA1 的代码
@@ -72,6 +74,7 @@ impl Sha1 {
                 if data.len() > 0 {
                     return self.update(data);
                 }
+                return ();
             }
         }
 
@@ -138,6 +141,21 @@ impl Sha1 {
         output
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32s(&self.state);
+        w.write_usize(self.len);
+        w.write_bytes(&self.buffer[..self.len % Self::BLOCK_LEN]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        r.read_u32s(&mut m.state)?;
+        m.len = r.read_usize()?;
+        let n = m.len % Self::BLOCK_LEN;
+        m.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+        Ok(m)
+    }
+
     pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
         let mut m = Self::new();
         m.update(data.as_ref());
@@ -146,6 +164,8 @@ impl Sha1 {
     }
 }
 
+impl_hash_state!(Sha1, "SHA1");
+
 // https://github.com/B-Con/crypto-algorithms/blob/master/sha1.c
 #[inline]
 fn transform(state: &mut [u32; 5], block: &[u8]) {
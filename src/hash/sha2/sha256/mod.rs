@@ -3,6 +3,8 @@
 // The SHA-256 Secure Hash Standard was published by NIST in 2002.
 // http://csrc.nist.gov/publications/fips/fips180-2/fips180-2.pdf
 use std::convert::TryFrom;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
 
 
 mod generic;
@@ -28,6 +30,17 @@ const INITIAL_STATE: [u32; 8] = [
     0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
 ];
 
+// SHA-224
+const SHA224_INITIAL_STATE: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939,
+    0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
+
+/// SHA2-224
+pub fn sha224<T: AsRef<[u8]>>(data: T) -> [u8; Sha224::DIGEST_LEN] {
+    Sha224::oneshot(data)
+}
 
 /// SHA2-256
 pub fn sha256<T: AsRef<[u8]>>(data: T) -> [u8; Sha256::DIGEST_LEN] {
@@ -78,6 +91,68 @@ fn transform(state: &mut [u32; 8], block: &[u8]) {
 }
 
 
+/// SHA2-224
+#[derive(Clone)]
+pub struct Sha224 {
+    inner: Sha256,
+}
+
+impl Sha224 {
+    pub const BLOCK_LEN: usize  = 64;
+    pub const DIGEST_LEN: usize = 28;
+
+    pub fn new() -> Self {
+        let inner = Sha256 {
+            buffer: [0u8; 64],
+            state: SHA224_INITIAL_STATE,
+            len: 0,
+        };
+        Self { inner }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data)
+    }
+
+    pub fn state(&self) -> &[u32; 8] {
+        &self.inner.state
+    }
+
+    pub fn finalize(&mut self) {
+        self.inner.finalize();
+    }
+
+    pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+        let mut output = [0u8; Self::DIGEST_LEN];
+
+        output[ 0.. 4].copy_from_slice(&self.inner.state[0].to_be_bytes());
+        output[ 4.. 8].copy_from_slice(&self.inner.state[1].to_be_bytes());
+        output[ 8..12].copy_from_slice(&self.inner.state[2].to_be_bytes());
+        output[12..16].copy_from_slice(&self.inner.state[3].to_be_bytes());
+        output[16..20].copy_from_slice(&self.inner.state[4].to_be_bytes());
+        output[20..24].copy_from_slice(&self.inner.state[5].to_be_bytes());
+        output[24..28].copy_from_slice(&self.inner.state[6].to_be_bytes());
+
+        output
+    }
+
+    pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new();
+        m.update(data.as_ref());
+        m.finalize();
+        m.output()
+    }
+}
+
+impl Default for Sha224 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_hash_state!(Sha224, "SHA2-224", inner: Sha256);
+
+
 /// SHA2-256
 #[derive(Clone)]
 pub struct Sha256 {
@@ -122,6 +197,7 @@ impl Sha256 {
                 if data.len() > 0 {
                     return self.update(data);
                 }
+                return ();
             }
         }
 
@@ -191,6 +267,21 @@ impl Sha256 {
         output
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32s(&self.state);
+        w.write_usize(self.len);
+        w.write_bytes(&self.buffer[..self.len % Self::BLOCK_LEN]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        r.read_u32s(&mut m.state)?;
+        m.len = r.read_usize()?;
+        let n = m.len % Self::BLOCK_LEN;
+        m.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+        Ok(m)
+    }
+
     pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
         let mut m = Self::new();
         m.update(data.as_ref());
@@ -199,6 +290,8 @@ impl Sha256 {
     }
 }
 
+impl_hash_state!(Sha256, "SHA2-256");
+
 
 #[test]
 fn test_sha256_one_block_message() {
@@ -228,6 +321,20 @@ fn test_sha256_long_message() {
     assert_eq!(Sha256::oneshot(&msg), digest);
 }
 
+#[test]
+fn test_sha224() {
+    assert_eq!(&sha224("")[..],
+        &hex::decode("d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f").unwrap()[..]);
+    assert_eq!(&sha224("abc")[..],
+        &hex::decode("23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7").unwrap()[..]);
+    assert_eq!(&sha224("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")[..],
+        &hex::decode("75388b16512776cc5dba5da1fd890150b0c6455cb4f58b1952522525").unwrap()[..]);
+
+    let msg = vec![b'a'; 1000_000];
+    assert_eq!(&sha224(&msg)[..],
+        &hex::decode("20794655980c91d8bbb4c1ea97618a4bf03f42581948b2ee4ee7ad67").unwrap()[..]);
+}
+
 #[test]
 fn test_transform_block() {
     let mut state = INITIAL_STATE;
@@ -3,6 +3,8 @@
 // The SHA-512 Secure Hash Standard was published by NIST in 2002.
 // http://csrc.nist.gov/publications/fips/fips180-2/fips180-2.pdf
 use std::convert::TryFrom;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
 
 
 // NOTE:
@@ -50,6 +52,18 @@ const SHA384_INITIAL_STATE: [u64; 8] = [
     0x67332667FFC00B31, 0x8EB44A8768581511, 0xDB0C2E0D64F98FA7, 0x47B5481DBEFA4FA4,
 ];
 
+// SHA-512/224
+const SHA512_224_INITIAL_STATE: [u64; 8] = [
+    0x8C3D37C819544DA2, 0x73E1996689DCD4D6, 0x1DFAB7AE32FF9C82, 0x679DD514582F9FCF,
+    0x0F6D2B697BD44DA8, 0x77E36F7304C48942, 0x3F9D85A86A1D36C8, 0x1112E6AD91D692A1,
+];
+
+// SHA-512/256
+const SHA512_256_INITIAL_STATE: [u64; 8] = [
+    0x22312194FC2BF72C, 0x9F555FA3C84C64C2, 0x2393B86B6F53B151, 0x963877195940EABD,
+    0x96283EE2A88EFFE3, 0xBE5E1E2553863992, 0x2B0199FC2C85B8AA, 0x0EB72DDC81C52CA2,
+];
+
 
 #[cfg(target_arch = "aarch64")]
 #[inline]
@@ -83,6 +97,16 @@ pub fn sha512<T: AsRef<[u8]>>(data: T) -> [u8; Sha512::DIGEST_LEN] {
     Sha512::oneshot(data)
 }
 
+/// SHA2-512/224
+pub fn sha512_224<T: AsRef<[u8]>>(data: T) -> [u8; Sha512_224::DIGEST_LEN] {
+    Sha512_224::oneshot(data)
+}
+
+/// SHA2-512/256
+pub fn sha512_256<T: AsRef<[u8]>>(data: T) -> [u8; Sha512_256::DIGEST_LEN] {
+    Sha512_256::oneshot(data)
+}
+
 
 /// SHA2-384
 #[derive(Clone)]
@@ -136,6 +160,128 @@ impl Sha384 {
     }
 }
 
+impl_hash_state!(Sha384, "SHA2-384", inner: Sha512);
+
+
+/// SHA2-512/224
+#[allow(non_camel_case_types)]
+#[derive(Clone)]
+pub struct Sha512_224 {
+    inner: Sha512,
+}
+
+impl Sha512_224 {
+    pub const BLOCK_LEN: usize  = 128;
+    pub const DIGEST_LEN: usize =  28;
+
+    pub fn new() -> Self {
+        let inner = Sha512 {
+            buffer: [0u8; 128],
+            state: SHA512_224_INITIAL_STATE,
+            len: 0,
+        };
+        Self { inner }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data)
+    }
+
+    pub fn state(&self) -> &[u64; 8] {
+        &self.inner.state
+    }
+
+    pub fn finalize(&mut self) {
+        self.inner.finalize();
+    }
+
+    pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+        let mut output = [0u8; Self::DIGEST_LEN];
+
+        output[ 0.. 8].copy_from_slice(&self.inner.state[0].to_be_bytes());
+        output[ 8..16].copy_from_slice(&self.inner.state[1].to_be_bytes());
+        output[16..24].copy_from_slice(&self.inner.state[2].to_be_bytes());
+        output[24..28].copy_from_slice(&self.inner.state[3].to_be_bytes()[..4]);
+
+        output
+    }
+
+    pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new();
+        m.update(data.as_ref());
+        m.finalize();
+        m.output()
+    }
+}
+
+impl Default for Sha512_224 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_hash_state!(Sha512_224, "SHA2-512/224", inner: Sha512);
+
+
+/// SHA2-512/256
+#[allow(non_camel_case_types)]
+#[derive(Clone)]
+pub struct Sha512_256 {
+    inner: Sha512,
+}
+
+impl Sha512_256 {
+    pub const BLOCK_LEN: usize  = 128;
+    pub const DIGEST_LEN: usize =  32;
+
+    pub fn new() -> Self {
+        let inner = Sha512 {
+            buffer: [0u8; 128],
+            state: SHA512_256_INITIAL_STATE,
+            len: 0,
+        };
+        Self { inner }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data)
+    }
+
+    pub fn state(&self) -> &[u64; 8] {
+        &self.inner.state
+    }
+
+    pub fn finalize(&mut self) {
+        self.inner.finalize();
+    }
+
+    pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+        let mut output = [0u8; Self::DIGEST_LEN];
+
+        output[ 0.. 8].copy_from_slice(&self.inner.state[0].to_be_bytes());
+        output[ 8..16].copy_from_slice(&self.inner.state[1].to_be_bytes());
+        output[16..24].copy_from_slice(&self.inner.state[2].to_be_bytes());
+        output[24..32].copy_from_slice(&self.inner.state[3].to_be_bytes());
+
+        output
+    }
+
+    pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new();
+        m.update(data.as_ref());
+        m.finalize();
+        m.output()
+    }
+}
+
+impl Default for Sha512_256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_hash_state!(Sha512_256, "SHA2-512/256", inner: Sha512);
+
 
 /// SHA2-512
 #[derive(Clone)]
@@ -180,6 +326,7 @@ impl Sha512 {
                 if data.len() > 0 {
                     return self.update(data);
                 }
+                return ();
             }
         }
 
@@ -249,6 +396,21 @@ impl Sha512 {
         output
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u64s(&self.state);
+        w.write_u128(self.len);
+        w.write_bytes(&self.buffer[..(self.len % Self::BLOCK_LEN as u128) as usize]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        r.read_u64s(&mut m.state)?;
+        m.len = r.read_u128()?;
+        let n = (m.len % Self::BLOCK_LEN as u128) as usize;
+        m.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+        Ok(m)
+    }
+
     pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
         let mut m = Self::new();
         m.update(data.as_ref());
@@ -257,6 +419,8 @@ impl Sha512 {
     }
 }
 
+impl_hash_state!(Sha512, "SHA2-512");
+
 
 #[test]
 fn test_sha512_one_block_message() {
@@ -324,4 +488,23 @@ fn test_sha384_long_message() {
         179, 220, 56, 236, 196, 235, 174, 151, 221, 216, 127, 61, 137, 133
     ];
     assert_eq!(&(sha384(&msg[..]))[..], &digest[..]);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_sha512_224() {
+    assert_eq!(&sha512_224("")[..],
+        &hex::decode("6ed0dd02806fa89e25de060c19d3ac86cabb87d6a0ddd05c333b84f4").unwrap()[..]);
+    assert_eq!(&sha512_224("abc")[..],
+        &hex::decode("4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa").unwrap()[..]);
+    assert_eq!(&sha512_224("abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu")[..],
+        &hex::decode("23fec5bb94d60b23308192640b0c453335d664734fe40e7268674af9").unwrap()[..]);
+}
+#[test]
+fn test_sha512_256() {
+    assert_eq!(&sha512_256("")[..],
+        &hex::decode("c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a").unwrap()[..]);
+    assert_eq!(&sha512_256("abc")[..],
+        &hex::decode("53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23").unwrap()[..]);
+    assert_eq!(&sha512_256("abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu")[..],
+        &hex::decode("3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a").unwrap()[..]);
+}
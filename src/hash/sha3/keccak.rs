@@ -0,0 +1,83 @@
+// The Keccak-f[1600] permutation
+// https://keccak.team/keccak_specs_summary.html
+//
+// C code
+// https://github.com/mjosaarinen/tiny_sha3/blob/master/sha3.c
+
+const KECCAKF_ROUNDS: usize = 24;
+
+// Round constants (ι step)
+const RNDC: [u64; KECCAKF_ROUNDS] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+// Rotation offsets (ρ step)
+const ROTC: [u32; 24] = [
+     1,  3,  6, 10, 15, 21, 28, 36, 45, 55,  2, 14,
+    27, 41, 56,  8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+// Lane positions (π step)
+const PILN: [usize; 24] = [
+    10,  7, 11, 17, 18,  3,  5, 16,  8, 21, 24,  4,
+    15, 23, 19, 13, 12,  2, 20, 14, 22,  9,  6,  1,
+];
+
+
+#[inline]
+pub fn keccakf(st: &mut [u64; 25]) {
+    let mut bc = [0u64; 5];
+
+    for rndc in RNDC.iter() {
+        // Theta
+        for i in 0..5 {
+            bc[i] = st[i] ^ st[i + 5] ^ st[i + 10] ^ st[i + 15] ^ st[i + 20];
+        }
+
+        for i in 0..5 {
+            let t = bc[(i + 4) % 5] ^ bc[(i + 1) % 5].rotate_left(1);
+            for j in (0..25).step_by(5) {
+                st[j + i] ^= t;
+            }
+        }
+
+        // Rho Pi
+        let mut t = st[1];
+        for i in 0..24 {
+            let j = PILN[i];
+            let tmp = st[j];
+            st[j] = t.rotate_left(ROTC[i]);
+            t = tmp;
+        }
+
+        // Chi
+        for j in (0..25).step_by(5) {
+            bc.copy_from_slice(&st[j..j + 5]);
+            for i in 0..5 {
+                st[j + i] ^= (!bc[(i + 1) % 5]) & bc[(i + 2) % 5];
+            }
+        }
+
+        // Iota
+        st[0] ^= rndc;
+    }
+}
+
+
+#[test]
+fn test_keccakf() {
+    // KeccakF-1600 applied to the all-zero state.
+    // https://github.com/XKCP/XKCP/blob/master/tests/TestVectors/KeccakF-1600-IntermediateValues.txt
+    let mut state = [0u64; 25];
+    keccakf(&mut state);
+
+    assert_eq!(&state[..5], &[
+        0xF1258F7940E1DDE7, 0x84D5CCF933C0478A, 0xD598261EA65AA9EE,
+        0xBD1547306F80494D, 0x8B284E056253D057,
+    ]);
+}
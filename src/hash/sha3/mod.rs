@@ -1,7 +1,337 @@
-
 // This Standard specifies the Secure Hash Algorithm-3 (SHA-3)
 // https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf
-// 
+//
 
 // C code
 // https://github.com/mjosaarinen/tiny_sha3/blob/master/sha3.c
+mod keccak;
+mod sp800_185;
+
+use self::keccak::keccakf;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
+
+pub use self::sp800_185::*;
+
+
+// SHA-3 的 Domain Separation 比特（已经包含 pad10*1 的第一个比特）。
+const SHA3_DS: u8 = 0x06;
+// SHAKE 的 Domain Separation 比特。
+const SHAKE_DS: u8 = 0x1f;
+
+
+// Keccak[c] 海绵结构（Sponge construction）
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.202.pdf#section.4
+#[derive(Clone)]
+struct Keccak {
+    state: [u64; 25],
+    rate: usize,      // in bytes.
+    offset: usize,    // 当前块内已吸收（或已挤出）的字节数。
+}
+
+impl Keccak {
+    fn new(rate: usize) -> Self {
+        debug_assert!(rate.is_multiple_of(8) && rate < 200);
+
+        Self { state: [0u64; 25], rate, offset: 0 }
+    }
+
+    #[inline]
+    fn xor_byte(&mut self, idx: usize, byte: u8) {
+        self.state[idx / 8] ^= (byte as u64) << (8 * (idx % 8));
+    }
+
+    fn absorb(&mut self, data: &[u8]) {
+        let mut data = data;
+
+        while self.offset != 0 && !data.is_empty() {
+            self.xor_byte(self.offset, data[0]);
+            self.offset += 1;
+            data = &data[1..];
+
+            if self.offset == self.rate {
+                keccakf(&mut self.state);
+                self.offset = 0;
+            }
+        }
+
+        while data.len() >= self.rate {
+            for (lane, chunk) in self.state.iter_mut().zip(data[..self.rate].chunks_exact(8)) {
+                *lane ^= u64::from_le_bytes([
+                    chunk[0], chunk[1], chunk[2], chunk[3],
+                    chunk[4], chunk[5], chunk[6], chunk[7],
+                ]);
+            }
+            keccakf(&mut self.state);
+            data = &data[self.rate..];
+        }
+
+        for (i, &byte) in data.iter().enumerate() {
+            self.xor_byte(self.offset + i, byte);
+        }
+        self.offset += data.len();
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u64s(&self.state);
+        w.write_usize(self.rate);
+        w.write_usize(self.offset);
+    }
+
+    // NOTE: rate 由具体的哈希函数决定，导入的 rate 必须和它一致。
+    fn load_state(r: &mut StateReader, rate: usize) -> Result<Self, InvalidHashState> {
+        let mut state = [0u64; 25];
+        r.read_u64s(&mut state)?;
+        if r.read_usize()? != rate {
+            return Err(InvalidHashState);
+        }
+        let offset = r.read_usize()?;
+        if offset >= rate {
+            return Err(InvalidHashState);
+        }
+
+        Ok(Self { state, rate, offset })
+    }
+
+    // 填充（pad10*1）并切换到挤出阶段。
+    fn pad(&mut self, ds: u8) {
+        self.xor_byte(self.offset, ds);
+        self.xor_byte(self.rate - 1, 0x80);
+        keccakf(&mut self.state);
+        self.offset = 0;
+    }
+
+    fn squeeze(&mut self, output: &mut [u8]) {
+        for byte in output.iter_mut() {
+            if self.offset == self.rate {
+                keccakf(&mut self.state);
+                self.offset = 0;
+            }
+
+            *byte = (self.state[self.offset / 8] >> (8 * (self.offset % 8))) as u8;
+            self.offset += 1;
+        }
+    }
+}
+
+
+macro_rules! impl_sha3 {
+    ($name:tt, $fname:tt, $rate:tt, $dlen:tt, $doc:expr) => {
+        #[doc = $doc]
+        pub fn $fname<T: AsRef<[u8]>>(data: T) -> [u8; $name::DIGEST_LEN] {
+            $name::oneshot(data)
+        }
+
+        #[doc = $doc]
+        #[allow(non_camel_case_types)]
+        #[derive(Clone)]
+        pub struct $name {
+            inner: Keccak,
+        }
+
+        impl $name {
+            pub const BLOCK_LEN: usize  = $rate;
+            pub const DIGEST_LEN: usize = $dlen;
+
+            pub fn new() -> Self {
+                Self { inner: Keccak::new(Self::BLOCK_LEN) }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                self.inner.absorb(data);
+            }
+
+            pub fn finalize(&mut self) {
+                self.inner.pad(SHA3_DS);
+            }
+
+            pub fn state(&self) -> &[u64; 25] {
+                &self.inner.state
+            }
+
+            pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+                let mut inner = self.inner;
+                let mut output = [0u8; Self::DIGEST_LEN];
+                inner.squeeze(&mut output);
+                output
+            }
+
+            pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+                let mut m = Self::new();
+                m.update(data.as_ref());
+                m.finalize();
+                m.output()
+            }
+
+            fn save_state(&self, w: &mut StateWriter) {
+                self.inner.save_state(w);
+            }
+
+            fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+                Ok(Self { inner: Keccak::load_state(r, Self::BLOCK_LEN)? })
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl_hash_state!($name, $doc);
+    }
+}
+
+// NOTE: BLOCK_LEN 即海绵结构的 rate（r = 1600 - 2 * DIGEST_LEN * 8 bits）。
+impl_sha3!(Sha3_224, sha3_224, 144, 28, "SHA3-224");
+impl_sha3!(Sha3_256, sha3_256, 136, 32, "SHA3-256");
+impl_sha3!(Sha3_384, sha3_384, 104, 48, "SHA3-384");
+impl_sha3!(Sha3_512, sha3_512,  72, 64, "SHA3-512");
+
+
+/// 可扩展输出函数（XOF）的挤出阶段，可以分多次读取任意长度的输出。
+#[derive(Clone)]
+pub struct XofReader {
+    inner: Keccak,
+}
+
+impl XofReader {
+    pub fn read(&mut self, output: &mut [u8]) {
+        self.inner.squeeze(output);
+    }
+}
+
+
+macro_rules! impl_shake {
+    ($name:tt, $fname:tt, $rate:tt, $doc:expr) => {
+        #[doc = $doc]
+        pub fn $fname<T: AsRef<[u8]>>(data: T, output: &mut [u8]) {
+            $name::oneshot(data, output)
+        }
+
+        #[doc = $doc]
+        #[derive(Clone)]
+        pub struct $name {
+            inner: Keccak,
+        }
+
+        impl $name {
+            pub const BLOCK_LEN: usize = $rate;
+
+            pub fn new() -> Self {
+                Self { inner: Keccak::new(Self::BLOCK_LEN) }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                self.inner.absorb(data);
+            }
+
+            /// 结束吸收阶段，返回可以持续读取输出的 `XofReader`。
+            pub fn finalize_xof(self) -> XofReader {
+                let mut inner = self.inner;
+                inner.pad(SHAKE_DS);
+                XofReader { inner }
+            }
+
+            pub fn oneshot<T: AsRef<[u8]>>(data: T, output: &mut [u8]) {
+                let mut m = Self::new();
+                m.update(data.as_ref());
+                m.finalize_xof().read(output);
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    }
+}
+
+impl_shake!(Shake128, shake128, 168, "SHAKE128");
+impl_shake!(Shake256, shake256, 136, "SHAKE256");
+
+
+#[test]
+fn test_sha3_224() {
+    assert_eq!(&sha3_224(""),
+        &hex::decode("6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7").unwrap()[..]);
+    assert_eq!(&sha3_224("abc"),
+        &hex::decode("e642824c3f8cf24ad09234ee7d3c766fc9a3a5168d0c94ad73b46fdf").unwrap()[..]);
+}
+#[test]
+fn test_sha3_256() {
+    assert_eq!(&sha3_256(""),
+        &hex::decode("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a").unwrap()[..]);
+    assert_eq!(&sha3_256("abc"),
+        &hex::decode("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532").unwrap()[..]);
+    assert_eq!(&sha3_256("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        &hex::decode("41c0dba2a9d6240849100376a8235e2c82e1b9998a999e21db32dd97496d3376").unwrap()[..]);
+}
+#[test]
+fn test_sha3_384() {
+    assert_eq!(&sha3_384(""),
+        &hex::decode("0c63a75b845e4f7d01107d852e4c2485c51a50aaaa94fc61995e71bbee983a2a\
+c3713831264adb47fb6bd1e058d5f004").unwrap()[..]);
+    assert_eq!(&sha3_384("abc"),
+        &hex::decode("ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c2596da7cf0e49be4b2\
+98d88cea927ac7f539f1edf228376d25").unwrap()[..]);
+}
+#[test]
+fn test_sha3_512() {
+    assert_eq!(&sha3_512(""),
+        &hex::decode("a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a6\
+15b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26").unwrap()[..]);
+    assert_eq!(&sha3_512("abc"),
+        &hex::decode("b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0").unwrap()[..]);
+}
+#[test]
+fn test_sha3_256_long_message() {
+    let msg = vec![b'a'; 1000_000];
+    assert_eq!(&sha3_256(&msg),
+        &hex::decode("5c8875ae474a3634ba4fd55ec85bffd661f32aca75c6d699d0cdcb6c115891c1").unwrap()[..]);
+
+    // NOTE: 分段写入的结果应当和一次性写入相同。
+    let mut m = Sha3_256::new();
+    for chunk in msg.chunks(1000 - 1) {
+        m.update(chunk);
+    }
+    m.finalize();
+    assert_eq!(&m.output()[..], &sha3_256(&msg)[..]);
+}
+
+#[test]
+fn test_shake128() {
+    let mut output = [0u8; 32];
+    shake128("", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26").unwrap()[..]);
+
+    // NOTE: 跨越多个 rate 大小的块，分段读取。
+    let mut output = [0u8; 200];
+    let mut reader = {
+        let mut m = Shake128::new();
+        m.update(b"abc");
+        m.finalize_xof()
+    };
+    for chunk in output.chunks_mut(7) {
+        reader.read(chunk);
+    }
+    assert_eq!(&output[168..],
+        &hex::decode("6aa01b3f5af057805f973ff8ecb8b226ac32ada6f01c1fcd4818cb006aa5b4cd").unwrap()[..]);
+}
+#[test]
+fn test_shake256() {
+    let mut output = [0u8; 64];
+    shake256("", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be").unwrap()[..]);
+
+    let data = (0..1024).map(|i| i as u8).collect::<Vec<u8>>();
+    let mut output = [0u8; 300];
+    shake256(&data, &mut output);
+    assert_eq!(&output[268..],
+        &hex::decode("f3f80efdcca3b24ca403558009b62dcc62dec4654fbbc2514038af7b49305e12").unwrap()[..]);
+}
@@ -0,0 +1,382 @@
+// SHA-3 Derived Functions: cSHAKE, KMAC, TupleHash and ParallelHash
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf
+//
+// NOTE: KMAC 属于消息认证码，实现位于 `crate::kmac`。
+use super::{Keccak, XofReader, SHAKE_DS};
+use super::{Shake128, Shake256};
+use crate::error::InvalidParams;
+
+
+// cSHAKE 的 Domain Separation 比特。
+const CSHAKE_DS: u8 = 0x04;
+
+
+// 2.3.1 Integer to Byte String Encoding
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf#subsection.2.3.1
+#[inline]
+fn encode_len(x: u64) -> usize {
+    std::cmp::max(1, 8 - x.leading_zeros() as usize / 8)
+}
+
+pub(crate) fn left_encode(x: u64, buf: &mut [u8; 9]) -> &[u8] {
+    let n = encode_len(x);
+    buf[0] = n as u8;
+    buf[1..n + 1].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+    &buf[..n + 1]
+}
+
+pub(crate) fn right_encode(x: u64, buf: &mut [u8; 9]) -> &[u8] {
+    let n = encode_len(x);
+    buf[..n].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+    buf[n] = n as u8;
+    &buf[..n + 1]
+}
+
+// bytepad(encode_string(s1) || encode_string(s2) || ..., rate)
+fn absorb_bytepad(sponge: &mut Keccak, strings: &[&[u8]]) {
+    let mut buf = [0u8; 9];
+    let mut len = 0usize;
+
+    let w = left_encode(sponge.rate as u64, &mut buf);
+    len += w.len();
+    sponge.absorb(w);
+
+    for s in strings.iter() {
+        let prefix = left_encode(s.len() as u64 * 8, &mut buf);
+        len += prefix.len();
+        sponge.absorb(prefix);
+
+        len += s.len();
+        sponge.absorb(s);
+    }
+
+    let zeros = [0u8; 200];
+    let n = (sponge.rate - len % sponge.rate) % sponge.rate;
+    sponge.absorb(&zeros[..n]);
+}
+
+
+macro_rules! impl_cshake {
+    ($name:tt, $fname:tt, $rate:tt, $doc:expr) => {
+        #[doc = $doc]
+        pub fn $fname<T: AsRef<[u8]>>(data: T, function_name: &[u8], customization: &[u8], output: &mut [u8]) {
+            $name::oneshot(data, function_name, customization, output)
+        }
+
+        #[doc = $doc]
+        #[derive(Clone)]
+        pub struct $name {
+            inner: Keccak,
+            ds: u8,
+        }
+
+        impl $name {
+            pub const BLOCK_LEN: usize = $rate;
+
+            pub fn new(function_name: &[u8], customization: &[u8]) -> Self {
+                let mut inner = Keccak::new(Self::BLOCK_LEN);
+
+                // NOTE: 当 N 和 S 都为空字符串时，cSHAKE 等价于 SHAKE。
+                if function_name.is_empty() && customization.is_empty() {
+                    return Self { inner, ds: SHAKE_DS };
+                }
+
+                absorb_bytepad(&mut inner, &[function_name, customization]);
+
+                Self { inner, ds: CSHAKE_DS }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                self.inner.absorb(data);
+            }
+
+            #[doc(hidden)]
+            pub(crate) fn update_bytepad(&mut self, strings: &[&[u8]]) {
+                absorb_bytepad(&mut self.inner, strings);
+            }
+
+            pub fn finalize_xof(self) -> XofReader {
+                let mut inner = self.inner;
+                inner.pad(self.ds);
+                XofReader { inner }
+            }
+
+            pub fn oneshot<T: AsRef<[u8]>>(data: T, function_name: &[u8], customization: &[u8], output: &mut [u8]) {
+                let mut m = Self::new(function_name, customization);
+                m.update(data.as_ref());
+                m.finalize_xof().read(output);
+            }
+        }
+    }
+}
+
+impl_cshake!(CShake128, cshake128, 168, "cSHAKE128");
+impl_cshake!(CShake256, cshake256, 136, "cSHAKE256");
+
+
+macro_rules! impl_tuple_hash {
+    ($name:tt, $cshake:tt, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Clone)]
+        pub struct $name {
+            inner: $cshake,
+        }
+
+        impl $name {
+            pub fn new(customization: &[u8]) -> Self {
+                Self { inner: $cshake::new(b"TupleHash", customization) }
+            }
+
+            /// 追加元组中的一个元素，每个元素都会单独编码（`encode_string`），
+            /// 因此 `push(b"ab")` 和 `push(b"a"); push(b"b")` 的结果不同。
+            pub fn push(&mut self, item: &[u8]) {
+                let mut buf = [0u8; 9];
+                self.inner.update(left_encode(item.len() as u64 * 8, &mut buf));
+                self.inner.update(item);
+            }
+
+            pub fn finalize(self, output: &mut [u8]) {
+                let mut m = self.inner;
+                let mut buf = [0u8; 9];
+                m.update(right_encode(output.len() as u64 * 8, &mut buf));
+                m.finalize_xof().read(output);
+            }
+
+            /// TupleHashXOF
+            pub fn finalize_xof(self) -> XofReader {
+                let mut m = self.inner;
+                let mut buf = [0u8; 9];
+                m.update(right_encode(0, &mut buf));
+                m.finalize_xof()
+            }
+
+            pub fn oneshot(items: &[&[u8]], customization: &[u8], output: &mut [u8]) {
+                let mut m = Self::new(customization);
+                for item in items.iter() {
+                    m.push(item);
+                }
+                m.finalize(output);
+            }
+        }
+    }
+}
+
+impl_tuple_hash!(TupleHash128, CShake128, "TupleHash128");
+impl_tuple_hash!(TupleHash256, CShake256, "TupleHash256");
+
+
+// NOTE: 目前各个分块是按顺序计算的，并没有利用 SIMD 或者多线程。
+macro_rules! impl_parallel_hash {
+    ($name:tt, $cshake:tt, $shake:tt, $clen:tt, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Clone)]
+        pub struct $name {
+            inner: $cshake,
+            block_size: usize,
+            buffer: Vec<u8>,
+            blocks: u64,
+        }
+
+        impl $name {
+            // 每个分块的摘要长度（安全强度的两倍）。
+            const CHAINING_LEN: usize = $clen;
+
+            /// `block_size` 为每个分块的字节数 B，不能为 0。
+            pub fn new(block_size: usize, customization: &[u8]) -> Result<Self, InvalidParams> {
+                if block_size == 0 {
+                    return Err(InvalidParams);
+                }
+
+                let mut inner = $cshake::new(b"ParallelHash", customization);
+                let mut buf = [0u8; 9];
+                inner.update(left_encode(block_size as u64, &mut buf));
+
+                Ok(Self { inner, block_size, buffer: Vec::with_capacity(block_size), blocks: 0 })
+            }
+
+            #[inline]
+            fn process_block(inner: &mut $cshake, block: &[u8]) {
+                let mut chaining_value = [0u8; Self::CHAINING_LEN];
+                $shake::oneshot(block, &mut chaining_value);
+                inner.update(&chaining_value);
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                let mut data = data;
+
+                if !self.buffer.is_empty() {
+                    let n = std::cmp::min(self.block_size - self.buffer.len(), data.len());
+                    self.buffer.extend_from_slice(&data[..n]);
+                    data = &data[n..];
+
+                    if self.buffer.len() < self.block_size {
+                        return ();
+                    }
+
+                    Self::process_block(&mut self.inner, &self.buffer);
+                    self.blocks += 1;
+                    self.buffer.clear();
+                }
+
+                while data.len() >= self.block_size {
+                    Self::process_block(&mut self.inner, &data[..self.block_size]);
+                    self.blocks += 1;
+                    data = &data[self.block_size..];
+                }
+
+                self.buffer.extend_from_slice(data);
+            }
+
+            fn finish(mut self, output_bits: u64) -> XofReader {
+                if !self.buffer.is_empty() {
+                    Self::process_block(&mut self.inner, &self.buffer);
+                    self.blocks += 1;
+                }
+
+                let mut buf = [0u8; 9];
+                self.inner.update(right_encode(self.blocks, &mut buf));
+                self.inner.update(right_encode(output_bits, &mut buf));
+                self.inner.finalize_xof()
+            }
+
+            pub fn finalize(self, output: &mut [u8]) {
+                self.finish(output.len() as u64 * 8).read(output);
+            }
+
+            /// ParallelHashXOF
+            pub fn finalize_xof(self) -> XofReader {
+                self.finish(0)
+            }
+
+            pub fn oneshot<T: AsRef<[u8]>>(data: T, block_size: usize, customization: &[u8], output: &mut [u8]) -> Result<(), InvalidParams> {
+                let mut m = Self::new(block_size, customization)?;
+                m.update(data.as_ref());
+                m.finalize(output);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl_parallel_hash!(ParallelHash128, CShake128, Shake128, 32, "ParallelHash128");
+impl_parallel_hash!(ParallelHash256, CShake256, Shake256, 64, "ParallelHash256");
+
+
+#[test]
+fn test_left_right_encode() {
+    let mut buf = [0u8; 9];
+    assert_eq!(left_encode(0, &mut buf), &[0x01, 0x00]);
+    assert_eq!(left_encode(168, &mut buf), &[0x01, 0xa8]);
+    assert_eq!(left_encode(256, &mut buf), &[0x02, 0x01, 0x00]);
+    assert_eq!(right_encode(0, &mut buf), &[0x00, 0x01]);
+    assert_eq!(right_encode(256, &mut buf), &[0x01, 0x00, 0x02]);
+    assert_eq!(right_encode(u64::MAX, &mut buf), &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x08]);
+}
+
+#[test]
+fn test_cshake() {
+    // cSHAKE Samples
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Standards-and-Guidelines/documents/examples/cSHAKE_samples.pdf
+    let data1 = [0x00, 0x01, 0x02, 0x03];
+    let data2 = (0u8..0xc8).collect::<Vec<u8>>();
+
+    let mut output = [0u8; 32];
+    cshake128(&data1, b"", b"Email Signature", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5").unwrap()[..]);
+
+    cshake128(&data2, b"", b"Email Signature", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b").unwrap()[..]);
+
+    let mut output = [0u8; 64];
+    cshake256(&data1, b"", b"Email Signature", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd1\
+64020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c").unwrap()[..]);
+
+    cshake256(&data2, b"", b"Email Signature", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("07dc27b11e51fbac75bc7b3c1d983e8b4b85fb1defaf218912ac864302730917\
+27f42b17ed1df63e8ec118f04b23633c1dfb1574c8fb55cb45da8e25afb092bb").unwrap()[..]);
+
+    // NOTE: N 和 S 都为空时等价于 SHAKE。
+    let mut a = [0u8; 32];
+    let mut b = [0u8; 32];
+    cshake128(&data2, b"", b"", &mut a);
+    Shake128::oneshot(&data2, &mut b);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_tuple_hash() {
+    // TupleHash Samples
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Standards-and-Guidelines/documents/examples/TupleHash_samples.pdf
+    let x1: &[u8] = &[0x00, 0x01, 0x02];
+    let x2: &[u8] = &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15];
+    let x3: &[u8] = &[0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28];
+
+    let mut output = [0u8; 32];
+    TupleHash128::oneshot(&[x1, x2], b"", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("c5d8786c1afb9b82111ab34b65b2c0048fa64e6d48e263264ce1707d3ffc8ed1").unwrap()[..]);
+
+    TupleHash128::oneshot(&[x1, x2], b"My Tuple App", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("75cdb20ff4db1154e841d758e24160c54bae86eb8c13e7f5f40eb35588e96dfb").unwrap()[..]);
+
+    TupleHash128::oneshot(&[x1, x2, x3], b"My Tuple App", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("e60f202c89a2631eda8d4c588ca5fd07f39e5151998deccf973adb3804bb6e84").unwrap()[..]);
+
+    let mut output = [0u8; 64];
+    TupleHash256::oneshot(&[x1, x2], b"", &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("cfb7058caca5e668f81a12a20a2195ce97a925f1dba3e7449a56f82201ec6073\
+11ac2696b1ab5ea2352df1423bde7bd4bb78c9aed1a853c78672f9eb23bbe194").unwrap()[..]);
+
+    // TupleHashXOF128
+    let mut m = TupleHash128::new(b"");
+    m.push(x1);
+    m.push(x2);
+    let mut output = [0u8; 32];
+    m.finalize_xof().read(&mut output);
+    assert_eq!(&output[..],
+        &hex::decode("2f103cd7c32320353495c68de1a8129245c6325f6f2a3d608d92179c96e68488").unwrap()[..]);
+}
+
+#[test]
+fn test_parallel_hash() {
+    // ParallelHash Samples
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Standards-and-Guidelines/documents/examples/ParallelHash_samples.pdf
+    let data = hex::decode("000102030405060710111213141516172021222324252627").unwrap();
+
+    let mut output = [0u8; 32];
+    ParallelHash128::oneshot(&data, 8, b"", &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("ba8dc1d1d979331d3f813603c67f72609ab5e44b94a0b8f9af46514454a2b4f5").unwrap()[..]);
+
+    ParallelHash128::oneshot(&data, 8, b"Parallel Data", &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("fc484dcb3f84dceedc353438151bee58157d6efed0445a81f165e495795b7206").unwrap()[..]);
+
+    // NOTE: 分段写入的结果应当和一次性写入相同。
+    let mut m = ParallelHash128::new(8, b"").unwrap();
+    for chunk in data.chunks(5) {
+        m.update(chunk);
+    }
+    let mut reader = m.finalize_xof();
+    reader.read(&mut output);
+    assert_eq!(&output[..],
+        &hex::decode("fe47d661e49ffe5b7d999922c062356750caf552985b8e8ce6667f2727c3c8d3").unwrap()[..]);
+
+    let mut output = [0u8; 64];
+    ParallelHash256::oneshot(&data, 8, b"", &mut output).unwrap();
+    assert_eq!(&output[..],
+        &hex::decode("bc1ef124da34495e948ead207dd9842235da432d2bbc54b4c110e64c45110553\
+1b7f2a3e0ce055c02805e7c2de1fb746af97a1dd01f43b824e31b87612410429").unwrap()[..]);
+
+    // NOTE: 分块大小为 0 是无效参数。
+    assert!(ParallelHash128::new(0, b"").is_err());
+    assert!(ParallelHash256::oneshot(&data, 0, b"", &mut output).is_err());
+}
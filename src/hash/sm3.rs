@@ -3,6 +3,8 @@
 // 
 // GM/T 0004-2012 SM3 Cryptographic Hash Algorithm （English Version）
 // http://www.gmbz.org.cn/upload/2018-07-24/1532401392982079739.pdf
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
 
 
 const INITIAL_STATE: [u32; 8] = [
@@ -93,6 +95,25 @@ impl Sm3 {
         output
     }
 
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u32s(&self.state);
+        w.write_u64(self.len);
+        w.write_usize(self.offset);
+        w.write_bytes(&self.buffer[..self.offset]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        r.read_u32s(&mut m.state)?;
+        m.len = r.read_u64()?;
+        m.offset = r.read_usize()?;
+        if m.offset >= Self::BLOCK_LEN {
+            return Err(InvalidHashState);
+        }
+        m.buffer[..m.offset].copy_from_slice(r.read_bytes(m.offset)?);
+        Ok(m)
+    }
+
     pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
         let mut m = Self::new();
         m.update(data.as_ref());
@@ -101,6 +122,8 @@ impl Sm3 {
     }
 }
 
+impl_hash_state!(Sm3, "SM3");
+
 
 #[inline(always)]
 fn ff0(x: u32, y: u32, z: u32) -> u32 {
@@ -0,0 +1,322 @@
+// 哈希函数中间状态（Midstate）的导出与导入
+//
+// 序列化格式：
+//     name_len(u8) || name || fields...
+//
+// NOTE: 所有整数均以小端序编码，`usize` 统一编码为 `u64`。
+//       `name` 用于区分不同的哈希函数，避免把 SHA-224 的状态导入到 SHA-256 里面。
+use crate::error::InvalidHashState;
+
+use std::convert::TryFrom;
+
+
+pub struct StateWriter {
+    buf: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new(name: &str) -> Self {
+        debug_assert!(name.len() <= u8::MAX as usize);
+
+        let mut buf = Vec::with_capacity(256);
+        buf.push(name.len() as u8);
+        buf.extend_from_slice(name.as_bytes());
+
+        Self { buf }
+    }
+
+    pub fn write_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub fn write_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_u128(&mut self, v: u128) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn write_usize(&mut self, v: usize) {
+        self.write_u64(v as u64);
+    }
+
+    pub fn write_u32s(&mut self, v: &[u32]) {
+        for n in v.iter() {
+            self.write_u32(*n);
+        }
+    }
+
+    pub fn write_u64s(&mut self, v: &[u64]) {
+        for n in v.iter() {
+            self.write_u64(*n);
+        }
+    }
+
+    pub fn write_bytes(&mut self, v: &[u8]) {
+        self.buf.extend_from_slice(v);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(state: &'a [u8], name: &str) -> Result<Self, InvalidHashState> {
+        let mut r = Self { data: state };
+
+        let len = r.read_u8()? as usize;
+        if r.read_bytes(len)? != name.as_bytes() {
+            return Err(InvalidHashState);
+        }
+
+        Ok(r)
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], InvalidHashState> {
+        if self.data.len() < len {
+            return Err(InvalidHashState);
+        }
+
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+
+        Ok(head)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, InvalidHashState> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, InvalidHashState> {
+        let mut octets = [0u8; 4];
+        octets.copy_from_slice(self.read_bytes(4)?);
+        Ok(u32::from_le_bytes(octets))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, InvalidHashState> {
+        let mut octets = [0u8; 8];
+        octets.copy_from_slice(self.read_bytes(8)?);
+        Ok(u64::from_le_bytes(octets))
+    }
+
+    pub fn read_u128(&mut self) -> Result<u128, InvalidHashState> {
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(self.read_bytes(16)?);
+        Ok(u128::from_le_bytes(octets))
+    }
+
+    pub fn read_usize(&mut self) -> Result<usize, InvalidHashState> {
+        usize::try_from(self.read_u64()?).map_err(|_| InvalidHashState)
+    }
+
+    pub fn read_u32s(&mut self, v: &mut [u32]) -> Result<(), InvalidHashState> {
+        for n in v.iter_mut() {
+            *n = self.read_u32()?;
+        }
+        Ok(())
+    }
+
+    pub fn read_u64s(&mut self, v: &mut [u64]) -> Result<(), InvalidHashState> {
+        for n in v.iter_mut() {
+            *n = self.read_u64()?;
+        }
+        Ok(())
+    }
+
+    // NOTE: 检查状态数据是否已经全部读取完毕。
+    pub fn finish(self) -> Result<(), InvalidHashState> {
+        if !self.data.is_empty() {
+            return Err(InvalidHashState);
+        }
+        Ok(())
+    }
+}
+
+
+// 为哈希函数生成 `export_state` 和 `import_state` 方法。
+//
+// 需要类型自身（或者内部的 `$inner` 字段的类型）提供
+// `save_state(&self, &mut StateWriter)` 以及 `load_state(&mut StateReader) -> Result<Self, InvalidHashState>`。
+macro_rules! impl_hash_state {
+    ($name:tt, $label:expr) => {
+        impl $name {
+            /// 导出尚未 `finalize` 的完整中间状态
+            pub fn export_state(&self) -> Vec<u8> {
+                let mut w = crate::hash::state::StateWriter::new($label);
+                self.save_state(&mut w);
+                w.finish()
+            }
+
+            /// 从 `export_state` 导出的数据中恢复中间状态
+            pub fn import_state(state: &[u8]) -> Result<Self, crate::error::InvalidHashState> {
+                let mut r = crate::hash::state::StateReader::new(state, $label)?;
+                let m = Self::load_state(&mut r)?;
+                r.finish()?;
+                Ok(m)
+            }
+        }
+    };
+    ($name:tt, $label:expr, $inner:tt: $ty:tt) => {
+        impl $name {
+            /// 导出尚未 `finalize` 的完整中间状态
+            pub fn export_state(&self) -> Vec<u8> {
+                let mut w = crate::hash::state::StateWriter::new($label);
+                self.$inner.save_state(&mut w);
+                w.finish()
+            }
+
+            /// 从 `export_state` 导出的数据中恢复中间状态
+            pub fn import_state(state: &[u8]) -> Result<Self, crate::error::InvalidHashState> {
+                let mut r = crate::hash::state::StateReader::new(state, $label)?;
+                let inner = $ty::load_state(&mut r)?;
+                r.finish()?;
+                Ok(Self { $inner: inner })
+            }
+        }
+    };
+}
+
+
+
+#[cfg(test)]
+fn check_resume<H: crate::hash::ResumableHasher + crate::hash::BuildCryptoHasher<Hasher = H>>() {
+    use crate::hash::Array;
+
+    let data = (0..3000usize).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    let expected = H::oneshot(&data);
+
+    for &n in [0usize, 1, 63, 64, 65, 127, 128, 129, 1024, 1025, 2049, 3000].iter() {
+        let mut m = H::build_hasher();
+        m.write(&data[..n]);
+
+        let state = m.export_state();
+        let mut m = H::import_state(&state).unwrap();
+        m.write(&data[n..]);
+        assert_eq!(m.digest().array_as_slice(), expected.array_as_slice());
+    }
+}
+
+#[test]
+fn test_hash_state_resume() {
+    use crate::hash::*;
+
+    check_resume::<Md2>();
+    check_resume::<Md4>();
+    check_resume::<Md5>();
+    check_resume::<Sm3>();
+    check_resume::<Sha1>();
+    check_resume::<Sha224>();
+    check_resume::<Sha256>();
+    check_resume::<Sha384>();
+    check_resume::<Sha512>();
+    check_resume::<Sha512_224>();
+    check_resume::<Sha512_256>();
+    check_resume::<Sha3_224>();
+    check_resume::<Sha3_256>();
+    check_resume::<Sha3_384>();
+    check_resume::<Sha3_512>();
+    check_resume::<Blake2b512>();
+    check_resume::<Blake2s256>();
+    check_resume::<Blake3>();
+    check_resume::<Ripemd160>();
+    check_resume::<Ripemd256>();
+    check_resume::<Ripemd320>();
+    check_resume::<Streebog256>();
+    check_resume::<Streebog512>();
+    check_resume::<Whirlpool>();
+}
+
+#[test]
+fn test_hash_state_keyed() {
+    use crate::hash::{Blake2b512, Blake3};
+
+    let key = [7u8; 32];
+
+    let mut m = Blake2b512::with_key(&key).unwrap();
+    m.update(b"hello, ");
+    let mut m = Blake2b512::import_state(&m.export_state()).unwrap();
+    m.update(b"world");
+    m.finalize();
+    let mut m2 = Blake2b512::with_key(&key).unwrap();
+    m2.update(b"hello, world");
+    m2.finalize();
+    assert_eq!(&m.output()[..], &m2.output()[..]);
+
+    let mut m = Blake3::new_keyed(&key);
+    m.update(&[1u8; 5000]);
+    let mut m = Blake3::import_state(&m.export_state()).unwrap();
+    m.update(b"world");
+    let mut m2 = Blake3::new_keyed(&key);
+    m2.update(&[1u8; 5000]);
+    m2.update(b"world");
+    assert_eq!(m.output(), m2.output());
+}
+
+#[test]
+fn test_hash_state_invalid() {
+    use crate::hash::{Sha224, Sha256, Sha3_256, Sha3_512};
+
+    let mut m = Sha256::new();
+    m.update(b"abc");
+    let state = m.export_state();
+
+    assert!(Sha256::import_state(&state).is_ok());
+    // 不同的哈希函数
+    assert_eq!(Sha224::import_state(&state).err(), Some(InvalidHashState));
+    // 数据被截断
+    assert_eq!(Sha256::import_state(&state[..state.len() - 1]).err(), Some(InvalidHashState));
+    // 多余的数据
+    let mut state2 = state.clone();
+    state2.push(0);
+    assert_eq!(Sha256::import_state(&state2).err(), Some(InvalidHashState));
+    assert_eq!(Sha256::import_state(&[]).err(), Some(InvalidHashState));
+
+    let state = Sha3_256::new().export_state();
+    assert_eq!(Sha3_512::import_state(&state).err(), Some(InvalidHashState));
+}
+
+#[test]
+fn test_hash_state_crafted() {
+    use crate::hash::{Sha3_256, Blake3};
+
+    // NOTE: 篡改 SHA3-256 状态里的 rate（位于 25 个 u64 之后）。
+    let mut m = Sha3_256::new();
+    m.update(b"abc");
+    let state = m.export_state();
+    let pos = 1 + "SHA3-256".len() + 25 * 8;
+    assert!(Sha3_256::import_state(&state).is_ok());
+    for &rate in [72usize, 168, 0, 137].iter() {
+        let mut state = state.clone();
+        state[pos..pos + 8].copy_from_slice(&(rate as u64).to_le_bytes());
+        assert_eq!(Sha3_256::import_state(&state).err(), Some(InvalidHashState));
+    }
+
+    // NOTE: 篡改 BLAKE3 状态里的 chunk_counter，使它和链值栈的长度不一致。
+    let mut m = Blake3::new();
+    m.update(&[1u8; 5000]);
+    let state = m.export_state();
+    assert!(Blake3::import_state(&state).is_ok());
+    // name || key || flags || cv_stack_len || cv_stack[1] || chunk cv || chunk_counter
+    let pos = 1 + "BLAKE3".len() + 32 + 4 + 8 + 32 + 32;
+    assert_eq!(&state[pos..pos + 8], &4u64.to_le_bytes());
+    for &counter in [0u64, 3, 5, 7].iter() {
+        let mut state = state.clone();
+        state[pos..pos + 8].copy_from_slice(&counter.to_le_bytes());
+        assert_eq!(Blake3::import_state(&state).err(), Some(InvalidHashState));
+    }
+    // NOTE: 链值个数一致时（4 和 8 都只有一个 1）仍然是合法的状态。
+    let mut state = state;
+    state[pos..pos + 8].copy_from_slice(&8u64.to_le_bytes());
+    assert!(Blake3::import_state(&state).is_ok());
+}
@@ -0,0 +1,303 @@
+// The WHIRLPOOL Hashing Function
+// https://web.archive.org/web/20171129084214/http://www.larc.usp.br/~pbarreto/WhirlpoolPage.html
+//
+// ISO/IEC 10118-3:2004
+//
+// NOTE: 这里实现的是最终版本（ISO 标准版本）的 Whirlpool，
+//       而不是更早的 Whirlpool-0 和 Whirlpool-T。
+use std::convert::TryFrom;
+use crate::hash::state::{StateWriter, StateReader};
+use crate::error::InvalidHashState;
+
+
+const ROUNDS: usize = 10;
+
+// Mini-boxes E, E^-1 and R
+// https://en.wikipedia.org/wiki/Whirlpool_(hash_function)#Internal_structure
+const E: [u8; 16]     = [0x1, 0xb, 0x9, 0xc, 0xd, 0x6, 0xf, 0x3, 0xe, 0x8, 0x7, 0x4, 0xa, 0x2, 0x5, 0x0];
+const E_INV: [u8; 16] = [0xf, 0x0, 0xd, 0x7, 0xb, 0xe, 0x5, 0xa, 0x9, 0x2, 0xc, 0x1, 0x3, 0x4, 0x8, 0x6];
+const R: [u8; 16]     = [0x7, 0xc, 0xb, 0xd, 0xe, 0x4, 0x9, 0xf, 0x6, 0x3, 0x8, 0xa, 0x2, 0x5, 0x1, 0x0];
+
+// The diffusion layer θ: cir(1, 1, 4, 1, 8, 5, 2, 9)
+const CIR: [u8; 8] = [0x01, 0x01, 0x04, 0x01, 0x08, 0x05, 0x02, 0x09];
+
+
+// GF(2^8) 上的乘法，既约多项式为 x^8 + x^4 + x^3 + x^2 + 1 (0x11d)
+const fn gf_mul(a: u8, b: u8) -> u8 {
+    let mut a = a;
+    let mut b = b;
+    let mut r = 0u8;
+    while b != 0 {
+        if b & 1 == 1 {
+            r ^= a;
+        }
+        let hi = a & 0x80;
+        a <<= 1;
+        if hi != 0 {
+            a ^= 0x1d;
+        }
+        b >>= 1;
+    }
+    r
+}
+
+const fn build_sbox() -> [u8; 256] {
+    let mut sbox = [0u8; 256];
+
+    let mut x = 0;
+    while x < 256 {
+        let u = E[x >> 4];
+        let l = E_INV[x & 0xf];
+        let r = R[(u ^ l) as usize];
+        sbox[x] = (E[(u ^ r) as usize] << 4) | E_INV[(l ^ r) as usize];
+        x += 1;
+    }
+
+    sbox
+}
+
+// NOTE: 把 γ（S 盒）、π（循环移位）以及 θ（乘以 MDS 矩阵）合并成 8 张查找表：
+//       TABLE[k][x] 表示第 k 列的字节为 x 时，对结果所在行的贡献（大端序）。
+const fn build_table() -> [[u64; 256]; 8] {
+    let sbox = build_sbox();
+    let mut table = [[0u64; 256]; 8];
+
+    let mut k = 0;
+    while k < 8 {
+        let mut x = 0;
+        while x < 256 {
+            let mut v = 0u64;
+            let mut j = 0;
+            while j < 8 {
+                v = (v << 8) | gf_mul(sbox[x], CIR[(j + 8 - k) % 8]) as u64;
+                j += 1;
+            }
+            table[k][x] = v;
+            x += 1;
+        }
+        k += 1;
+    }
+
+    table
+}
+
+const fn build_round_constants() -> [u64; ROUNDS] {
+    let sbox = build_sbox();
+    let mut rc = [0u64; ROUNDS];
+
+    let mut r = 0;
+    while r < ROUNDS {
+        let mut v = 0u64;
+        let mut j = 0;
+        while j < 8 {
+            v = (v << 8) | sbox[8 * r + j] as u64;
+            j += 1;
+        }
+        rc[r] = v;
+        r += 1;
+    }
+
+    rc
+}
+
+static TABLE: [[u64; 256]; 8] = build_table();
+static RC: [u64; ROUNDS] = build_round_constants();
+
+
+/// Whirlpool
+pub fn whirlpool<T: AsRef<[u8]>>(data: T) -> [u8; Whirlpool::DIGEST_LEN] {
+    Whirlpool::oneshot(data)
+}
+
+/// Whirlpool
+#[derive(Clone)]
+pub struct Whirlpool {
+    buffer: [u8; 64],
+    state: [u64; 8],
+    len: usize,      // in bytes.
+}
+
+impl Whirlpool {
+    pub const BLOCK_LEN: usize  = 64;
+    pub const DIGEST_LEN: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            buffer: [0u8; 64],
+            state: [0u64; 8],
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+
+        let n = self.len % Self::BLOCK_LEN;
+        if n != 0 {
+            let amt = std::cmp::min(Self::BLOCK_LEN - n, data.len());
+            self.buffer[n..n + amt].copy_from_slice(&data[..amt]);
+            self.len += amt;
+            data = &data[amt..];
+
+            if n + amt < Self::BLOCK_LEN {
+                return;
+            }
+            transform(&mut self.state, &self.buffer);
+        }
+
+        while data.len() >= Self::BLOCK_LEN {
+            transform(&mut self.state, &data[..Self::BLOCK_LEN]);
+            self.len += Self::BLOCK_LEN;
+            data = &data[Self::BLOCK_LEN..];
+        }
+
+        if !data.is_empty() {
+            self.buffer[..data.len()].copy_from_slice(data);
+            self.len += data.len();
+        }
+    }
+
+    pub fn finalize(&mut self) {
+        // NOTE: Whirlpool 使用 256 位（大端序）的消息长度，高 128 位总是为零。
+        let len_bits = u128::try_from(self.len).unwrap() * 8;
+        let n = self.len % Self::BLOCK_LEN;
+
+        self.buffer[n] = 0x80;
+        for i in n+1..64 {
+            self.buffer[i] = 0;
+        }
+        if n + 1 > 32 {
+            transform(&mut self.state, &self.buffer);
+            self.buffer = [0u8; 64];
+        }
+        self.buffer[48..].copy_from_slice(&len_bits.to_be_bytes());
+        transform(&mut self.state, &self.buffer);
+    }
+
+    pub fn state(&self) -> &[u64; 8] {
+        &self.state
+    }
+
+    pub fn output(self) -> [u8; Self::DIGEST_LEN] {
+        let mut output = [0u8; Self::DIGEST_LEN];
+        for i in 0..8 {
+            output[i*8..i*8 + 8].copy_from_slice(&self.state[i].to_be_bytes());
+        }
+        output
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.write_u64s(&self.state);
+        w.write_usize(self.len);
+        w.write_bytes(&self.buffer[..self.len % Self::BLOCK_LEN]);
+    }
+
+    fn load_state(r: &mut StateReader) -> Result<Self, InvalidHashState> {
+        let mut m = Self::new();
+        r.read_u64s(&mut m.state)?;
+        m.len = r.read_usize()?;
+        let n = m.len % Self::BLOCK_LEN;
+        m.buffer[..n].copy_from_slice(r.read_bytes(n)?);
+        Ok(m)
+    }
+
+    pub fn oneshot<T: AsRef<[u8]>>(data: T) -> [u8; Self::DIGEST_LEN] {
+        let mut m = Self::new();
+        m.update(data.as_ref());
+        m.finalize();
+        m.output()
+    }
+}
+
+impl Default for Whirlpool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_hash_state!(Whirlpool, "Whirlpool");
+
+
+// ρ[k] = σ[k] ∘ θ ∘ π ∘ γ
+#[inline]
+fn round(a: &[u64; 8], k: &[u64; 8]) -> [u64; 8] {
+    let mut b = [0u64; 8];
+    for i in 0..8 {
+        let mut v = k[i];
+        for t in 0..8 {
+            // NOTE: π 把第 t 列循环下移 t 行。
+            let row = a[(i + 8 - t) % 8];
+            v ^= TABLE[t][(row >> (56 - 8 * t)) as u8 as usize];
+        }
+        b[i] = v;
+    }
+    b
+}
+
+// Miyaguchi-Preneel: H_i = W[H_{i-1}](m_i) ^ H_{i-1} ^ m_i
+#[inline]
+fn transform(state: &mut [u64; 8], block: &[u8]) {
+    debug_assert_eq!(block.len(), Whirlpool::BLOCK_LEN);
+
+    let mut m = [0u64; 8];
+    for (w, chunk) in m.iter_mut().zip(block.chunks_exact(8)) {
+        *w = u64::from_be_bytes([
+            chunk[0], chunk[1], chunk[2], chunk[3],
+            chunk[4], chunk[5], chunk[6], chunk[7],
+        ]);
+    }
+
+    let mut k = *state;
+    let mut s = [0u64; 8];
+    for i in 0..8 {
+        s[i] = m[i] ^ k[i];
+    }
+
+    for rc in RC.iter() {
+        let mut c = [0u64; 8];
+        c[0] = *rc;
+
+        k = round(&k, &c);
+        s = round(&s, &k);
+    }
+
+    for i in 0..8 {
+        state[i] ^= s[i] ^ m[i];
+    }
+}
+
+
+
+#[test]
+fn test_whirlpool() {
+    // ISO/IEC 10118-3 test vectors
+    // https://web.archive.org/web/20171129084214/http://www.larc.usp.br/~pbarreto/WhirlpoolPage.html
+    assert_eq!(&whirlpool("")[..],
+        &hex::decode("19fa61d75522a4669b44e39c1d2e1726c530232130d407f89afee0964997f7a7\
+3e83be698b288febcf88e3e03c4f0757ea8964e59b63d93708b138cc42a66eb3").unwrap()[..]);
+    assert_eq!(&whirlpool("a")[..],
+        &hex::decode("8aca2602792aec6f11a67206531fb7d7f0dff59413145e6973c45001d0087b42\
+d11bc645413aeff63a42391a39145a591a92200d560195e53b478584fdae231a").unwrap()[..]);
+    assert_eq!(&whirlpool("abc")[..],
+        &hex::decode("4e2448a4c6f486bb16b6562c73b4020bf3043e3a731bce721ae1b303d97e6d4c\
+7181eebdb6c57e277d0e34957114cbd6c797fc9d95d8b582d225292076d4eef5").unwrap()[..]);
+    assert_eq!(&whirlpool("message digest")[..],
+        &hex::decode("378c84a4126e2dc6e56dcc7458377aac838d00032230f53ce1f5700c0ffb4d3b\
+8421557659ef55c106b4b52ac5a4aaa692ed920052838f3362e86dbd37a8903e").unwrap()[..]);
+    assert_eq!(&whirlpool("abcdefghijklmnopqrstuvwxyz")[..],
+        &hex::decode("f1d754662636ffe92c82ebb9212a484a8d38631ead4238f5442ee13b8054e41b\
+08bf2a9251c30b6a0b8aae86177ab4a6f68f673e7207865d5d9819a3dba4eb3b").unwrap()[..]);
+    assert_eq!(&whirlpool("12345678901234567890123456789012345678901234567890123456789012345678901234567890")[..],
+        &hex::decode("466ef18babb0154d25b9d38a6414f5c08784372bccb204d6549c4afadb601429\
+4d5bd8df2a6c44e538cd047b2681a51a2c60481e88c5a20b2c2a80cf3a9a083b").unwrap()[..]);
+
+    let msg = vec![b'a'; 1000_000];
+    let mut m = Whirlpool::new();
+    for chunk in msg.chunks(1000 - 1) {
+        m.update(chunk);
+    }
+    m.finalize();
+    assert_eq!(&m.output()[..],
+        &hex::decode("0c99005beb57eff50a7cf005560ddf5d29057fd86b20bfd62deca0f1ccea4af5\
+1fc15490eddc47af32bb2b66c34ff9ad8c6008ad677f77126953b226e4ed8b01").unwrap()[..]);
+}
@@ -6,12 +6,16 @@
 // https://github.com/casebeer/python-hkdf/blob/master/hkdf.py
 // https://en.wikipedia.org/wiki/HKDF#Example:_Python_implementation
 
-use crate::hmac::Hmac;
+use crate::hmac::{Hmac, HmacState};
+use crate::error::InvalidOutputLen;
 use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
-use crate::hash::{Md2, Md4, Md5, Sm3, Sha1, Sha256, Sha384, Sha512, };
+
+#[cfg(test)]
+use crate::hash::{Sha1, Sha256, };
 
 
-pub trait Hkdf: Hmac {
+/// NOTE: HKDF-Expand 在计算每个 T(i) 时复用同一个 HMAC 状态，所以要求 Hasher 实现 `Clone`。
+pub trait Hkdf: Hmac + BuildCryptoHasher<Hasher: Clone> {
     // Inputs:
     //      salt     optional salt value (a non-secret random value);
     //               if not provided, it is set to a string of HashLen zeros.
@@ -31,77 +35,121 @@ pub trait Hkdf: Hmac {
     // Output:
     //      OKM      output keying material (of L octets)
     fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Vec<u8>;
+
+    /// 不分配内存的 HKDF-Expand，输出长度由 `okm.len()` 决定。
+    fn hkdf_expand_into(prk: &[u8], info: &[u8], okm: &mut [u8]) -> Result<(), InvalidOutputLen>;
 }
 
-macro_rules! impl_hkdf {
-    ($hasher:ident) => {
-        impl Hkdf for $hasher {
-            fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::Output {
-                // PRK = HMAC-Hash(salt, IKM)
-                if salt.is_empty() {
-                    let salt = [0u8; <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN];
-                    $hasher::hmac(&salt[..], ikm)
-                } else {
-                    $hasher::hmac(salt, ikm)
-                }
-            }
-            
-            fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
-                assert!(len <= <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN * 255);
-                // N = ceil(L/HashLen)
-                // T = T(1) | T(2) | T(3) | ... | T(N)
-                // OKM = first L octets of T
-                // 
-                // where:
-                // T(0) = empty string (zero length)
-                // T(1) = HMAC-Hash(PRK, T(0) | info | 0x01)
-                // T(2) = HMAC-Hash(PRK, T(1) | info | 0x02)
-                // T(3) = HMAC-Hash(PRK, T(2) | info | 0x03)
-                // ...
-                // 
-                // (where the constant concatenated to the end of each T(n) is a
-                // single octet.)
-                let mut n = len / <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
-                if len % <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN != 0 {
-                    // ceil
-                    n += 1;
-                }
-                assert!(n <= std::u8::MAX as usize);
-
-                let mut t = [0u8; <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN];
-                let mut okm: Vec<u8> = Vec::with_capacity(n * <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN);
-
-                let n = n as u8;
-                for i in 0..n {
-                    if i == 0 {
-                        t = $hasher::hmac_inner(prk, &[], Some(info), Some(i + 1));
-                    } else {
-                        t = $hasher::hmac_inner(prk, &t[..], Some(info), Some(i + 1));
-                    }
-                    okm.extend_from_slice(&t.array_as_slice());
-                }
+// NOTE: 对所有实现了 `Hmac` 的类型自动实现 HKDF。
+impl<H: Hmac> Hkdf for H
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::Output {
+        // PRK = HMAC-Hash(salt, IKM)
+        if salt.is_empty() {
+            let salt = vec![0u8; <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN];
+            Self::hmac(&salt[..], ikm)
+        } else {
+            Self::hmac(salt, ikm)
+        }
+    }
 
-                okm.truncate(len);
+    fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+        let hash_len = <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+        assert!(len <= hash_len * 255);
 
-                okm
-            }
-        }
+        let mut okm = vec![0u8; len];
+        Self::hkdf_expand_into(prk, info, &mut okm).unwrap();
+
+        okm
+    }
+
+    fn hkdf_expand_into(prk: &[u8], info: &[u8], okm: &mut [u8]) -> Result<(), InvalidOutputLen> {
+        HkdfExpander::<Self>::new(prk, &[info]).read(okm)
     }
 }
 
-impl_hkdf!(Md2);
-impl_hkdf!(Md4);
-impl_hkdf!(Md5);
-impl_hkdf!(Sm3);
-impl_hkdf!(Sha1);
 
-// SHA-2
-impl_hkdf!(Sha256);
-impl_hkdf!(Sha384);
-impl_hkdf!(Sha512);
+/// 可以逐步读取 OKM 的 HKDF-Expand，`info` 可以由多个片段拼接而成。
+///
+/// NOTE: 不会分配内存，`info` 的各个片段在计算每个 T(i) 时依次输入 HMAC。
+///       PRK 只在 `new` 中处理一次，之后每个 T(i) 都复用已经吸收了 ipad/opad 的状态。
+pub struct HkdfExpander<'a, H: Hmac> {
+    prf: HmacState<H>,
+    info: &'a [&'a [u8]],
+    t: Option<<<H as BuildCryptoHasher>::Hasher as CryptoHasher>::Output>,
+    counter: u8,
+    offset: usize,
+    consumed: usize,
+}
+
+impl<'a, H: Hmac> HkdfExpander<'a, H>
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    pub const MAX_OUTPUT_LEN: usize = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN * 255;
+
 
-// SHA-3
+    pub fn new(prk: &'a [u8], info: &'a [&'a [u8]]) -> Self {
+        let prf = HmacState::<H>::new(prk);
+        Self { prf, info, t: None, counter: 0, offset: 0, consumed: 0 }
+    }
 
+    /// 剩余可以读取的字节数
+    pub fn remaining(&self) -> usize {
+        Self::MAX_OUTPUT_LEN - self.consumed
+    }
+
+    /// 读取后续的 OKM；超出 255 * HashLen 时返回错误，并且不会输出任何数据。
+    pub fn read(&mut self, output: &mut [u8]) -> Result<(), InvalidOutputLen> {
+        if output.len() > self.remaining() {
+            return Err(InvalidOutputLen);
+        }
+
+        let hash_len = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+
+        // N = ceil(L/HashLen)
+        // T = T(1) | T(2) | T(3) | ... | T(N)
+        // OKM = first L octets of T
+        // 
+        // where:
+        // T(0) = empty string (zero length)
+        // T(1) = HMAC-Hash(PRK, T(0) | info | 0x01)
+        // T(2) = HMAC-Hash(PRK, T(1) | info | 0x02)
+        // T(3) = HMAC-Hash(PRK, T(2) | info | 0x03)
+        // ...
+        // 
+        // (where the constant concatenated to the end of each T(n) is a
+        // single octet.)
+        let mut pos = 0;
+        while pos < output.len() {
+            if self.t.is_none() || self.offset == hash_len {
+                let mut m = self.prf.clone();
+                if let Some(ref t) = self.t {
+                    m.update(t.array_as_slice());
+                }
+                for data in self.info.iter() {
+                    m.update(data);
+                }
+                self.counter += 1;
+                m.update(&[self.counter]);
+
+                self.t = Some(m.finalize());
+                self.offset = 0;
+            }
+
+            let t = self.t.as_ref().unwrap().array_as_slice();
+            let n = std::cmp::min(hash_len - self.offset, output.len() - pos);
+            output[pos..pos + n].copy_from_slice(&t[self.offset..self.offset + n]);
+            self.offset += n;
+            pos += n;
+        }
+        self.consumed += output.len();
+
+        Ok(())
+    }
+}
 
 
 #[cfg(test)]
@@ -269,3 +317,41 @@ b3bae548aa53d423b0d1f27ebba6f5e5\
 ");
 }
 
+#[test]
+fn test_hkdf_expand_into() {
+    // A.2.  Test Case 2
+    // https://tools.ietf.org/html/rfc5869#appendix-A.2
+    let prk = hexdecode("06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244");
+    let info = hexdecode("b0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecf\
+d0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeef\
+f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+    let okm = hexdecode("b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+cc30c58179ec3e87c14c01d5c1f3434f1d87");
+
+    let mut out = [0u8; 82];
+    Sha256::hkdf_expand_into(&prk, &info, &mut out).unwrap();
+    assert_eq!(&out[..], &okm[..]);
+
+    // info 分成多个片段，并分多次读取
+    let parts = [&info[..7], &info[7..7], &info[7..50], &info[50..]];
+    let mut expander = HkdfExpander::<Sha256>::new(&prk, &parts);
+    let mut out = [0u8; 82];
+    for (start, end) in [(0, 1), (1, 32), (32, 33), (33, 70), (70, 82)].iter() {
+        expander.read(&mut out[*start..*end]).unwrap();
+    }
+    assert_eq!(&out[..], &okm[..]);
+    assert_eq!(expander.remaining(), 255 * 32 - 82);
+
+    // 超出 255 * HashLen
+    let mut out = vec![0u8; 255 * 32 + 1];
+    assert_eq!(Sha256::hkdf_expand_into(&prk, &info, &mut out), Err(InvalidOutputLen));
+    assert_eq!(Sha256::hkdf_expand_into(&prk, &info, &mut out[..255 * 32]), Ok(()));
+    assert_eq!(&out[..82], &okm[..]);
+
+    let mut expander = HkdfExpander::<Sha256>::new(&prk, &parts);
+    expander.read(&mut out[..255 * 32 - 1]).unwrap();
+    assert_eq!(expander.read(&mut [0u8; 2]), Err(InvalidOutputLen));
+    assert_eq!(expander.read(&mut [0u8; 1]), Ok(()));
+    assert_eq!(expander.remaining(), 0);
+}
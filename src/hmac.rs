@@ -5,7 +5,13 @@
 // https://github.com/python/cpython/blob/3.8/Lib/hmac.py
 // https://en.wikipedia.org/wiki/HMAC#Implementation
 use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
-use crate::hash::{Md2, Md4, Md5, Sm3, Sha1, Sha256, Sha384, Sha512, };
+
+#[cfg(test)]
+use crate::hash::{Md5, Sha1, Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256, };
+#[cfg(test)]
+use crate::hash::{Sha3_224, Sha3_256, Sha3_384, Sha3_512, };
+#[cfg(test)]
+use crate::hash::{Blake2b512, Blake2s256, Blake3, Ripemd160, Streebog256, Streebog512, Whirlpool, };
 
 
 const IPAD: u8 = 0x36;
@@ -21,72 +27,97 @@ pub trait Hmac: BuildCryptoHasher + CryptoHasher {
     fn hmac_inner(key: &[u8], data1: &[u8], data2: Option<&[u8]>, data3: Option<u8>) -> <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::Output;
 }
 
-macro_rules! impl_hmac {
-    ($hasher:path) => {
-        impl Hmac for $hasher {
-            #[doc(hidden)]
-            fn hmac_inner(key: &[u8], data1: &[u8], data2: Option<&[u8]>, data3: Option<u8>) -> <Self as CryptoHasher>::Output {
-                if key.len() > <Self as CryptoHasher>::BLOCK_LEN {
-                    let mut h = Self::build_hasher();
-                    h.write(key);
-                    // h.finish();
-                    let new_key = h.digest();
-                    return Self::hmac_inner(new_key.array_as_slice(), data1, data2, data3);
-                }
-
-                let mut ikey = [0u8; <Self as CryptoHasher>::BLOCK_LEN];
-                let mut okey = [0u8; <Self as CryptoHasher>::BLOCK_LEN];
-
-                ikey[..key.len()].copy_from_slice(key);
-                okey[..key.len()].copy_from_slice(key);
-                for idx in 0..<Self as CryptoHasher>::BLOCK_LEN {
-                    ikey[idx] ^= IPAD;
-                    okey[idx] ^= OPAD;
-                }
-
-                // h1 = hash(ipad || message)
-                let mut h = Self::build_hasher();
-                h.write(&ikey[..]);
-                h.write(&data1);
-                if let Some(ref data2) = data2 {
-                    h.write(data2);
-                }
-                if let Some(data3) = data3 {
-                    h.write(&[data3]);
-                }
-                // h.finish();
-                let h1 = h.digest();
-
-                // h2 = hash(opad || h1)
-                let mut h = Self::build_hasher();
-                h.write(&okey[..]);
-                h.write(&h1[..]);
-                // h.finish();
-                let h2 = h.digest();
-
-                // return hash(opad || hash(ipad || message)) // Where || is concatenation
-                return h2;
-            }
+// NOTE: 对所有实现了 `BuildCryptoHasher + CryptoHasher` 的类型（包括外部定义的哈希函数）自动实现 HMAC。
+impl<H: BuildCryptoHasher + CryptoHasher> Hmac for H {
+    #[doc(hidden)]
+    fn hmac_inner(key: &[u8], data1: &[u8], data2: Option<&[u8]>, data3: Option<u8>) -> <<Self as BuildCryptoHasher>::Hasher as CryptoHasher>::Output {
+        // return hash(opad || hash(ipad || message)) // Where || is concatenation
+        let mut m = HmacState::<Self>::new(key);
+        m.update(data1);
+        if let Some(data2) = data2 {
+            m.update(data2);
+        }
+        if let Some(data3) = data3 {
+            m.update(&[data3]);
         }
+        m.finalize()
+    }
+}
+
+
+/// 支持流式输入的 HMAC
+///
+/// NOTE: Key 只在 `new` 时处理一次，之后可以 `clone` 复用已经吸收了 ipad/opad 的哈希状态。
+pub struct HmacState<H: Hmac> {
+    inner: <H as BuildCryptoHasher>::Hasher,   // hash(ipad || message)
+    outer: <H as BuildCryptoHasher>::Hasher,   // hash(opad || ...)
+}
+
+impl<H: Hmac> Clone for HmacState<H>
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), outer: self.outer.clone() }
     }
 }
 
-impl_hmac!(Md2);
-impl_hmac!(Md4);
-impl_hmac!(Md5);
-impl_hmac!(Sm3);
-impl_hmac!(Sha1);
+impl<H: Hmac> HmacState<H> {
+    pub const BLOCK_LEN: usize = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::BLOCK_LEN;
+    pub const TAG_LEN: usize   = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
 
-// SHA-2
-impl_hmac!(Sha256);
-impl_hmac!(Sha384);
-impl_hmac!(Sha512);
 
-// SHA-3
+    pub fn new(key: &[u8]) -> Self {
+        if key.len() > Self::BLOCK_LEN {
+            let mut h = H::build_hasher();
+            h.write(key);
+            let new_key = h.digest();
+            return Self::new(new_key.array_as_slice());
+        }
+
+        // NOTE: 分组长度由 Hasher 决定（包括外部定义的 Hasher），
+        //       所以 (K ^ ipad) 和 (K ^ opad) 借助栈上的小缓冲区分段写入，不需要分配内存。
+        let mut inner = H::build_hasher();
+        let mut outer = H::build_hasher();
 
+        let mut ibuf = [0u8; 64];
+        let mut obuf = [0u8; 64];
+        let mut pos = 0;
+        while pos < Self::BLOCK_LEN {
+            let n = std::cmp::min(ibuf.len(), Self::BLOCK_LEN - pos);
+            for (i, (a, b)) in ibuf.iter_mut().zip(obuf.iter_mut()).take(n).enumerate() {
+                let k = key.get(pos + i).copied().unwrap_or(0);
+                *a = IPAD ^ k;
+                *b = OPAD ^ k;
+            }
+            inner.write(&ibuf[..n]);
+            outer.write(&obuf[..n]);
+            pos += n;
+        }
+
+        Self { inner, outer }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.write(data);
+    }
+
+    pub fn finalize(self) -> <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::Output {
+        let h1 = self.inner.digest();
+
+        let mut h = self.outer;
+        h.write(h1.array_as_slice());
+        h.digest()
+    }
+
+    /// 以常量时间比较 Tag
+    pub fn verify(self, tag: &[u8]) -> bool {
+        let output = self.finalize();
+
+        bool::from(subtle::ConstantTimeEq::ct_eq(output.array_as_slice(), tag))
+    }
+}
 
-// TODO: hmac-drbg
-// https://github.com/sorpaas/rust-hmac-drbg/blob/master/src/lib.rs
 
 
 // HMAC_MD5("key", "The quick brown fox jumps over the lazy dog")    = 80070713463e7749b90c2dc24911e275
@@ -119,6 +150,16 @@ fn test_hmac_sha1() {
     assert_eq!(&hex::encode(&Sha1::hmac(key, data)), result);
 }
 
+#[test]
+fn test_hmac_sha2_224() {
+    // 4.3.  Test Case 2
+    // https://tools.ietf.org/html/rfc4231#section-4.3
+    let key = b"Jefe";
+    let data = b"what do ya want for nothing?";
+    let result = "a30e01098bc6dbbf45690f3a7e9e6d0f8bbea2a39e6148008fd05e44";
+
+    assert_eq!(&hex::encode(&Sha224::hmac(key, data)), result);
+}
 #[test]
 fn test_hmac_sha2_256() {
     let key = b"key";
@@ -142,4 +183,242 @@ fn test_hmac_sha2_512() {
     let result = "b42af09057bac1e2d41708e48a902e09b5ff7f12ab428a4fe86653c73dd248fb82f948a549f7b791a5b41915ee4d1ec3935357e4e2317250d0372afa2ebeeb3a";
 
     assert_eq!(&hex::encode(&Sha512::hmac(key, data)), result);
-}
\ No newline at end of file
+}
+#[test]
+fn test_hmac_sha2_512_truncated() {
+    let key = b"Jefe";
+    let data = b"what do ya want for nothing?";
+
+    assert_eq!(&hex::encode(&Sha512_224::hmac(key, data)),
+        "4a530b31a79ebcce36916546317c45f247d83241dfb818fd37254bde");
+    assert_eq!(&hex::encode(&Sha512_256::hmac(key, data)),
+        "6df7b24630d5ccb2ee335407081a87188c221489768fa2020513b2d593359456");
+}
+
+#[test]
+fn test_hmac_sha3() {
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    assert_eq!(&hex::encode(&Sha3_224::hmac(key, data)), 
+        "ff6fa8447ce10fb1efdccfe62caf8b640fe46c4fb1007912bf85100f");
+    assert_eq!(&hex::encode(&Sha3_256::hmac(key, data)), 
+        "8c6e0683409427f8931711b10ca92a506eb1fafa48fadd66d76126f47ac2c333");
+    assert_eq!(&hex::encode(&Sha3_384::hmac(key, data)), 
+        "aa739ad9fcdf9be4a04f06680ade7a1bd1e01a0af64accb04366234cf9f6934a0f8589772f857681fcde8acc256091a2");
+    assert_eq!(&hex::encode(&Sha3_512::hmac(key, data)), 
+        "237a35049c40b3ef5ddd960b3dc893d8284953b9a4756611b1b61bffcf53edd979f93547db714b06ef0a692062c609b70208ab8d4a280ceee40ed8100f293063");
+}
+
+#[test]
+fn test_hmac_blake2() {
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    assert_eq!(&hex::encode(&Blake2b512::hmac(key, data)), 
+        "92294f92c0dfb9b00ec9ae8bd94d7e7d8a036b885a499f149dfe2fd2199394aaaf6b8894a1730cccb2cd050f9bcf5062a38b51b0dab33207f8ef35ae2c9df51b");
+    assert_eq!(&hex::encode(&Blake2s256::hmac(key, data)), 
+        "f93215bb90d4af4c3061cd932fb169fb8bb8a91d0b4022baea1271e1323cd9a0");
+}
+
+#[test]
+fn test_hmac_whirlpool() {
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    assert_eq!(&hex::encode(&Whirlpool::hmac(key, data)),
+        "7f7192e3a155cb6a8171584ba146882f26821658112dfd2601272db013517a31\
+e573637d146584596f86a884eb0decc9514dde000ecf2476dc5d436a92197527");
+}
+
+#[test]
+fn test_hmac_ripemd160() {
+    // Test Cases for HMAC-RIPEMD160 and HMAC-RIPEMD128
+    // https://tools.ietf.org/html/rfc2286#section-2
+    assert_eq!(&hex::encode(&Ripemd160::hmac(&[0x0b; 20], b"Hi There")),
+        "24cb4bd67d20fc1a5d2ed7732dcc39377f0a5668");
+    assert_eq!(&hex::encode(&Ripemd160::hmac(b"Jefe", b"what do ya want for nothing?")),
+        "dda6c0213a485a9e24f4742064a7f033b43c4069");
+}
+
+#[test]
+fn test_hmac_streebog() {
+    // 4.1.1.  HMAC_GOSTR3411_2012_256
+    // https://tools.ietf.org/html/rfc7836#section-4.1.1
+    let key = (0u8..32).collect::<Vec<u8>>();
+    let data = hex::decode("0126bdb87800af214341456563780100").unwrap();
+
+    assert_eq!(&hex::encode(&Streebog256::hmac(&key, &data)),
+        "a1aa5f7de402d7b3d323f2991c8d4534013137010a83754fd0af6d7cd4922ed9");
+    // 4.1.2.  HMAC_GOSTR3411_2012_512
+    assert_eq!(&hex::encode(&Streebog512::hmac(&key, &data)),
+        "a59bab22ecae19c65fbde6e5f4e9f5d8549d31f037f9df9b905500e171923a77\
+3d5f1530f2ed7e964cb2eedc29e9ad2f3afe93b2814f79f5000ffc0366c251e6");
+}
+
+#[test]
+fn test_hmac_state() {
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    let mut m = HmacState::<Sha256>::new(key);
+    for chunk in data.chunks(7) {
+        m.update(chunk);
+    }
+    assert_eq!(&hex::encode(&m.finalize()),
+        "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+
+    // NOTE: Key 处理完之后 clone，两份状态互不影响。
+    let keyed = HmacState::<Sha1>::new(key);
+    let mut m1 = keyed.clone();
+    let mut m2 = keyed.clone();
+    m1.update(data);
+    m2.update(b"Hi There");
+    assert_eq!(&m1.finalize()[..], &Sha1::hmac(key, data)[..]);
+    assert_eq!(&m2.finalize()[..], &Sha1::hmac(key, b"Hi There")[..]);
+
+    // 4.8.  Test Case 7 (Key 长于分组长度)
+    // https://tools.ietf.org/html/rfc4231#section-4.8
+    let key = [0xaa; 131];
+    let data = b"This is a test using a larger than block-size key and a larger than block-size data. \
+The key needs to be hashed before being used by the HMAC algorithm.";
+    let mut m = HmacState::<Sha512>::new(&key);
+    m.update(&data[..100]);
+    m.update(&data[100..]);
+    assert_eq!(&hex::encode(&m.finalize()[..]),
+        "e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944\
+b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58");
+
+    let mut m = HmacState::<Sha3_256>::new(b"key");
+    m.update(data);
+    assert_eq!(&m.finalize()[..], &Sha3_256::hmac(b"key", data)[..]);
+}
+
+#[test]
+fn test_hmac_state_verify() {
+    let key = b"Jefe";
+    let data = b"what do ya want for nothing?";
+    let tag = hex::decode("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843").unwrap();
+
+    let mut m = HmacState::<Sha256>::new(key);
+    m.update(data);
+    assert!(m.clone().verify(&tag));
+    assert!(!m.clone().verify(&tag[..31]));
+
+    let mut bad_tag = tag.clone();
+    bad_tag[0] ^= 1;
+    assert!(!m.verify(&bad_tag));
+}
+
+#[test]
+fn test_hmac_blake3() {
+    // NOTE: BLAKE3 没有单独的 impl_hmac，HMAC 由泛型实现自动提供。
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    assert_eq!(&hex::encode(&Blake3::hmac(key, data)),
+        "3742da5c89b7c0c376c0af2f211bd59f97aeaa282f21dccb0c0308b7703ac959");
+}
+
+#[cfg(test)]
+#[derive(Clone)]
+struct MyHasher {
+    inner: Sha256,
+}
+
+#[cfg(test)]
+impl CryptoHasher for MyHasher {
+    const BLOCK_LEN : usize = Sha256::BLOCK_LEN;
+    const OUTPUT_LEN: usize = Sha256::DIGEST_LEN;
+
+    type Output = [u8; Sha256::DIGEST_LEN];
+
+    fn digest(self) -> Self::Output {
+        self.inner.digest()
+    }
+
+    fn write<T: AsRef<[u8]>>(&mut self, bytes: T) {
+        self.inner.update(bytes.as_ref());
+    }
+
+    fn oneshot<T: AsRef<[u8]>>(data: T) -> Self::Output {
+        Sha256::oneshot(data)
+    }
+}
+
+#[cfg(test)]
+impl BuildCryptoHasher for MyHasher {
+    type Hasher = Self;
+
+    fn build_hasher() -> Self::Hasher {
+        Self { inner: Sha256::new() }
+    }
+}
+
+#[test]
+fn test_hmac_user_defined_hasher() {
+    use crate::hkdf::Hkdf;
+
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+
+    assert_eq!(&hex::encode(&MyHasher::hmac(key, data)),
+        "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8");
+
+    let mut m = HmacState::<MyHasher>::new(key);
+    m.update(data);
+    assert!(m.verify(&Sha256::hmac(key, data)));
+
+    let prk = MyHasher::hkdf_extract(b"salt", b"ikm");
+    assert_eq!(&prk[..], &Sha256::hkdf_extract(b"salt", b"ikm")[..]);
+    assert_eq!(MyHasher::hkdf_expand(&prk, b"info", 100), Sha256::hkdf_expand(&prk, b"info", 100));
+}
+
+// NOTE: 分组长度超过所有内置哈希函数的 Hasher（SHA-256 的压缩函数，分组长度声明为 512 Bytes）。
+#[cfg(test)]
+struct BigBlockHasher {
+    inner: Sha256,
+}
+
+#[cfg(test)]
+impl CryptoHasher for BigBlockHasher {
+    const BLOCK_LEN : usize = 512;
+    const OUTPUT_LEN: usize = Sha256::DIGEST_LEN;
+
+    type Output = [u8; Sha256::DIGEST_LEN];
+
+    fn digest(self) -> Self::Output {
+        self.inner.digest()
+    }
+
+    fn write<T: AsRef<[u8]>>(&mut self, bytes: T) {
+        self.inner.update(bytes.as_ref());
+    }
+
+    fn oneshot<T: AsRef<[u8]>>(data: T) -> Self::Output {
+        Sha256::oneshot(data)
+    }
+}
+
+#[cfg(test)]
+impl BuildCryptoHasher for BigBlockHasher {
+    type Hasher = Self;
+
+    fn build_hasher() -> Self::Hasher {
+        Self { inner: Sha256::new() }
+    }
+}
+
+#[test]
+fn test_hmac_large_block_hasher() {
+    // NOTE: 与 Python 按照 RFC 2104 的定义（B = 512）计算的结果一致。
+    let key = b"key";
+    let data = b"The quick brown fox jumps over the lazy dog";
+    assert_eq!(&hex::encode(&BigBlockHasher::hmac(key, data)),
+        "2fef8c6f5531fcd1a182b9705c731eccf9c83979f0f9447cbd2732c59a2f4647");
+
+    // Key 的长度超过分组长度时先计算哈希
+    let key = [0xaa; 600];
+    assert_eq!(&hex::encode(&BigBlockHasher::hmac(&key, b"data")),
+        "26927d51843cdead138b8f818c9208ea07c67144954e6ffd89312d47b71e78da");
+}
@@ -0,0 +1,419 @@
+// NIST Special Publication 800-108r1
+// Recommendation for Key Derivation Using Pseudorandom Functions
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-108r1-upd1.pdf
+//
+// Key-Based Key Derivation Functions (KBKDF)：
+//     Counter Mode、Feedback Mode、Double-Pipeline Iteration Mode
+use crate::hmac::{Hmac, HmacState};
+use crate::cmac::Cmac;
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
+use crate::blockcipher::BlockCipher;
+use crate::error::{InvalidKeyLen, InvalidParams};
+
+
+// NOTE: 目前输出最长的是 SHA-512 等 512 比特的哈希函数。
+const MAX_PRF_OUTPUT_LEN: usize = 64;
+
+
+/// SP 800-108 中使用的 PRF（HMAC 或者 CMAC）
+///
+/// NOTE: `K_I` 只在 `prf_new` 中处理一次，之后每个 K(i) 都从已经处理过密钥的状态 `clone` 出来。
+pub trait KbkdfPrf {
+    type Prf: Clone;
+    /// PRF 的输出长度 h（Bytes）
+    const PRF_OUTPUT_LEN: usize;
+
+    fn prf_new(key: &[u8]) -> Result<Self::Prf, InvalidKeyLen>;
+    fn prf_update(prf: &mut Self::Prf, data: &[u8]);
+    fn prf_finalize(prf: Self::Prf, output: &mut [u8]);
+}
+
+// NOTE: 对所有实现了 `Hmac` 的类型（Hasher 需要实现 `Clone`）自动实现。
+impl<H: Hmac> KbkdfPrf for H
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    type Prf = HmacState<H>;
+    const PRF_OUTPUT_LEN: usize = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+
+    fn prf_new(key: &[u8]) -> Result<Self::Prf, InvalidKeyLen> {
+        Ok(HmacState::<H>::new(key))
+    }
+
+    fn prf_update(prf: &mut Self::Prf, data: &[u8]) {
+        prf.update(data);
+    }
+
+    fn prf_finalize(prf: Self::Prf, output: &mut [u8]) {
+        output.copy_from_slice(prf.finalize().array_as_slice());
+    }
+}
+
+impl<C: BlockCipher + Clone> KbkdfPrf for Cmac<C> {
+    type Prf = Cmac<C>;
+    const PRF_OUTPUT_LEN: usize = Cmac::<C>::TAG_LEN;
+
+    fn prf_new(key: &[u8]) -> Result<Self::Prf, InvalidKeyLen> {
+        if C::BLOCK_LEN != Cmac::<C>::BLOCK_LEN || key.len() != Cmac::<C>::KEY_LEN {
+            return Err(InvalidKeyLen);
+        }
+        Ok(Cmac::<C>::new(key))
+    }
+
+    fn prf_update(prf: &mut Self::Prf, data: &[u8]) {
+        prf.update(data);
+    }
+
+    fn prf_finalize(prf: Self::Prf, output: &mut [u8]) {
+        output.copy_from_slice(&prf.finalize());
+    }
+}
+
+
+/// 计数器 [i]_2 在 PRF 输入中的位置
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CounterLocation {
+    /// [i]_2 || K(i-1) || FixedInputData（仅用于 Feedback / Double-Pipeline 模式）
+    BeforeIteration,
+    /// K(i-1) || [i]_2 || FixedInputData
+    BeforeFixedData,
+    /// K(i-1) || FixedInputData || [i]_2
+    AfterFixedData,
+    /// 插入在 FixedInputData 的第 N 个字节之前（仅用于 Counter 模式）
+    MiddleFixedData(usize),
+}
+
+/// KBKDF 的编码选项
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct KbkdfOptions {
+    /// 计数器 [i]_2 的长度（Bytes，1 ~ 4）。Feedback 与 Double-Pipeline 模式下可以为 0，表示不使用计数器。
+    pub counter_len: usize,
+    pub counter_location: CounterLocation,
+    /// 附加在 FixedInputData 之后的 [L]_2 的长度（Bytes，0 ~ 8），0 表示不附加。
+    pub l_len: usize,
+}
+
+impl Default for KbkdfOptions {
+    /// 32 比特的计数器位于 FixedInputData 之前，附加 32 比特的 [L]_2。
+    fn default() -> Self {
+        Self { counter_len: 4, counter_location: CounterLocation::BeforeFixedData, l_len: 4 }
+    }
+}
+
+impl KbkdfOptions {
+    fn check(&self, out_len: usize, prf_len: usize, counter_required: bool) -> Result<(), InvalidParams> {
+        if self.counter_len > 4 || (counter_required && self.counter_len == 0) || self.l_len > 8 {
+            return Err(InvalidParams);
+        }
+        if out_len == 0 || prf_len > MAX_PRF_OUTPUT_LEN {
+            return Err(InvalidParams);
+        }
+
+        // n = ceil(L / h)，n 不能超过 2^r - 1
+        let n = out_len.div_ceil(prf_len) as u64;
+        let r = if self.counter_len == 0 { 4 } else { self.counter_len };
+        if n > (1u64 << (r * 8)) - 1 {
+            return Err(InvalidParams);
+        }
+
+        // L 以比特为单位，必须能用 l_len 个字节表示
+        let l = (out_len as u128) * 8;
+        if self.l_len > 0 && self.l_len < 8 && l >= 1u128 << (self.l_len * 8) {
+            return Err(InvalidParams);
+        }
+
+        Ok(())
+    }
+}
+
+
+// 依次输入 FixedInputData 的各个片段（包括 [L]_2），并在 `split` 处插入计数器。
+fn update_fixed_data<P: KbkdfPrf>(prf: &mut P::Prf, fixed_data: &[&[u8]], l: &[u8], counter: &[u8], split: Option<usize>) {
+    let mut pos = 0usize;
+    let mut inserted = split.is_none();
+
+    for data in fixed_data.iter().chain([l].iter()) {
+        if !inserted {
+            let split = split.unwrap();
+            if split <= pos + data.len() {
+                let (a, b) = data.split_at(split - pos);
+                P::prf_update(prf, a);
+                P::prf_update(prf, counter);
+                P::prf_update(prf, b);
+                inserted = true;
+                pos += data.len();
+                continue;
+            }
+        }
+        P::prf_update(prf, data);
+        pos += data.len();
+    }
+}
+
+fn fixed_data_len(fixed_data: &[&[u8]], l_len: usize) -> usize {
+    fixed_data.iter().map(|data| data.len()).sum::<usize>() + l_len
+}
+
+fn encode_l(out_len: usize, l_len: usize, buf: &mut [u8; 8]) -> &[u8] {
+    *buf = ((out_len as u64) * 8).to_be_bytes();
+    &buf[8 - l_len..]
+}
+
+
+/// KDF in Counter Mode
+///
+/// K(i) := PRF (K_I, [i]_2 || FixedInputData)，其中 FixedInputData = `fixed_data` || [L]_2 。
+///
+/// NOTE: `fixed_data` 可以由多个片段组成，例如 `&[label, &[0x00], context]`。
+pub fn kbkdf_counter<P: KbkdfPrf>(key: &[u8], fixed_data: &[&[u8]], options: &KbkdfOptions, output: &mut [u8]) -> Result<(), InvalidParams> {
+    options.check(output.len(), P::PRF_OUTPUT_LEN, true)?;
+
+    let mut lbuf = [0u8; 8];
+    let l = encode_l(output.len(), options.l_len, &mut lbuf);
+
+    let split = match options.counter_location {
+        CounterLocation::BeforeIteration | CounterLocation::BeforeFixedData => None,
+        CounterLocation::AfterFixedData => Some(fixed_data_len(fixed_data, options.l_len)),
+        CounterLocation::MiddleFixedData(n) => {
+            if n > fixed_data_len(fixed_data, options.l_len) {
+                return Err(InvalidParams);
+            }
+            Some(n)
+        },
+    };
+
+    let keyed = P::prf_new(key).map_err(|_| InvalidParams)?;
+
+    let mut k = [0u8; MAX_PRF_OUTPUT_LEN];
+    let k = &mut k[..P::PRF_OUTPUT_LEN];
+
+    for (i, chunk) in output.chunks_mut(P::PRF_OUTPUT_LEN).enumerate() {
+        let counter = (i as u32 + 1).to_be_bytes();
+        let counter = &counter[4 - options.counter_len..];
+
+        let mut prf = keyed.clone();
+        if split.is_none() {
+            P::prf_update(&mut prf, counter);
+        }
+        update_fixed_data::<P>(&mut prf, fixed_data, l, counter, split);
+        P::prf_finalize(prf, k);
+
+        chunk.copy_from_slice(&k[..chunk.len()]);
+    }
+
+    Ok(())
+}
+
+// K(i) := PRF (K_I, {[i]_2 ||} iter {|| [i]_2} || FixedInputData {|| [i]_2})
+fn prf_with_iteration<P: KbkdfPrf>(keyed: &P::Prf, iter: &[u8], fixed_data: &[&[u8]], l: &[u8], counter: &[u8], location: CounterLocation, output: &mut [u8]) -> Result<(), InvalidParams> {
+    let mut prf = keyed.clone();
+    match location {
+        CounterLocation::BeforeIteration => {
+            P::prf_update(&mut prf, counter);
+            P::prf_update(&mut prf, iter);
+            update_fixed_data::<P>(&mut prf, fixed_data, l, &[], None);
+        },
+        CounterLocation::BeforeFixedData => {
+            P::prf_update(&mut prf, iter);
+            P::prf_update(&mut prf, counter);
+            update_fixed_data::<P>(&mut prf, fixed_data, l, &[], None);
+        },
+        CounterLocation::AfterFixedData => {
+            P::prf_update(&mut prf, iter);
+            update_fixed_data::<P>(&mut prf, fixed_data, l, &[], None);
+            P::prf_update(&mut prf, counter);
+        },
+        CounterLocation::MiddleFixedData(_) => return Err(InvalidParams),
+    }
+    P::prf_finalize(prf, output);
+
+    Ok(())
+}
+
+/// KDF in Feedback Mode
+///
+/// K(0) := IV，K(i) := PRF (K_I, K(i-1) {|| [i]_2} || FixedInputData)
+pub fn kbkdf_feedback<P: KbkdfPrf>(key: &[u8], iv: &[u8], fixed_data: &[&[u8]], options: &KbkdfOptions, output: &mut [u8]) -> Result<(), InvalidParams> {
+    options.check(output.len(), P::PRF_OUTPUT_LEN, false)?;
+
+    let mut lbuf = [0u8; 8];
+    let l = encode_l(output.len(), options.l_len, &mut lbuf);
+
+    let keyed = P::prf_new(key).map_err(|_| InvalidParams)?;
+
+    let mut k = [0u8; MAX_PRF_OUTPUT_LEN];
+    let k = &mut k[..P::PRF_OUTPUT_LEN];
+
+    for (i, chunk) in output.chunks_mut(P::PRF_OUTPUT_LEN).enumerate() {
+        let counter = (i as u32 + 1).to_be_bytes();
+        let counter = &counter[4 - options.counter_len..];
+
+        let mut prev = [0u8; MAX_PRF_OUTPUT_LEN];
+        let prev = if i == 0 {
+            iv
+        } else {
+            prev[..k.len()].copy_from_slice(k);
+            &prev[..k.len()]
+        };
+
+        prf_with_iteration::<P>(&keyed, prev, fixed_data, l, counter, options.counter_location, k)?;
+        chunk.copy_from_slice(&k[..chunk.len()]);
+    }
+
+    Ok(())
+}
+
+/// KDF in Double-Pipeline Iteration Mode
+///
+/// A(0) := FixedInputData，A(i) := PRF (K_I, A(i-1))，
+/// K(i) := PRF (K_I, A(i) {|| [i]_2} || FixedInputData)
+pub fn kbkdf_double_pipeline<P: KbkdfPrf>(key: &[u8], fixed_data: &[&[u8]], options: &KbkdfOptions, output: &mut [u8]) -> Result<(), InvalidParams> {
+    options.check(output.len(), P::PRF_OUTPUT_LEN, false)?;
+
+    let mut lbuf = [0u8; 8];
+    let l = encode_l(output.len(), options.l_len, &mut lbuf);
+
+    let keyed = P::prf_new(key).map_err(|_| InvalidParams)?;
+
+    let mut k = [0u8; MAX_PRF_OUTPUT_LEN];
+    let k = &mut k[..P::PRF_OUTPUT_LEN];
+    let mut a = [0u8; MAX_PRF_OUTPUT_LEN];
+    let a = &mut a[..P::PRF_OUTPUT_LEN];
+
+    for (i, chunk) in output.chunks_mut(P::PRF_OUTPUT_LEN).enumerate() {
+        let mut prf = keyed.clone();
+        if i == 0 {
+            update_fixed_data::<P>(&mut prf, fixed_data, l, &[], None);
+        } else {
+            P::prf_update(&mut prf, a);
+        }
+        P::prf_finalize(prf, a);
+
+        let counter = (i as u32 + 1).to_be_bytes();
+        let counter = &counter[4 - options.counter_len..];
+
+        prf_with_iteration::<P>(&keyed, a, fixed_data, l, counter, options.counter_location, k)?;
+        chunk.copy_from_slice(&k[..chunk.len()]);
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+fn test_key(len: usize) -> Vec<u8> {
+    (0..len as u8).collect()
+}
+
+#[test]
+fn test_kbkdf_counter() {
+    use crate::hash::{Sha256, Sha512};
+    use crate::blockcipher::Aes128;
+
+    let key = test_key(32);
+    let fixed_data: &[&[u8]] = &[b"label", &[0x00], b"context"];
+
+    let mut okm = [0u8; 42];
+    kbkdf_counter::<Sha256>(&key, fixed_data, &KbkdfOptions::default(), &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("b9cd5f6323f01f4680650855f1ebea9b4c54c08131b506fc28c856364a38a2f4\
+fb680c12ea51696887d9").unwrap()[..]);
+
+    let options = KbkdfOptions { counter_len: 2, counter_location: CounterLocation::AfterFixedData, l_len: 2 };
+    let mut okm = [0u8; 40];
+    kbkdf_counter::<Cmac<Aes128>>(&key[..16], fixed_data, &options, &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("e76bb6c4e343b8a8ad96c81e416f0449cec218f9601f7996438336a9720989b0\
+8a0a6ec31256bcdd").unwrap()[..]);
+
+    // 不附加 [L]_2，不使用分隔符
+    let options = KbkdfOptions { counter_len: 2, counter_location: CounterLocation::BeforeFixedData, l_len: 0 };
+    let mut okm = [0u8; 40];
+    kbkdf_counter::<Cmac<Aes128>>(&key[..16], &[b"label", b"context"], &options, &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("61edc44f2e34cdf15bb4fbfd0974e961070657eb5878032a71aed04e854e96c2\
+5dcce76bf080b45e").unwrap()[..]);
+
+    // 计数器位于 FixedInputData 中间
+    let fixed: Vec<u8> = (100..140).collect();
+    let options = KbkdfOptions { counter_len: 1, counter_location: CounterLocation::MiddleFixedData(5), l_len: 0 };
+    let mut okm = [0u8; 100];
+    kbkdf_counter::<Sha512>(&key, &[&fixed[..3], &fixed[3..7], &fixed[7..]], &options, &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("269d01d8ca60f36fa4220327e9eaee0433ba14f6d2e3ea2f7b1620ad83668609\
+b4e746f99d2035b30bdc2261251cab867c915ed068803ef2a09e43379191aa65\
+a4b95a7d6f8853b0f6beb0a9f102f181b4295fbb46d8e7eae4c48d2e97abbb54\
+f2200f32").unwrap()[..]);
+}
+
+#[test]
+fn test_kbkdf_feedback() {
+    use crate::hash::Sha256;
+    use crate::blockcipher::Aes128;
+
+    let key = test_key(32);
+    let fixed_data: &[&[u8]] = &[b"label", &[0x00], b"context"];
+
+    let iv: Vec<u8> = (200..232).collect();
+    let mut okm = [0u8; 42];
+    kbkdf_feedback::<Sha256>(&key, &iv, fixed_data, &KbkdfOptions::default(), &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("8a9e98a64b5c7abc11e1300dd19a36decb4d855645f12cb5d8cf16695c0692a2\
+036c8162f92218f5bdc8").unwrap()[..]);
+
+    let options = KbkdfOptions { counter_len: 1, ..KbkdfOptions::default() };
+    let mut okm = [0u8; 40];
+    kbkdf_feedback::<Cmac<Aes128>>(&key[..16], &iv[..16], fixed_data, &options, &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("c9c93b1d7d3a6bc9576fd2c3b74785a6269f7f25544313b2079c99104f2ddeaf\
+dc33f901a089396d").unwrap()[..]);
+
+    // 不使用计数器，IV 为空
+    let options = KbkdfOptions { counter_len: 0, l_len: 0, ..KbkdfOptions::default() };
+    let mut okm = [0u8; 70];
+    kbkdf_feedback::<Sha256>(&key, &[], fixed_data, &options, &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("8f59e7b5a0844bdf632c592f5c0507cab2da64b1cf89506bf2118baed8279ba7\
+7f73671b4e6c312d6c6e7e41af209da1bfc2e510bdd4a9f7e9f51e5b8f617ae0\
+6f3efd5d3cad").unwrap()[..]);
+}
+
+#[test]
+fn test_kbkdf_double_pipeline() {
+    use crate::hash::Sha256;
+
+    let key = test_key(32);
+    let fixed_data: &[&[u8]] = &[b"label", &[0x00], b"context"];
+
+    let mut okm = [0u8; 80];
+    kbkdf_double_pipeline::<Sha256>(&key, fixed_data, &KbkdfOptions::default(), &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("35e297f71a608735462708a2acfcd32b04670e15f391bf71c25007e3c7197844\
+e358b452f58f0e99c9c72b52cf05a8118343c4f6c27cf10f08d1740a042dba90\
+dae53703894d5a4400f069d177cd17d4").unwrap()[..]);
+
+    let options = KbkdfOptions { counter_len: 0, l_len: 2, ..KbkdfOptions::default() };
+    let mut okm = [0u8; 50];
+    kbkdf_double_pipeline::<Sha256>(&key, fixed_data, &options, &mut okm).unwrap();
+    assert_eq!(&okm[..], &hex::decode("a2d9629919929c7b9b45091bea8ad3362d93f531f2c679a15702909d7b31d2e5\
+a9742b9567f2dcf6ef4869dcf8ed3c8fdd9f").unwrap()[..]);
+}
+
+#[test]
+fn test_kbkdf_invalid_params() {
+    use crate::hash::Sha256;
+    use crate::blockcipher::Aes128;
+
+    let key = test_key(32);
+    let mut okm = [0u8; 32];
+
+    // Counter 模式必须使用计数器
+    let options = KbkdfOptions { counter_len: 0, ..KbkdfOptions::default() };
+    assert_eq!(kbkdf_counter::<Sha256>(&key, &[], &options, &mut okm), Err(InvalidParams));
+    // 8 比特的计数器最多输出 255 个块
+    let options = KbkdfOptions { counter_len: 1, ..KbkdfOptions::default() };
+    assert_eq!(kbkdf_counter::<Sha256>(&key, &[], &options, &mut vec![0u8; 32 * 256]), Err(InvalidParams));
+    assert_eq!(kbkdf_counter::<Sha256>(&key, &[], &options, &mut vec![0u8; 32 * 255]), Ok(()));
+    // [L]_2 放不下
+    let options = KbkdfOptions { l_len: 1, ..KbkdfOptions::default() };
+    assert_eq!(kbkdf_counter::<Sha256>(&key, &[], &options, &mut okm), Err(InvalidParams));
+    // 计数器的位置超出 FixedInputData
+    let options = KbkdfOptions { counter_location: CounterLocation::MiddleFixedData(12), ..KbkdfOptions::default() };
+    assert_eq!(kbkdf_counter::<Sha256>(&key, &[b"label"], &options, &mut okm), Err(InvalidParams));
+    assert_eq!(kbkdf_feedback::<Sha256>(&key, &[], &[b"label"], &options, &mut okm), Err(InvalidParams));
+    // CMAC 的密钥长度
+    assert_eq!(kbkdf_counter::<Cmac<Aes128>>(&key, &[], &KbkdfOptions::default(), &mut okm), Err(InvalidParams));
+    assert_eq!(kbkdf_counter::<Sha256>(&key, &[], &KbkdfOptions::default(), &mut []), Err(InvalidParams));
+}
@@ -0,0 +1,172 @@
+// KMAC: KECCAK Message Authentication Code
+// https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-185.pdf
+//
+// NOTE: KMAC 不需要像 HMAC 那样做两次哈希，当密钥本身已经是均匀随机的时候，
+//       可以直接用来替代 HMAC。
+use crate::hash::{CShake128, CShake256, XofReader};
+use crate::hash::right_encode;
+
+use subtle;
+
+
+macro_rules! impl_kmac {
+    ($name:tt, $fname:tt, $cshake:tt, $tlen:tt, $doc:expr) => {
+        #[doc = $doc]
+        pub fn $fname(key: &[u8], customization: &[u8], data: &[u8], output: &mut [u8]) {
+            let mut m = $name::new(key, customization);
+            m.update(data);
+            m.finalize(output);
+        }
+
+        #[doc = $doc]
+        #[derive(Clone)]
+        pub struct $name {
+            inner: $cshake,
+        }
+
+        impl $name {
+            pub const BLOCK_LEN: usize = $cshake::BLOCK_LEN;
+            // NOTE: KMAC 的输出长度可以是任意的，这里只是 `oneshot` 使用的默认长度。
+            pub const TAG_LEN: usize   = $tlen;
+            // NOTE: SP 800-185 8.4.2 建议用作 MAC 时输出长度不少于 32 比特，
+            //       过短的 Tag 可以被轻易猜中，`verify` 会直接拒绝。
+            pub const MIN_TAG_LEN: usize = 4;
+
+            pub fn new(key: &[u8], customization: &[u8]) -> Self {
+                // newX = bytepad(encode_string(K), rate) || X || right_encode(L)
+                let mut inner = $cshake::new(b"KMAC", customization);
+                inner.update_bytepad(&[key]);
+
+                Self { inner }
+            }
+
+            pub fn update(&mut self, data: &[u8]) {
+                self.inner.update(data);
+            }
+
+            pub fn finalize(self, output: &mut [u8]) {
+                let mut m = self.inner;
+                let mut buf = [0u8; 9];
+                m.update(right_encode(output.len() as u64 * 8, &mut buf));
+                m.finalize_xof().read(output);
+            }
+
+            /// KMACXOF
+            pub fn finalize_xof(self) -> XofReader {
+                let mut m = self.inner;
+                let mut buf = [0u8; 9];
+                m.update(right_encode(0, &mut buf));
+                m.finalize_xof()
+            }
+
+            /// 以常量时间比较 Tag（Tag 的长度即为 KMAC 的输出长度 L，不能短于 `MIN_TAG_LEN`）。
+            pub fn verify(self, tag: &[u8]) -> bool {
+                if tag.len() < Self::MIN_TAG_LEN {
+                    return false;
+                }
+
+                let mut output = vec![0u8; tag.len()];
+                self.finalize(&mut output);
+
+                bool::from(subtle::ConstantTimeEq::ct_eq(&output[..], tag))
+            }
+
+            pub fn oneshot(key: &[u8], data: &[u8]) -> [u8; Self::TAG_LEN] {
+                let mut output = [0u8; Self::TAG_LEN];
+                $fname(key, &[], data, &mut output);
+                output
+            }
+        }
+    }
+}
+
+impl_kmac!(Kmac128, kmac128, CShake128, 32, "KMAC128");
+impl_kmac!(Kmac256, kmac256, CShake256, 64, "KMAC256");
+
+
+
+#[test]
+fn test_kmac128() {
+    // KMAC Samples
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Standards-and-Guidelines/documents/examples/KMAC_samples.pdf
+    let key = hex::decode("404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f").unwrap();
+    let data1 = [0x00, 0x01, 0x02, 0x03];
+    let data2 = (0u8..0xc8).collect::<Vec<u8>>();
+
+    // Sample #1
+    assert_eq!(&Kmac128::oneshot(&key, &data1)[..],
+        &hex::decode("e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e").unwrap()[..]);
+
+    // Sample #2
+    let mut output = [0u8; 32];
+    kmac128(&key, b"My Tagged Application", &data1, &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5").unwrap()[..]);
+
+    // Sample #3
+    let mut m = Kmac128::new(&key, b"My Tagged Application");
+    for chunk in data2.chunks(33) {
+        m.update(chunk);
+    }
+    let tag = hex::decode("1f5b4e6cca02209e0dcb5ca635b89a15e271ecc760071dfd805faa38f9729230").unwrap();
+    assert!(m.clone().verify(&tag));
+    assert!(!m.verify(&tag[..31]));
+
+    // NOTE: 空的或者过短的 Tag 即使是正确的 KMAC 输出也会被拒绝。
+    let m = Kmac128::new(&key, b"");
+    assert!(!m.clone().verify(&[]));
+    let mut short = [0u8; Kmac128::MIN_TAG_LEN - 1];
+    m.clone().finalize(&mut short);
+    assert!(!m.clone().verify(&short));
+    let mut min = [0u8; Kmac128::MIN_TAG_LEN];
+    m.clone().finalize(&mut min);
+    assert!(m.verify(&min));
+}
+
+#[test]
+fn test_kmac256() {
+    let key = hex::decode("404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f").unwrap();
+    let data1 = [0x00, 0x01, 0x02, 0x03];
+    let data2 = (0u8..0xc8).collect::<Vec<u8>>();
+
+    // Sample #4
+    let mut output = [0u8; 64];
+    kmac256(&key, b"My Tagged Application", &data1, &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7\
+f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd").unwrap()[..]);
+
+    // Sample #5
+    assert_eq!(&Kmac256::oneshot(&key, &data2)[..],
+        &hex::decode("75358cf39e41494e949707927cee0af20a3ff553904c86b08f21cc414bcfd691\
+589d27cf5e15369cbbff8b9a4c2eb17800855d0235ff635da82533ec6b759b69").unwrap()[..]);
+
+    // Sample #6
+    kmac256(&key, b"My Tagged Application", &data2, &mut output);
+    assert_eq!(&output[..],
+        &hex::decode("b58618f71f92e1d56c1b8c55ddd7cd188b97b4ca4d99831eb2699a837da2e4d9\
+70fbacfde50033aea585f1a2708510c32d07880801bd182898fe476876fc8965").unwrap()[..]);
+}
+
+#[test]
+fn test_kmac_xof() {
+    // KMACXOF Samples
+    // https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Standards-and-Guidelines/documents/examples/KMACXOF_samples.pdf
+    let key = hex::decode("404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f").unwrap();
+    let data1 = [0x00, 0x01, 0x02, 0x03];
+
+    let mut m = Kmac128::new(&key, b"");
+    m.update(&data1);
+    let mut output = [0u8; 32];
+    m.finalize_xof().read(&mut output);
+    assert_eq!(&output[..],
+        &hex::decode("cd83740bbd92ccc8cf032b1481a0f4460e7ca9dd12b08a0c4031178bacd6ec35").unwrap()[..]);
+
+    let mut m = Kmac256::new(&key, b"My Tagged Application");
+    m.update(&data1);
+    let mut output = [0u8; 64];
+    m.finalize_xof().read(&mut output);
+    assert_eq!(&output[..],
+        &hex::decode("1755133f1534752aad0748f2c706fb5c784512cab835cd15676b16c0c6647fa9\
+6faa7af634a0bf8ff6df39374fa00fad9a39e322a7c92065a64eb1fb0801eb2b").unwrap()[..]);
+}
@@ -1,5 +1,4 @@
 #![cfg_attr(test, feature(test))]
-#![feature(stdsimd, llvm_asm)]
 #![allow(unused_macros, unused_variables, dead_code, unused_assignments, unused_imports)]
 
 // #![no_std]
@@ -19,8 +18,19 @@ pub mod error;
 pub mod hash;
 
 pub mod hmac;
+pub mod kmac;
+pub mod cmac;
 // Key derivation function (KDF)
 pub mod hkdf;
+pub mod pbkdf2;
+pub mod scrypt;
+pub mod argon2;
+pub mod bcrypt;
+pub mod kbkdf;
+
+// Deterministic Random Bit Generator (DRBG)
+pub mod drbg;
+pub mod random;
 
 
 mod util;
@@ -34,6 +44,13 @@ pub mod blockcipher;
 pub mod streamcipher;
 pub mod aeadcipher;
 
+// Transport Layer Security (TLS)
+#[cfg(any(feature = "tls1_1", feature = "tls1_2", feature = "tls1_3"))]
+pub mod tls;
+// QUIC
+#[cfg(feature = "quic")]
+pub mod quic;
+
 
 
 // Elliptic Curve Cryptography（ECC）
@@ -42,10 +59,3 @@ pub mod aeadcipher;
 
 // RSA
 // https://docs.rs/rsa
-
-
-// pub mod scrypt; // TODO
-// The scrypt Password-Based Key Derivation Function
-// https://tools.ietf.org/html/rfc7914
-// 
-// https://en.wikipedia.org/wiki/Scrypt#Algorithm
\ No newline at end of file
@@ -30,11 +30,11 @@ unsafe fn vrbitq_u8(a: uint8x16_t) -> uint8x16_t {
     let result: uint8x16_t;
 
     // rbit v0.16b, v0.16b
-    llvm_asm!("rbit v0.16b, v0.16b"
-        : "=w" (result)
-        : "w"(a)
-        :
-        );
+    core::arch::asm!("rbit {0:v}.16b, {1:v}.16b",
+        out(vreg) result,
+        in(vreg) a,
+        options(pure, nomem, nostack, preserves_flags),
+    );
 
     result
 }
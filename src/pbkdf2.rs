@@ -0,0 +1,115 @@
+// PKCS #5: Password-Based Key Derivation Function 2 (PBKDF2)
+// https://tools.ietf.org/html/rfc8018#section-5.2
+// 
+// 参考实现
+// https://en.wikipedia.org/wiki/PBKDF2
+use crate::hmac::{Hmac, HmacState};
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
+
+
+/// PBKDF2 (P, S, c, dkLen)
+///
+/// 输出长度由 `out.len()` 决定，必须满足 dkLen <= (2^32 - 1) * hLen。
+pub fn pbkdf2<H: Hmac>(password: &[u8], salt: &[u8], iterations: usize, out: &mut [u8])
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    assert!(iterations > 0);
+
+    let hash_len = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+    assert!(out.len() as u64 <= (u32::MAX as u64) * (hash_len as u64));
+
+    // NOTE: 密码只处理一次，之后每次迭代都复用已经吸收了 ipad/opad 的状态。
+    let prf = HmacState::<H>::new(password);
+
+    // T_i = F (P, S, c, i)
+    // F (P, S, c, i) = U_1 \xor U_2 \xor ... \xor U_c
+    // 
+    // U_1 = PRF (P, S || INT (i)) ,
+    // U_2 = PRF (P, U_1) ,
+    // ...
+    // U_c = PRF (P, U_{c-1}) .
+    for (i, block) in out.chunks_mut(hash_len).enumerate() {
+        let block_index = (i as u32 + 1).to_be_bytes();
+
+        let mut m = prf.clone();
+        m.update(salt);
+        m.update(&block_index);
+        let mut u = m.finalize();
+
+        let mut t = u.array_as_slice().to_vec();
+        for _ in 1..iterations {
+            let mut m = prf.clone();
+            m.update(u.array_as_slice());
+            u = m.finalize();
+
+            for (a, b) in t.iter_mut().zip(u.array_as_slice().iter()) {
+                *a ^= b;
+            }
+        }
+
+        block.copy_from_slice(&t[..block.len()]);
+    }
+}
+
+
+#[test]
+fn test_pbkdf2_hmac_sha1() {
+    use crate::hash::Sha1;
+
+    // PKCS #5: Password-Based Key Derivation Function 2 (PBKDF2) Test Vectors
+    // https://tools.ietf.org/html/rfc6070#section-2
+    let suites: &[(&[u8], &[u8], usize, &str)] = &[
+        (b"password", b"salt", 1, "0c60c80f961f0e71f3a9b524af6012062fe037a6"),
+        (b"password", b"salt", 2, "ea6c014dc72d6f8ccd1ed92ace1d41f0d8de8957"),
+        (b"password", b"salt", 4096, "4b007901b765489abead49d926f721d065a429c1"),
+        (b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096,
+            "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"),
+        (b"pass\0word", b"sa\0lt", 4096, "56fa6aa75548099dcc37d7f03425e0c3"),
+    ];
+
+    for (password, salt, iterations, dk) in suites.iter() {
+        let dk = hex::decode(dk).unwrap();
+        let mut out = vec![0u8; dk.len()];
+        pbkdf2::<Sha1>(password, salt, *iterations, &mut out);
+        assert_eq!(out, dk);
+    }
+}
+
+#[test]
+fn test_pbkdf2_hmac_sha256() {
+    use crate::hash::Sha256;
+
+    // 11.  Test Vectors for PBKDF2 with HMAC-SHA-256
+    // https://tools.ietf.org/html/rfc7914#section-11
+    let suites: &[(&[u8], &[u8], usize, &str)] = &[
+        (b"passwd", b"salt", 1,
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"),
+        (b"Password", b"NaCl", 80000,
+            "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56\
+a1d425a1225833549adb841b51c9b3176a272bdebba1d078478f62b397f33c8d"),
+    ];
+
+    for (password, salt, iterations, dk) in suites.iter() {
+        let dk = hex::decode(dk).unwrap();
+        let mut out = vec![0u8; dk.len()];
+        pbkdf2::<Sha256>(password, salt, *iterations, &mut out);
+        assert_eq!(out, dk);
+    }
+}
+
+#[test]
+fn test_pbkdf2_hmac_sha512_sm3() {
+    use crate::hash::{Sha512, Sm3};
+
+    let mut out = [0u8; 64];
+    pbkdf2::<Sha512>(b"password", b"salt", 2, &mut out);
+    assert_eq!(&out[..], &hex::decode("e1d9c16aa681708a45f5c7c4e215ceb66e011a2e9f0040713f18aefdb866d53c\
+f76cab2868a39b9f7840edce4fef5a82be67335c77a6068e04112754f27ccf4e").unwrap()[..]);
+
+    let mut out = [0u8; 40];
+    pbkdf2::<Sm3>(b"password", b"saltSALTsaltSALT", 1000, &mut out);
+    assert_eq!(&out[..], &hex::decode("ccab5280a7fbd0c34d6b09fb7b237479d304630cca743d1322b9cfb7e347a791\
+a54e30a8a6d1a65c").unwrap()[..]);
+}
@@ -0,0 +1,605 @@
+// Using TLS to Secure QUIC
+// https://tools.ietf.org/html/rfc9001
+//
+// QUIC Version 2
+// https://tools.ietf.org/html/rfc9369
+//
+// NOTE: 这里只包含 QUIC 中与密码学相关的部分（Initial 密钥、包保护、头部保护以及密钥更新），
+//       握手的其它密钥由 TLS 1.3 的 Key Schedule（`crate::tls`）派生。
+use crate::hkdf::Hkdf;
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher, Sha256};
+use crate::blockcipher::{Aes128, Aes256};
+use crate::blockmode::{Aes128Gcm, Aes256Gcm, Aes128Ccm};
+use crate::aeadcipher::Chacha20Poly1305;
+use crate::tls::{TlsAead, xor_nonce, decode_truncated_number, expand_label_fixed, TLS13_LABEL_PREFIX};
+
+pub use crate::tls::{HeaderProtection, Chacha20HeaderProtection};
+
+use std::marker::PhantomData;
+
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum QuicVersion {
+    V1,
+    V2,
+}
+
+impl QuicVersion {
+    // 5.2.  Initial Secrets
+    // https://tools.ietf.org/html/rfc9001#section-5.2
+    //
+    // 3.3.1.  Initial Salt
+    // https://tools.ietf.org/html/rfc9369#section-3.3.1
+    pub fn initial_salt(&self) -> &'static [u8] {
+        match self {
+            QuicVersion::V1 => &[
+                0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17,
+                0x9a, 0xe6, 0xa4, 0xc8, 0x0c, 0xad, 0xcc, 0xbb, 0x7f, 0x0a,
+            ],
+            QuicVersion::V2 => &[
+                0x0d, 0xed, 0xe3, 0xde, 0xf7, 0x00, 0xa6, 0xdb, 0x81, 0x93,
+                0x81, 0xbe, 0x6e, 0x26, 0x9d, 0xcb, 0xf9, 0xbd, 0x2e, 0xd9,
+            ],
+        }
+    }
+
+    // 3.3.2.  HMAC-based Key Derivation Function (HKDF) Labels
+    // https://tools.ietf.org/html/rfc9369#section-3.3.2
+    fn labels(&self) -> Labels {
+        match self {
+            QuicVersion::V1 => Labels { key: b"quic key", iv: b"quic iv", hp: b"quic hp", ku: b"quic ku" },
+            QuicVersion::V2 => Labels { key: b"quicv2 key", iv: b"quicv2 iv", hp: b"quicv2 hp", ku: b"quicv2 ku" },
+        }
+    }
+}
+
+struct Labels {
+    key: &'static [u8],
+    iv: &'static [u8],
+    hp: &'static [u8],
+    ku: &'static [u8],
+}
+
+
+/// 根据客户端选择的 Destination Connection ID 派生 Initial 密钥，返回 (client_initial_secret, server_initial_secret)。
+///
+/// initial_secret = HKDF-Extract(initial_salt, client_dst_connection_id)
+///
+/// client_initial_secret = HKDF-Expand-Label(initial_secret, "client in", "", Hash.length)
+/// server_initial_secret = HKDF-Expand-Label(initial_secret, "server in", "", Hash.length)
+pub fn quic_initial_secrets(version: QuicVersion, dcid: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let initial_secret = Sha256::hkdf_extract(version.initial_salt(), dcid);
+    let initial_secret = initial_secret.array_as_slice();
+
+    let mut client = [0u8; 32];
+    let mut server = [0u8; 32];
+    expand_label_fixed::<Sha256>(TLS13_LABEL_PREFIX, initial_secret, b"client in", &[], &mut client);
+    expand_label_fixed::<Sha256>(TLS13_LABEL_PREFIX, initial_secret, b"server in", &[], &mut server);
+
+    (client, server)
+}
+
+/// Initial 包的保护（固定使用 AEAD_AES_128_GCM 和 SHA-256），返回 (client, server)。
+pub fn quic_initial_protectors(version: QuicVersion, dcid: &[u8]) -> (PacketProtector<Aes128Gcm, Sha256>, PacketProtector<Aes128Gcm, Sha256>) {
+    let (client, server) = quic_initial_secrets(version, dcid);
+
+    (PacketProtector::new(version, &client), PacketProtector::new(version, &server))
+}
+
+// A.3.  Sample Packet Number Decoding Algorithm
+// https://tools.ietf.org/html/rfc9000#appendix-A.3
+/// 根据已经成功处理的最大包序号，还原被截断的包序号（`pn_nbits` 为截断后的比特数）。
+///
+/// NOTE: 包序号被截断为 1 ~ 4 个字节，`pn_nbits` 不在 1 ~ 32 之间或者 `truncated_pn`
+///       超出 `pn_nbits` 能表示的范围时返回 `Malformed`。
+pub fn decode_packet_number(largest_pn: Option<u64>, truncated_pn: u64, pn_nbits: u32) -> Result<u64, PacketError> {
+    if pn_nbits == 0 || pn_nbits > 32 || truncated_pn >> pn_nbits != 0 {
+        return Err(PacketError::Malformed);
+    }
+
+    // NOTE: 包序号的取值范围为 0 ~ 2^62 - 1。
+    Ok(decode_truncated_number(largest_pn, truncated_pn, pn_nbits, (1 << 62) - 1))
+}
+
+
+/// QUIC 包保护可以使用的 AEAD 算法，以及对应的头部保护算法
+pub trait QuicAead: TlsAead {
+    type HeaderProtection: HeaderProtection;
+
+    // 6.6.  Limits on AEAD Usage
+    // https://tools.ietf.org/html/rfc9001#section-6.6
+    /// 同一个密钥最多可以加密的包数量
+    const CONFIDENTIALITY_LIMIT: u64;
+    /// 整个连接中最多允许认证失败的包数量
+    const INTEGRITY_LIMIT: u64;
+}
+
+macro_rules! impl_quic_aead {
+    ($name:tt, $hp:tt, $confidentiality_limit:expr, $integrity_limit:expr) => {
+        impl QuicAead for $name {
+            type HeaderProtection = $hp;
+
+            const CONFIDENTIALITY_LIMIT: u64 = $confidentiality_limit;
+            const INTEGRITY_LIMIT: u64       = $integrity_limit;
+        }
+    }
+}
+
+// AEAD_AES_128_GCM / AEAD_AES_256_GCM: 2^23 / 2^52
+// AEAD_CHACHA20_POLY1305: 超过包序号的上限（2^62） / 2^36
+// AEAD_AES_128_CCM: 2^21.5 / 2^21.5
+impl_quic_aead!(Aes128Gcm,        Aes128,                   1 << 23,  1 << 52);
+impl_quic_aead!(Aes256Gcm,        Aes256,                   1 << 23,  1 << 52);
+impl_quic_aead!(Chacha20Poly1305, Chacha20HeaderProtection, u64::MAX, 1 << 36);
+impl_quic_aead!(Aes128Ccm,        Aes128,                   2965820,  2965820);
+
+
+/// QUIC 包保护失败的原因
+///
+/// NOTE: 除了 `AeadLimitReached` 以外，其它错误都应该直接丢弃这个包，而不是关闭连接。
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PacketError {
+    /// 包的长度不足以进行头部保护的采样，或者头部格式错误
+    Malformed,
+    /// 认证失败
+    AuthenticationFailed,
+    /// 当前密钥加密的包数量达到上限，需要更新密钥
+    KeyUpdateRequired,
+    /// 认证失败的包数量达到上限，需要关闭连接（AEAD_LIMIT_REACHED）
+    AeadLimitReached,
+}
+
+impl std::fmt::Display for PacketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PacketError::Malformed => write!(f, "Malformed Packet"),
+            PacketError::AuthenticationFailed => write!(f, "Authentication Failed"),
+            PacketError::KeyUpdateRequired => write!(f, "Key Update Required"),
+            PacketError::AeadLimitReached => write!(f, "AEAD Limit Reached"),
+        }
+    }
+}
+impl std::error::Error for PacketError { }
+
+
+/// QUIC 包保护和头部保护（单个方向、单个加密级别）
+///
+/// NOTE: `packet` 的布局为：头部（包含未加密的包序号） || 明文 || Tag（加密时为预留的空间）。
+pub struct PacketProtector<C: QuicAead, H: Hkdf> {
+    version: QuicVersion,
+    secret: Vec<u8>,
+    key: Vec<u8>,
+    iv: [u8; 12],
+    hp: C::HeaderProtection,
+    encrypted: u64,
+    failed: u64,
+    key_update_threshold: u64,
+    _cipher: PhantomData<C>,
+    _hasher: PhantomData<H>,
+}
+
+impl<C: QuicAead, H: Hkdf> PacketProtector<C, H> {
+    pub const SAMPLE_LEN: usize = 16;
+    pub const MAX_PN_LEN: usize = 4;
+
+
+    // 5.1.  Packet Protection Keys
+    // https://tools.ietf.org/html/rfc9001#section-5.1
+    pub fn new(version: QuicVersion, secret: &[u8]) -> Self {
+        let labels = version.labels();
+
+        let mut hp_key = vec![0u8; <C::HeaderProtection as HeaderProtection>::KEY_LEN];
+        expand_label_fixed::<H>(TLS13_LABEL_PREFIX, secret, labels.hp, &[], &mut hp_key);
+        let hp = C::HeaderProtection::new(&hp_key);
+
+        Self::with_header_protection(version, secret, hp, 0)
+    }
+
+    fn with_header_protection(version: QuicVersion, secret: &[u8], hp: C::HeaderProtection, failed: u64) -> Self {
+        assert_eq!(C::NONCE_LEN, 12);
+
+        let labels = version.labels();
+
+        let mut key = vec![0u8; C::KEY_LEN];
+        let mut iv = [0u8; 12];
+        expand_label_fixed::<H>(TLS13_LABEL_PREFIX, secret, labels.key, &[], &mut key);
+        expand_label_fixed::<H>(TLS13_LABEL_PREFIX, secret, labels.iv, &[], &mut iv);
+
+        // NOTE: 默认在达到使用上限的一半时提示更新密钥。
+        let key_update_threshold = C::CONFIDENTIALITY_LIMIT / 2;
+
+        Self {
+            version,
+            secret: secret.to_vec(),
+            key,
+            iv,
+            hp,
+            encrypted: 0,
+            failed,
+            key_update_threshold,
+            _cipher: PhantomData,
+            _hasher: PhantomData,
+        }
+    }
+
+    // 6.1.  Initiating a Key Update
+    // https://tools.ietf.org/html/rfc9001#section-6.1
+    /// 派生下一代的密钥（Key Phase 翻转）
+    ///
+    /// secret_<n+1> = HKDF-Expand-Label(secret_<n>, "quic ku", "", Hash.length)
+    ///
+    /// NOTE: 头部保护的密钥不会更新，认证失败的计数在整个连接中累计。
+    pub fn next_key(&self) -> Self {
+        let mut secret = vec![0u8; <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN];
+        expand_label_fixed::<H>(TLS13_LABEL_PREFIX, &self.secret, self.version.labels().ku, &[], &mut secret);
+
+        Self::with_header_protection(self.version, &secret, self.hp.clone(), self.failed)
+    }
+
+    pub fn set_key_update_threshold(&mut self, threshold: u64) {
+        self.key_update_threshold = std::cmp::min(threshold, C::CONFIDENTIALITY_LIMIT);
+    }
+
+    /// 当前密钥加密的包数量达到阈值，应当更新密钥。
+    pub fn needs_key_update(&self) -> bool {
+        self.encrypted >= self.key_update_threshold
+    }
+
+    // 5.3.  AEAD Usage
+    // https://tools.ietf.org/html/rfc9001#section-5.3
+    //
+    // The 62 bits of the reconstructed QUIC packet number in network byte
+    // order are left-padded with zeros to the size of the IV.  The
+    // exclusive OR of the padded packet number and the IV forms the AEAD
+    // nonce.
+    fn nonce(&self, packet_number: u64) -> [u8; 12] {
+        xor_nonce(&self.iv, packet_number)
+    }
+
+    // 5.4.2.  Header Protection Sample
+    // https://tools.ietf.org/html/rfc9001#section-5.4.2
+    fn header_mask(&self, packet: &[u8], pn_offset: usize) -> Result<[u8; 5], PacketError> {
+        let sample_offset = pn_offset + Self::MAX_PN_LEN;
+        if packet.len() < sample_offset + Self::SAMPLE_LEN {
+            return Err(PacketError::Malformed);
+        }
+
+        let mut sample = [0u8; 16];
+        sample.copy_from_slice(&packet[sample_offset..sample_offset + Self::SAMPLE_LEN]);
+        Ok(self.hp.mask(&sample))
+    }
+
+    // Long Header: 低 4 位；Short Header: 低 5 位
+    fn first_byte_mask(first_byte: u8) -> u8 {
+        if first_byte & 0x80 == 0x80 { 0x0f } else { 0x1f }
+    }
+
+    /// 加密并添加头部保护。
+    ///
+    /// `header_len` 为包括包序号在内的头部长度，包序号的长度由第一个字节的低 2 位决定。
+    pub fn seal(&mut self, packet_number: u64, packet: &mut [u8], header_len: usize) -> Result<(), PacketError> {
+        if self.encrypted >= C::CONFIDENTIALITY_LIMIT {
+            return Err(PacketError::KeyUpdateRequired);
+        }
+        if packet.is_empty() || packet_number >= 1 << 62 {
+            return Err(PacketError::Malformed);
+        }
+
+        let pn_len = (packet[0] & 0x03) as usize + 1;
+        if header_len < pn_len + 1 || packet.len() < header_len + C::TAG_LEN {
+            return Err(PacketError::Malformed);
+        }
+        let pn_offset = header_len - pn_len;
+        if packet.len() < pn_offset + Self::MAX_PN_LEN + Self::SAMPLE_LEN {
+            return Err(PacketError::Malformed);
+        }
+
+        let (header, payload) = packet.split_at_mut(header_len);
+        let mut cipher = C::new(&self.key, &self.nonce(packet_number));
+        cipher.aead_encrypt(header, payload);
+        self.encrypted += 1;
+
+        let mask = self.header_mask(packet, pn_offset)?;
+        packet[0] ^= mask[0] & Self::first_byte_mask(packet[0]);
+        for i in 0..pn_len {
+            packet[pn_offset + i] ^= mask[1 + i];
+        }
+
+        Ok(())
+    }
+
+    /// 去掉头部保护，返回头部长度以及被截断的包序号。
+    ///
+    /// `pn_offset` 为包序号在包中的偏移量（对于 Long Header 需要先解析 Length 字段）。
+    /// 头部保护的密钥在密钥更新时保持不变，所以可以在选择解密密钥（检查 Key Phase）之前调用。
+    pub fn remove_header_protection(&self, packet: &mut [u8], pn_offset: usize) -> Result<(usize, u64), PacketError> {
+        if pn_offset == 0 {
+            return Err(PacketError::Malformed);
+        }
+
+        let mask = self.header_mask(packet, pn_offset)?;
+        packet[0] ^= mask[0] & Self::first_byte_mask(packet[0]);
+
+        let pn_len = (packet[0] & 0x03) as usize + 1;
+        let mut truncated_pn = 0u64;
+        for i in 0..pn_len {
+            packet[pn_offset + i] ^= mask[1 + i];
+            truncated_pn = (truncated_pn << 8) | packet[pn_offset + i] as u64;
+        }
+
+        Ok((pn_offset + pn_len, truncated_pn))
+    }
+
+    /// 解密已经去掉头部保护的包，返回明文。
+    pub fn decrypt_payload<'a>(&mut self, packet: &'a mut [u8], header_len: usize, packet_number: u64) -> Result<&'a [u8], PacketError> {
+        if self.failed >= C::INTEGRITY_LIMIT {
+            return Err(PacketError::AeadLimitReached);
+        }
+        if packet.len() < header_len + C::TAG_LEN {
+            return Err(PacketError::Malformed);
+        }
+
+        let (header, payload) = packet.split_at_mut(header_len);
+        let mut cipher = C::new(&self.key, &self.nonce(packet_number));
+        if !cipher.aead_decrypt(header, payload) {
+            self.failed += 1;
+            return Err(PacketError::AuthenticationFailed);
+        }
+
+        let packet: &'a [u8] = packet;
+        Ok(&packet[header_len..packet.len() - C::TAG_LEN])
+    }
+
+    /// 去掉头部保护并解密，返回完整的包序号以及明文。
+    ///
+    /// `largest_pn` 为当前包序号空间中已经成功处理的最大包序号。
+    pub fn open<'a>(&mut self, packet: &'a mut [u8], pn_offset: usize, largest_pn: Option<u64>) -> Result<(u64, &'a [u8]), PacketError> {
+        let (header_len, truncated_pn) = self.remove_header_protection(packet, pn_offset)?;
+        let pn_nbits = ((header_len - pn_offset) * 8) as u32;
+        let packet_number = decode_packet_number(largest_pn, truncated_pn, pn_nbits)?;
+
+        let payload = self.decrypt_payload(packet, header_len, packet_number)?;
+
+        Ok((packet_number, payload))
+    }
+}
+
+
+#[test]
+fn test_quic_initial_secrets() {
+    // A.1.  Keys
+    // https://tools.ietf.org/html/rfc9001#appendix-A.1
+    let dcid = hex::decode("8394c8f03e515708").unwrap();
+    let (client, server) = quic_initial_secrets(QuicVersion::V1, &dcid);
+    assert_eq!(&client[..], &hex::decode("c00cf151ca5be075ed0ebfb5c80323c42d6b7db67881289af4008f1f6c357aea").unwrap()[..]);
+    assert_eq!(&server[..], &hex::decode("3c199828fd139efd216c155ad844cc81fb82fa8d7446fa7d78be803acdda951b").unwrap()[..]);
+
+    let (client, server) = quic_initial_protectors(QuicVersion::V1, &dcid);
+    assert_eq!(&client.key[..], &hex::decode("1f369613dd76d5467730efcbe3b1a22d").unwrap()[..]);
+    assert_eq!(&client.iv[..], &hex::decode("fa044b2f42a3fd3b46fb255c").unwrap()[..]);
+    assert_eq!(&server.key[..], &hex::decode("cf3a5331653c364c88f0f379b6067e37").unwrap()[..]);
+    assert_eq!(&server.iv[..], &hex::decode("0ac1493ca1905853b0bba03e").unwrap()[..]);
+
+    // A.2.  Client Initial
+    // https://tools.ietf.org/html/rfc9001#appendix-A.2
+    let sample = hex::decode("d1b1c98dd7689fb8ec11d242b123dc9b").unwrap();
+    let mut s = [0u8; 16];
+    s.copy_from_slice(&sample);
+    assert_eq!(&client.hp.mask(&s)[..], &hex::decode("437b9aec36").unwrap()[..]);
+
+    // A.1.  Keys
+    // https://tools.ietf.org/html/rfc9369#appendix-A.1
+    let (client, server) = quic_initial_secrets(QuicVersion::V2, &dcid);
+    assert_eq!(&client[..], &hex::decode("14ec9d6eb9fd7af83bf5a668bc17a7e283766aade7ecd0891f70f9ff7f4bf47b").unwrap()[..]);
+    assert_eq!(&server[..], &hex::decode("0263db1782731bf4588e7e4d93b7463907cb8cd8200b5da55a8bd488eafc37c1").unwrap()[..]);
+
+    let (client, server) = quic_initial_protectors(QuicVersion::V2, &dcid);
+    assert_eq!(&client.key[..], &hex::decode("8b1a0bc121284290a29e0971b5cd045d").unwrap()[..]);
+    assert_eq!(&client.iv[..], &hex::decode("91f73e2351d8fa91660e909f").unwrap()[..]);
+    assert_eq!(&server.key[..], &hex::decode("82db637861d55e1d011f19ea71d5d2a7").unwrap()[..]);
+    assert_eq!(&server.iv[..], &hex::decode("dd13c276499c0249d3310652").unwrap()[..]);
+}
+
+#[test]
+fn test_quic_client_initial() {
+    // A.2.  Client Initial
+    // https://tools.ietf.org/html/rfc9001#appendix-A.2
+    let dcid = hex::decode("8394c8f03e515708").unwrap();
+    let header = hex::decode("c300000001088394c8f03e5157080000449e00000002").unwrap();
+    // NOTE: CRYPTO 帧之后使用 PADDING 帧（0）填充到 1162 字节，使整个 UDP 数据报为 1200 字节。
+    let mut payload = hex::decode("060040f1010000ed0303ebf8fa56f12939b9584a3896472ec40bb863cfd3e868\
+04fe3a47f06a2b69484c00000413011302010000c000000010000e00000b6578\
+616d706c652e636f6dff01000100000a00080006001d00170018001000070005\
+04616c706e000500050100000000003300260024001d00209370b2c9caa47fba\
+baf4559fedba753de171fa71f50f1ce15d43e994ec74d748002b000302030400\
+0d0010000e0403050306030203080408050806002d00020101001c0002400100\
+3900320408ffffffffffffffff05048000ffff07048000ffff08011001048000\
+75300901100f088394c8f03e51570806048000ffff").unwrap();
+    payload.resize(1162, 0);
+    let expected = hex::decode("c000000001088394c8f03e5157080000449e7b9aec34d1b1c98dd7689fb8ec11\
+d242b123dc9bd8bab936b47d92ec356c0bab7df5976d27cd449f63300099f399\
+1c260ec4c60d17b31f8429157bb35a1282a643a8d2262cad67500cadb8e7378c\
+8eb7539ec4d4905fed1bee1fc8aafba17c750e2c7ace01e6005f80fcb7df6212\
+30c83711b39343fa028cea7f7fb5ff89eac2308249a02252155e2347b63d58c5\
+457afd84d05dfffdb20392844ae812154682e9cf012f9021a6f0be17ddd0c208\
+4dce25ff9b06cde535d0f920a2db1bf362c23e596d11a4f5a6cf3948838a3aec\
+4e15daf8500a6ef69ec4e3feb6b1d98e610ac8b7ec3faf6ad760b7bad1db4ba3\
+485e8a94dc250ae3fdb41ed15fb6a8e5eba0fc3dd60bc8e30c5c4287e53805db\
+059ae0648db2f64264ed5e39be2e20d82df566da8dd5998ccabdae053060ae6c\
+7b4378e846d29f37ed7b4ea9ec5d82e7961b7f25a9323851f681d582363aa5f8\
+9937f5a67258bf63ad6f1a0b1d96dbd4faddfcefc5266ba6611722395c906556\
+be52afe3f565636ad1b17d508b73d8743eeb524be22b3dcbc2c7468d54119c74\
+68449a13d8e3b95811a198f3491de3e7fe942b330407abf82a4ed7c1b311663a\
+c69890f4157015853d91e923037c227a33cdd5ec281ca3f79c44546b9d90ca00\
+f064c99e3dd97911d39fe9c5d0b23a229a234cb36186c4819e8b9c5927726632\
+291d6a418211cc2962e20fe47feb3edf330f2c603a9d48c0fcb5699dbfe58964\
+25c5bac4aee82e57a85aaf4e2513e4f05796b07ba2ee47d80506f8d2c25e50fd\
+14de71e6c418559302f939b0e1abd576f279c4b2e0feb85c1f28ff18f58891ff\
+ef132eef2fa09346aee33c28eb130ff28f5b766953334113211996d20011a198\
+e3fc433f9f2541010ae17c1bf202580f6047472fb36857fe843b19f5984009dd\
+c324044e847a4f4a0ab34f719595de37252d6235365e9b84392b061085349d73\
+203a4a13e96f5432ec0fd4a1ee65accdd5e3904df54c1da510b0ff20dcc0c77f\
+cb2c0e0eb605cb0504db87632cf3d8b4dae6e705769d1de354270123cb11450e\
+fc60ac47683d7b8d0f811365565fd98c4c8eb936bcab8d069fc33bd801b03ade\
+a2e1fbc5aa463d08ca19896d2bf59a071b851e6c239052172f296bfb5e724047\
+90a2181014f3b94a4e97d117b438130368cc39dbb2d198065ae3986547926cd2\
+162f40a29f0c3c8745c0f50fba3852e566d44575c29d39a03f0cda721984b6f4\
+40591f355e12d439ff150aab7613499dbd49adabc8676eef023b15b65bfc5ca0\
+6948109f23f350db82123535eb8a7433bdabcb909271a6ecbcb58b936a88cd4e\
+8f2e6ff5800175f113253d8fa9ca8885c2f552e657dc603f252e1a8e308f76f0\
+be79e2fb8f5d5fbbe2e30ecadd220723c8c0aea8078cdfcb3868263ff8f09400\
+54da48781893a7e49ad5aff4af300cd804a6b6279ab3ff3afb64491c85194aab\
+760d58a606654f9f4400e8b38591356fbf6425aca26dc85244259ff2b19c41b9\
+f96f3ca9ec1dde434da7d2d392b905ddf3d1f9af93d1af5950bd493f5aa731b4\
+056df31bd267b6b90a079831aaf579be0a39013137aac6d404f518cfd4684064\
+7e78bfe706ca4cf5e9c5453e9f7cfd2b8b4c8d169a44e55c88d4a9a7f9474241\
+e221af44860018ab0856972e194cd934").unwrap();
+
+    let (mut client, _) = quic_initial_protectors(QuicVersion::V1, &dcid);
+    let mut packet = header.clone();
+    packet.extend_from_slice(&payload);
+    packet.resize(packet.len() + 16, 0);
+    client.seal(2, &mut packet, header.len()).unwrap();
+    assert_eq!(packet.len(), 1200);
+    assert_eq!(&packet[..], &expected[..]);
+
+    let (mut client, _) = quic_initial_protectors(QuicVersion::V1, &dcid);
+    let (packet_number, plaintext) = client.open(&mut packet, header.len() - 4, None).unwrap();
+    assert_eq!(packet_number, 2);
+    assert_eq!(plaintext, &payload[..]);
+    assert_eq!(&packet[..header.len()], &header[..]);
+}
+
+#[test]
+fn test_quic_server_initial() {
+    // A.3.  Server Initial
+    // https://tools.ietf.org/html/rfc9001#appendix-A.3
+    let dcid = hex::decode("8394c8f03e515708").unwrap();
+    let header = hex::decode("c1000000010008f067a5502a4262b50040750001").unwrap();
+    let payload = hex::decode("02000000000600405a020000560303eefce7f7b37ba1d1632e96677825ddf7\
+3988cfc79825df566dc5430b9a045a1200130100002e00330024001d00209d3c\
+940d89690b84d08a60993c144eca684d1081287c834d5311bcf32bb9da1a002b\
+00020304").unwrap();
+    let expected = hex::decode("cf000000010008f067a5502a4262b5004075c0d95a482cd0991cd25b0aac406a\
+5816b6394100f37a1c69797554780bb38cc5a99f5ede4cf73c3ec2493a1839b3\
+dbcba3f6ea46c5b7684df3548e7ddeb9c3bf9c73cc3f3bded74b562bfb19fb84\
+022f8ef4cdd93795d77d06edbb7aaf2f58891850abbdca3d20398c276456cbc4\
+2158407dd074ee").unwrap();
+
+    let (_, mut server) = quic_initial_protectors(QuicVersion::V1, &dcid);
+    let mut packet = header.clone();
+    packet.extend_from_slice(&payload);
+    packet.resize(packet.len() + 16, 0);
+    server.seal(1, &mut packet, header.len()).unwrap();
+    assert_eq!(&packet[..], &expected[..]);
+
+    let (_, mut server) = quic_initial_protectors(QuicVersion::V1, &dcid);
+    let (packet_number, plaintext) = server.open(&mut packet, header.len() - 2, None).unwrap();
+    assert_eq!(packet_number, 1);
+    assert_eq!(plaintext, &payload[..]);
+    assert_eq!(&packet[..header.len()], &header[..]);
+}
+
+#[test]
+fn test_quic_chacha20_poly1305_short_header() {
+    // A.5.  ChaCha20-Poly1305 Short Header Packet
+    // https://tools.ietf.org/html/rfc9001#appendix-A.5
+    let secret = hex::decode("9ac312a7f877468ebe69422748ad00a15443f18203a07d6060f688f30f21632b").unwrap();
+    let mut protector = PacketProtector::<Chacha20Poly1305, Sha256>::new(QuicVersion::V1, &secret);
+    assert_eq!(&protector.key[..], &hex::decode("c6d98ff3441c3fe1b2182094f69caa2ed4b716b65488960a7a984979fb23e1c8").unwrap()[..]);
+    assert_eq!(&protector.iv[..], &hex::decode("e0459b3474bdd0e44a41c144").unwrap()[..]);
+    assert_eq!(&protector.nonce(654360564)[..], &hex::decode("e0459b3474bdd0e46d417eb0").unwrap()[..]);
+
+    let sample = hex::decode("5e5cd55c41f69080575d7999c25a5bfb").unwrap();
+    let mut s = [0u8; 16];
+    s.copy_from_slice(&sample);
+    assert_eq!(&protector.hp.mask(&s)[..], &hex::decode("aefefe7d03").unwrap()[..]);
+
+    let mut packet = hex::decode("4200bff401").unwrap();
+    packet.resize(packet.len() + 16, 0);
+    protector.seal(654360564, &mut packet, 4).unwrap();
+    assert_eq!(&packet[..], &hex::decode("4cfe4189655e5cd55c41f69080575d7999c25a5bfb").unwrap()[..]);
+
+    let mut receiver = PacketProtector::<Chacha20Poly1305, Sha256>::new(QuicVersion::V1, &secret);
+    let (packet_number, plaintext) = receiver.open(&mut packet, 1, Some(654360563)).unwrap();
+    assert_eq!(packet_number, 654360564);
+    assert_eq!(plaintext, &[0x01]);
+
+    // 密钥更新
+    let next = protector.next_key();
+    assert_eq!(&next.secret[..], &hex::decode("1223504755036d556342ee9361d253421a826c9ecdf3c7148684b36b714881f9").unwrap()[..]);
+}
+
+#[test]
+fn test_quic_key_update() {
+    let secret = [0x42u8; 48];
+    let sender = PacketProtector::<Aes256Gcm, crate::hash::Sha384>::new(QuicVersion::V2, &secret);
+    let mut receiver = PacketProtector::<Aes256Gcm, crate::hash::Sha384>::new(QuicVersion::V2, &secret);
+
+    let mut packet = vec![0x41, 0x00, 0x07, 0xff, 0x00, 0x01];
+    packet.extend_from_slice(&[0u8; 20 + 16]);
+
+    let mut sender = sender.next_key();
+    sender.seal(0x07ff, &mut packet, 4).unwrap();
+
+    // NOTE: 头部保护的密钥不变，可以先去掉头部保护，再根据 Key Phase 选择密钥。
+    let (header_len, truncated_pn) = receiver.remove_header_protection(&mut packet, 2).unwrap();
+    assert_eq!((header_len, truncated_pn), (4, 0x07ff));
+    assert_eq!(receiver.decrypt_payload(&mut packet.clone(), header_len, 0x07ff), Err(PacketError::AuthenticationFailed));
+    let mut receiver = receiver.next_key();
+    assert_eq!(receiver.failed, 1);
+    assert_eq!(&receiver.decrypt_payload(&mut packet, header_len, 0x07ff).unwrap()[..2], &[0x00, 0x01]);
+}
+
+#[test]
+fn test_quic_packet_errors() {
+    let dcid = hex::decode("8394c8f03e515708").unwrap();
+    let (mut client, _) = quic_initial_protectors(QuicVersion::V1, &dcid);
+
+    // 长度不足以采样
+    let mut packet = vec![0xc0, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00];
+    packet.resize(packet.len() + 16, 0);
+    assert_eq!(client.seal(0, &mut packet, 8), Err(PacketError::Malformed));
+
+    let mut packet = vec![0x40, 0x00];
+    packet.resize(packet.len() + 4 + 16, 0);
+    client.seal(0, &mut packet, 2).unwrap();
+
+    // 篡改
+    let (mut server_view, _) = quic_initial_protectors(QuicVersion::V1, &dcid);
+    let mut tampered = packet.clone();
+    tampered[4] ^= 1;
+    assert_eq!(server_view.open(&mut tampered, 1, None), Err(PacketError::AuthenticationFailed));
+    server_view.failed = <Aes128Gcm as QuicAead>::INTEGRITY_LIMIT;
+    assert_eq!(server_view.open(&mut packet.clone(), 1, None), Err(PacketError::AeadLimitReached));
+
+    let (mut server_view, _) = quic_initial_protectors(QuicVersion::V1, &dcid);
+    assert_eq!(server_view.open(&mut packet, 1, None).unwrap(), (0, &[0u8; 4][..]));
+
+    // 密钥使用上限
+    client.set_key_update_threshold(2);
+    assert!(!client.needs_key_update());
+    client.encrypted = <Aes128Gcm as QuicAead>::CONFIDENTIALITY_LIMIT;
+    assert!(client.needs_key_update());
+    let mut packet = vec![0x40, 0x00];
+    packet.resize(packet.len() + 4 + 16, 0);
+    assert_eq!(client.seal(1, &mut packet, 2), Err(PacketError::KeyUpdateRequired));
+}
+
+#[test]
+fn test_quic_decode_packet_number() {
+    // A.3.  Sample Packet Number Decoding Algorithm
+    // https://tools.ietf.org/html/rfc9000#appendix-A.3
+    assert_eq!(decode_packet_number(Some(0xa82f30ea), 0x9b32, 16), Ok(0xa82f9b32));
+
+    assert_eq!(decode_packet_number(None, 0, 8), Ok(0));
+    assert_eq!(decode_packet_number(Some(0xff), 0x00, 8), Ok(0x100));
+    assert_eq!(decode_packet_number(Some(0x100), 0xff, 8), Ok(0xff));
+    assert_eq!(decode_packet_number(Some(0xfffffffe), 0xffffffff, 32), Ok(0xffffffff));
+
+    // 截断后的长度只能为 1 ~ 4 个字节
+    assert_eq!(decode_packet_number(Some(0xff), 0, 0), Err(PacketError::Malformed));
+    assert_eq!(decode_packet_number(Some(0xff), 0, 33), Err(PacketError::Malformed));
+    assert_eq!(decode_packet_number(Some(0xff), 0, 64), Err(PacketError::Malformed));
+    assert_eq!(decode_packet_number(Some(0xff), 0x100, 8), Err(PacketError::Malformed));
+}
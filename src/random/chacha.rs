@@ -0,0 +1,157 @@
+// 基于 ChaCha20 的用户态 CSPRNG
+//
+// Fast-key-erasure random-number generators
+// https://blog.cr.yp.to/20170723-random.html
+//
+// NOTE: 每次输出之后都会用密钥流的后续部分替换掉当前的密钥（Fast key erasure），
+//       所以即便之后内存泄漏，也无法恢复之前的输出。
+use super::{CryptoRng, OsRng};
+use crate::streamcipher::Chacha20;
+
+use std::io;
+
+
+// 从操作系统重新获取种子之前，最多输出的字节数。
+const RESEED_THRESHOLD: u64 = 1024 * 1024;
+
+
+fn keystream(key: &[u8; Chacha20::KEY_LEN]) -> Chacha20 {
+    let mut cipher = Chacha20::new(key, &[0u8; Chacha20::NONCE_LEN]);
+
+    // NOTE: `Chacha20` 输出的第一个块是初始状态本身（Block Index 为 0 的密钥流在第二个块），
+    //       里面包含了明文的密钥，必须丢弃。
+    let mut block = [0u8; Chacha20::BLOCK_LEN];
+    cipher.encrypt(&mut block);
+
+    cipher
+}
+
+
+/// ChaCha20 随机数生成器
+pub struct ChaChaRng {
+    inner: Chacha20,
+    bytes_since_reseed: u64,
+    // NOTE: 从操作系统获取种子时记录进程 ID，`fork` 之后子进程会重新获取种子，
+    //       避免父子进程输出相同的随机数。确定性的 RNG（`from_seed`）没有这个检查。
+    pid: Option<u32>,
+}
+
+impl ChaChaRng {
+    pub const SEED_LEN: usize = Chacha20::KEY_LEN;
+
+
+    /// 使用操作系统提供的随机数作为种子
+    pub fn new() -> Self {
+        let mut seed = [0u8; Self::SEED_LEN];
+        OsRng::new().fill_bytes(&mut seed);
+
+        let mut m = Self::from_seed(seed);
+        m.pid = Some(std::process::id());
+        m
+    }
+
+    /// 确定性的 RNG，同样的种子总是得到同样的输出（不会自动重新获取种子）。
+    pub fn from_seed(seed: [u8; Self::SEED_LEN]) -> Self {
+        Self { inner: keystream(&seed), bytes_since_reseed: 0, pid: None }
+    }
+
+    /// 从操作系统获取新的种子，并与当前状态混合。
+    pub fn reseed(&mut self) -> io::Result<()> {
+        let mut seed = [0u8; Self::SEED_LEN];
+        OsRng::new().try_fill_bytes(&mut seed)?;
+
+        // key = keystream ^ seed
+        self.inner.encrypt(&mut seed);
+        self.inner = keystream(&seed);
+        self.bytes_since_reseed = 0;
+
+        if self.pid.is_some() {
+            self.pid = Some(std::process::id());
+        }
+
+        Ok(())
+    }
+
+    fn need_reseed(&self) -> bool {
+        match self.pid {
+            Some(pid) => pid != std::process::id() || self.bytes_since_reseed >= RESEED_THRESHOLD,
+            None => false,
+        }
+    }
+}
+
+impl Default for ChaChaRng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CryptoRng for ChaChaRng {
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        if self.need_reseed() {
+            if let Err(e) = self.reseed() {
+                panic!("ChaChaRng: failed to reseed: {}", e);
+            }
+        }
+
+        for n in dst.iter_mut() {
+            *n = 0;
+        }
+        self.inner.encrypt(dst);
+        self.bytes_since_reseed = self.bytes_since_reseed.saturating_add(dst.len() as u64);
+
+        // Fast key erasure
+        let mut key = [0u8; Chacha20::KEY_LEN];
+        self.inner.encrypt(&mut key);
+        self.inner = keystream(&key);
+    }
+}
+
+
+#[test]
+fn test_chacha_rng_from_seed() {
+    // A.1.  The ChaCha20 Block Functions, Test Vector #1
+    // https://tools.ietf.org/html/rfc8439#appendix-A.1
+    let mut rng = ChaChaRng::from_seed([0u8; 32]);
+    let mut output = [0u8; 64];
+    rng.fill_bytes(&mut output);
+    assert_eq!(&output[..],
+        &hex::decode("76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7\
+da41597c5157488d7724e03fb8d84a376a43b8f41518a11cc387b669b2ee6586").unwrap()[..]);
+
+    let mut rng1 = ChaChaRng::from_seed([7u8; 32]);
+    let mut rng2 = ChaChaRng::from_seed([7u8; 32]);
+    let mut a = [0u8; 100];
+    let mut b = [0u8; 100];
+    rng1.fill_bytes(&mut a);
+    rng2.fill_bytes(&mut b);
+    assert_eq!(&a[..], &b[..]);
+    rng1.fill_bytes(&mut a);
+    assert_ne!(&a[..], &b[..]);
+}
+
+#[test]
+fn test_chacha_rng_reseed() {
+    let mut rng = ChaChaRng::new();
+    let mut a = [0u8; 32];
+    let mut b = [0u8; 32];
+    rng.fill_bytes(&mut a);
+    rng.fill_bytes(&mut b);
+    assert_ne!(&a[..], &b[..]);
+
+    // 模拟 fork：进程 ID 改变之后，必须重新获取种子。
+    rng.pid = Some(std::process::id().wrapping_add(1));
+    assert!(rng.need_reseed());
+    rng.fill_bytes(&mut a);
+    assert_eq!(rng.pid, Some(std::process::id()));
+    assert!(!rng.need_reseed());
+
+    rng.bytes_since_reseed = RESEED_THRESHOLD;
+    assert!(rng.need_reseed());
+    rng.fill_bytes(&mut a);
+    assert_eq!(rng.bytes_since_reseed, a.len() as u64);
+
+    let mut rng = ChaChaRng::from_seed([0u8; 32]);
+    rng.bytes_since_reseed = RESEED_THRESHOLD;
+    assert!(!rng.need_reseed());
+}
@@ -0,0 +1,80 @@
+// 随机数生成
+//
+// NOTE: `OsRng` 直接从操作系统获取随机数，适合用来生成密钥或者作为其它 RNG 的种子；
+//       `ChaChaRng` 在用户态以 ChaCha20 的密钥流作为输出，速度更快。
+use crate::blockcipher::BlockCipher;
+use crate::streamcipher::StreamCipher;
+use crate::aeadcipher::AeadCipher;
+
+mod os;
+mod chacha;
+
+pub use self::os::*;
+pub use self::chacha::*;
+
+
+/// 密码学安全的随机数生成器（CSPRNG）
+pub trait CryptoRng {
+    fn fill_bytes(&mut self, dst: &mut [u8]);
+}
+
+
+fn random_bytes<R: CryptoRng>(rng: &mut R, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// 生成长度为 `C::KEY_LEN` 的随机密钥
+pub fn gen_block_cipher_key<C: BlockCipher, R: CryptoRng>(rng: &mut R) -> Vec<u8> {
+    random_bytes(rng, C::KEY_LEN)
+}
+
+/// 生成长度为 `C::KEY_LEN` 的随机密钥
+pub fn gen_stream_cipher_key<C: StreamCipher, R: CryptoRng>(rng: &mut R) -> Vec<u8> {
+    random_bytes(rng, C::KEY_LEN)
+}
+
+/// 生成长度为 `C::NONCE_LEN` 的随机 Nonce
+pub fn gen_stream_cipher_nonce<C: StreamCipher, R: CryptoRng>(rng: &mut R) -> Vec<u8> {
+    random_bytes(rng, C::NONCE_LEN)
+}
+
+/// 生成长度为 `C::KEY_LEN` 的随机密钥
+pub fn gen_aead_key<C: AeadCipher, R: CryptoRng>(rng: &mut R) -> Vec<u8> {
+    random_bytes(rng, C::KEY_LEN)
+}
+
+/// 生成随机 Nonce
+///
+/// NOTE: Nonce 长度可变的算法（例如 OCB、SIV）使用 RFC 5116 推荐的 12 Bytes。
+pub fn gen_aead_nonce<C: AeadCipher, R: CryptoRng>(rng: &mut R) -> Vec<u8> {
+    const RECOMMENDED_NONCE_LEN: usize = 12;
+
+    let len = std::cmp::min(std::cmp::max(RECOMMENDED_NONCE_LEN, C::N_MIN), C::N_MAX);
+    random_bytes(rng, len)
+}
+
+
+#[test]
+fn test_gen_key_and_nonce() {
+    use crate::blockcipher::{Aes128, Aes256};
+    use crate::streamcipher::Chacha20;
+    use crate::aeadcipher::{Chacha20Poly1305, Aes128Ccm, Aes128OcbTag128};
+
+    let mut rng = OsRng::new();
+
+    assert_eq!(gen_block_cipher_key::<Aes128, _>(&mut rng).len(), 16);
+    assert_eq!(gen_block_cipher_key::<Aes256, _>(&mut rng).len(), 32);
+    assert_eq!(gen_stream_cipher_key::<Chacha20, _>(&mut rng).len(), 32);
+    assert_eq!(gen_stream_cipher_nonce::<Chacha20, _>(&mut rng).len(), 12);
+    assert_eq!(gen_aead_key::<Chacha20Poly1305, _>(&mut rng).len(), 32);
+    assert_eq!(gen_aead_nonce::<Chacha20Poly1305, _>(&mut rng).len(), 12);
+    assert_eq!(gen_aead_nonce::<Aes128Ccm, _>(&mut rng).len(), Aes128Ccm::NONCE_LEN);
+    assert_eq!(gen_aead_nonce::<Aes128OcbTag128, _>(&mut rng).len(), 12);
+
+    let mut rng = ChaChaRng::new();
+    let k1 = gen_aead_key::<Chacha20Poly1305, _>(&mut rng);
+    let k2 = gen_aead_key::<Chacha20Poly1305, _>(&mut rng);
+    assert_ne!(k1, k2);
+}
@@ -0,0 +1,125 @@
+// 操作系统提供的随机数
+//
+// getrandom(2)
+// https://man7.org/linux/man-pages/man2/getrandom.2.html
+//
+// NOTE: Linux 3.17 之前的内核没有 getrandom 系统调用，此时退回到读取 `/dev/urandom`。
+use super::CryptoRng;
+
+use std::io;
+
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64", target_arch = "arm")))]
+mod sys {
+    use std::io;
+
+    #[cfg(target_arch = "x86_64")]
+    const SYS_GETRANDOM: i64 = 318;
+    #[cfg(target_arch = "x86")]
+    const SYS_GETRANDOM: i64 = 355;
+    #[cfg(target_arch = "aarch64")]
+    const SYS_GETRANDOM: i64 = 278;
+    #[cfg(target_arch = "arm")]
+    const SYS_GETRANDOM: i64 = 384;
+
+    const ENOSYS: i32 = 38;
+
+    extern "C" {
+        fn syscall(number: std::os::raw::c_long, ...) -> std::os::raw::c_long;
+    }
+
+    // 返回 `Ok(false)` 表示内核不支持 getrandom 系统调用。
+    pub fn getrandom(dst: &mut [u8]) -> io::Result<bool> {
+        let mut dst = dst;
+        while !dst.is_empty() {
+            let ret = unsafe {
+                syscall(SYS_GETRANDOM as std::os::raw::c_long, dst.as_mut_ptr(), dst.len(), 0u32)
+            };
+            if ret < 0 {
+                let err = io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(ENOSYS) => return Ok(false),
+                    _ if err.kind() == io::ErrorKind::Interrupted => continue,
+                    _ => return Err(err),
+                }
+            }
+
+            let n = ret as usize;
+            dst = &mut dst[n..];
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64", target_arch = "arm"))))]
+mod sys {
+    use std::io;
+
+    pub fn getrandom(_dst: &mut [u8]) -> io::Result<bool> {
+        Ok(false)
+    }
+}
+
+#[cfg(unix)]
+fn read_urandom(dst: &mut [u8]) -> io::Result<()> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open("/dev/urandom")?;
+    file.read_exact(dst)
+}
+
+#[cfg(not(unix))]
+fn read_urandom(dst: &mut [u8]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Other, "no entropy source available"))
+}
+
+
+/// 操作系统随机数生成器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsRng;
+
+impl OsRng {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn try_fill_bytes(&mut self, dst: &mut [u8]) -> io::Result<()> {
+        if sys::getrandom(dst)? {
+            return Ok(());
+        }
+
+        read_urandom(dst)
+    }
+}
+
+impl CryptoRng for OsRng {
+    // NOTE: 无法获得随机数时直接 panic，而不是返回可预测的数据。
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dst) {
+            panic!("OsRng: failed to get random bytes: {}", e);
+        }
+    }
+}
+
+
+#[test]
+fn test_os_rng() {
+    let mut rng = OsRng::new();
+
+    let mut a = [0u8; 64];
+    let mut b = [0u8; 64];
+    rng.fill_bytes(&mut a);
+    rng.fill_bytes(&mut b);
+    assert_ne!(&a[..], &[0u8; 64][..]);
+    assert_ne!(&a[..], &b[..]);
+
+    // 较大的请求
+    let mut buf = vec![0u8; 1024 * 1024];
+    rng.fill_bytes(&mut buf);
+    assert!(buf.iter().any(|&n| n != 0));
+
+    let mut c = [0u8; 64];
+    read_urandom(&mut c).unwrap();
+    assert_ne!(&c[..], &a[..]);
+}
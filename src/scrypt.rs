@@ -0,0 +1,245 @@
+// The scrypt Password-Based Key Derivation Function
+// https://tools.ietf.org/html/rfc7914
+// 
+// https://en.wikipedia.org/wiki/Scrypt#Algorithm
+use crate::hash::Sha256;
+use crate::pbkdf2::pbkdf2;
+use crate::error::InvalidParams;
+
+
+/// `max_mem` 的推荐取值（32 MiB，与 OpenSSL 的 `SCRYPT_MAX_MEM` 相同）
+pub const DEFAULT_MAX_MEM: usize = 32 * 1024 * 1024;
+
+/// scrypt (P, S, N, r, p, dkLen)
+///
+/// 参数要求：
+///     N: CPU/Memory 开销，必须是大于 1 的 2 的幂，且 N < 2^(128 * r / 8)；
+///     r: 块大小（128 * r Bytes），p: 并行度，r * p < 2^30；
+///     dkLen <= (2^32 - 1) * 32 。
+///
+/// `max_mem` 为调用者允许使用的内存上限（Bytes），超过时返回 `InvalidParams`，
+/// 避免不可信的参数（例如来自密码哈希字符串）耗尽内存。
+///
+/// NOTE: 各个并行分支依次计算并复用同一块内存，所以内存占用固定为 128 * r * (N + p + 2) Bytes 左右。
+pub fn scrypt(password: &[u8], salt: &[u8], n: u64, r: u32, p: u32, max_mem: usize, out: &mut [u8]) -> Result<(), InvalidParams> {
+    if n < 2 || !n.is_power_of_two() || r == 0 || p == 0 {
+        return Err(InvalidParams);
+    }
+    // N < 2^(128 * r / 8)
+    if r < 4 && n >= 1u64 << (16 * r) {
+        return Err(InvalidParams);
+    }
+    // p <= ((2^32-1) * hLen) / MFLen
+    if (r as u64) * (p as u64) >= 1 << 30 {
+        return Err(InvalidParams);
+    }
+    if out.is_empty() || out.len() as u64 > (u32::MAX as u64) * 32 {
+        return Err(InvalidParams);
+    }
+
+    if n > usize::MAX as u64 {
+        return Err(InvalidParams);
+    }
+
+    let block_len = 128usize.checked_mul(r as usize).ok_or(InvalidParams)?;
+    let b_len = block_len.checked_mul(p as usize).ok_or(InvalidParams)?;
+    let v_words = (block_len / 4).checked_mul(n as usize).ok_or(InvalidParams)?;
+
+    // 128 * r * (N + p + 2)
+    let mem = (n as usize).checked_add(p as usize)
+        .and_then(|k| k.checked_add(2))
+        .and_then(|k| k.checked_mul(block_len))
+        .ok_or(InvalidParams)?;
+    if mem > max_mem {
+        return Err(InvalidParams);
+    }
+
+    // NOTE: 内存不足时返回错误，而不是直接终止进程。
+    let mut v: Vec<u32> = Vec::new();
+    v.try_reserve_exact(v_words).map_err(|_| InvalidParams)?;
+    v.resize(v_words, 0);
+
+    // 1. B[0] || B[1] || ... || B[p - 1] = PBKDF2-HMAC-SHA256 (P, S, 1, p * 128 * r)
+    let mut b = vec![0u8; b_len];
+    pbkdf2::<Sha256>(password, salt, 1, &mut b);
+
+    // 2. for i = 0 to p - 1 do
+    //        B[i] = scryptROMix (r, B[i], N)
+    //    end for
+    let mut x = vec![0u32; block_len / 4];
+    let mut y = vec![0u32; block_len / 4];
+    for chunk in b.chunks_mut(block_len) {
+        for (w, bytes) in x.iter_mut().zip(chunk.chunks_exact(4)) {
+            *w = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        ro_mix(&mut x, &mut y, &mut v, n as usize);
+
+        for (w, bytes) in x.iter().zip(chunk.chunks_exact_mut(4)) {
+            bytes.copy_from_slice(&w.to_le_bytes());
+        }
+    }
+
+    // 3. DK = PBKDF2-HMAC-SHA256 (P, B[0] || B[1] || ... || B[p - 1], 1, dkLen)
+    pbkdf2::<Sha256>(password, &b, 1, out);
+
+    Ok(())
+}
+
+// scryptROMix
+fn ro_mix(x: &mut [u32], y: &mut [u32], v: &mut [u32], n: usize) {
+    let words = x.len();
+
+    // 2. for i = 0 to N - 1 do
+    //        V[i] = X
+    //        X = scryptBlockMix (X)
+    //    end for
+    for i in 0..n {
+        v[i * words..(i + 1) * words].copy_from_slice(x);
+        block_mix(x, y);
+        x.copy_from_slice(y);
+    }
+
+    // 3. for i = 0 to N - 1 do
+    //        j = Integerify (X) mod N
+    //               where Integerify (B[0] ... B[2 * r - 1]) is defined
+    //               as the result of interpreting B[2 * r - 1] as a
+    //               little-endian integer.
+    //        T = X xor V[j]
+    //        X = scryptBlockMix (T)
+    //    end for
+    for _ in 0..n {
+        let last = words - 16;
+        let j = ((x[last] as u64 | (x[last + 1] as u64) << 32) % n as u64) as usize;
+
+        for (a, b) in x.iter_mut().zip(v[j * words..(j + 1) * words].iter()) {
+            *a ^= b;
+        }
+        block_mix(x, y);
+        x.copy_from_slice(y);
+    }
+}
+
+// scryptBlockMix
+fn block_mix(input: &[u32], output: &mut [u32]) {
+    let r = input.len() / 32;
+
+    // 1. X = B[2 * r - 1]
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&input[input.len() - 16..]);
+
+    // 2. for i = 0 to 2 * r - 1 do
+    //        T = X xor B[i]
+    //        X = Salsa (T)
+    //        Y[i] = X
+    //    end for
+    // 3. B' = (Y[0], Y[2], ..., Y[2 * r - 2],
+    //          Y[1], Y[3], ..., Y[2 * r - 1])
+    for (i, block) in input.chunks_exact(16).enumerate() {
+        for (a, b) in x.iter_mut().zip(block.iter()) {
+            *a ^= b;
+        }
+        salsa20_8(&mut x);
+
+        let pos = (i / 2 + (i % 2) * r) * 16;
+        output[pos..pos + 16].copy_from_slice(&x);
+    }
+}
+
+macro_rules! quarter_round {
+    ($x:ident, $a:expr, $b:expr, $c:expr, $d:expr) => {
+        $x[$b] ^= $x[$a].wrapping_add($x[$d]).rotate_left(7);
+        $x[$c] ^= $x[$b].wrapping_add($x[$a]).rotate_left(9);
+        $x[$d] ^= $x[$c].wrapping_add($x[$b]).rotate_left(13);
+        $x[$a] ^= $x[$d].wrapping_add($x[$c]).rotate_left(18);
+    }
+}
+
+// Salsa20/8 Core
+fn salsa20_8(b: &mut [u32; 16]) {
+    let mut x = *b;
+
+    for _ in 0..4 {
+        // Operate on columns.
+        quarter_round!(x,  0,  4,  8, 12);
+        quarter_round!(x,  5,  9, 13,  1);
+        quarter_round!(x, 10, 14,  2,  6);
+        quarter_round!(x, 15,  3,  7, 11);
+
+        // Operate on rows.
+        quarter_round!(x,  0,  1,  2,  3);
+        quarter_round!(x,  5,  6,  7,  4);
+        quarter_round!(x, 10, 11,  8,  9);
+        quarter_round!(x, 15, 12, 13, 14);
+    }
+
+    for i in 0..16 {
+        b[i] = b[i].wrapping_add(x[i]);
+    }
+}
+
+
+#[test]
+fn test_salsa20_8() {
+    // 8.  Test Vectors for Salsa20/8 Core
+    // https://tools.ietf.org/html/rfc7914#section-8
+    let input = hex::decode("7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d\
+ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e").unwrap();
+    let output = hex::decode("a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29\
+b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81").unwrap();
+
+    let mut b = [0u32; 16];
+    for (w, bytes) in b.iter_mut().zip(input.chunks_exact(4)) {
+        *w = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    salsa20_8(&mut b);
+
+    let mut out = Vec::new();
+    for w in b.iter() {
+        out.extend_from_slice(&w.to_le_bytes());
+    }
+    assert_eq!(out, output);
+}
+
+#[test]
+fn test_scrypt() {
+    // 12.  Test Vectors for scrypt
+    // https://tools.ietf.org/html/rfc7914#section-12
+    let suites: &[(&[u8], &[u8], u64, u32, u32, &str)] = &[
+        (b"", b"", 16, 1, 1,
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"),
+        (b"password", b"NaCl", 1024, 8, 16,
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"),
+        (b"pleaseletmein", b"SodiumChloride", 16384, 8, 1,
+            "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2\
+d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887"),
+        // NOTE: 最后一组测试向量（N = 1048576）需要 1 GiB 内存，这里不测试。
+    ];
+
+    for (password, salt, n, r, p, dk) in suites.iter() {
+        let dk = hex::decode(dk).unwrap();
+        let mut out = vec![0u8; dk.len()];
+        scrypt(password, salt, *n, *r, *p, DEFAULT_MAX_MEM, &mut out).unwrap();
+        assert_eq!(out, dk);
+    }
+}
+
+#[test]
+fn test_scrypt_invalid_params() {
+    let mut out = [0u8; 32];
+    assert_eq!(scrypt(b"", b"", 0, 1, 1, usize::MAX, &mut out), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 1, 1, 1, usize::MAX, &mut out), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 1000, 1, 1, usize::MAX, &mut out), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 16, 0, 1, usize::MAX, &mut out), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 16, 1, 0, usize::MAX, &mut out), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 1 << 16, 1, 1, usize::MAX, &mut out), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 16, 1 << 15, 1 << 15, usize::MAX, &mut out), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 16, 1, 1, usize::MAX, &mut []), Err(InvalidParams));
+    assert_eq!(scrypt(b"", b"", 1 << 62, 8, 1, usize::MAX, &mut out), Err(InvalidParams));
+
+    // 128 * 8 * (16384 + 1 + 2) Bytes > 16 MiB
+    assert_eq!(scrypt(b"", b"", 16384, 8, 1, 16 * 1024 * 1024, &mut out), Err(InvalidParams));
+    assert!(scrypt(b"", b"", 16384, 8, 1, 128 * 8 * (16384 + 1 + 2), &mut out).is_ok());
+}
@@ -124,6 +124,16 @@ impl Chacha20 {
         Self { state, initial_state: state, keystream, offset: 0usize }
     }
 
+    /// 指定初始的块计数器（RFC 8439 Section 2.4 中的 counter）。
+    ///
+    /// NOTE: 和 `new` 不同，输出的第一个块就是 `counter` 对应的密钥流，不需要跳过任何数据。
+    pub fn new_with_counter(key: &[u8], counter: u32, nonce: &[u8]) -> Self {
+        let mut chacha20 = Self::new(key, nonce);
+        chacha20.initial_state[12] = counter;
+        chacha20.incr();
+        chacha20
+    }
+
     #[inline]
     fn incr(&mut self) {
         let mut state = self.initial_state.clone();
@@ -222,4 +232,9 @@ If I could offer you only one tip for the future, sunscreen would be it.";
     let mut ciphertext = plaintext.to_vec();
     chacha20.encrypt(&mut ciphertext);
     assert_eq!(&ciphertext[..], &expected_ciphertext[..]);
+
+    // Initial Counter = 1
+    let mut ciphertext = plaintext.to_vec();
+    Chacha20::new_with_counter(&key, 1, &nonce).encrypt(&mut ciphertext);
+    assert_eq!(&ciphertext[..], &expected_ciphertext[..]);
 }
@@ -0,0 +1,79 @@
+use crate::blockmode::{Aes128Gcm, Aes256Gcm, Aes128Ccm, Aes128Ccm8, Sm4Gcm, Sm4Ccm};
+use crate::aeadcipher::Chacha20Poly1305;
+
+
+/// TLS 记录层可以使用的 AEAD 算法
+///
+/// NOTE: 这些算法的 Nonce 在 `new` 的时候传入，所以每条记录都需要重新构造一次。
+pub trait TlsAead: Sized {
+    const KEY_LEN: usize;
+    const NONCE_LEN: usize;
+    const TAG_LEN: usize;
+    /// 同一个密钥最多可以加密的记录数（Confidentiality Limit）
+    const RECORD_LIMIT: u64;
+
+    fn new(key: &[u8], nonce: &[u8]) -> Self;
+    fn aead_encrypt(&mut self, aad: &[u8], plaintext_and_ciphertext: &mut [u8]);
+    fn aead_decrypt(&mut self, aad: &[u8], ciphertext_and_plaintext: &mut [u8]) -> bool;
+}
+
+// 5.5.  Limits on Key Usage
+// https://tools.ietf.org/html/rfc8446#section-5.5
+// 
+// AES-GCM: 2^24.5 个完整大小的记录（约 2400 万）。
+// ChaCha20-Poly1305: 超过 2^64，即不受限制。
+// 
+// 6.6.  Limits on AEAD Usage
+// https://tools.ietf.org/html/rfc9001#section-6.6
+// 
+// AES-CCM: 2^21.5
+const GCM_RECORD_LIMIT: u64    = 23726566;
+const CCM_RECORD_LIMIT: u64    = 2965820;
+const CHACHA_RECORD_LIMIT: u64 = u64::MAX;
+
+macro_rules! impl_tls_aead {
+    ($name:tt, $limit:tt) => {
+        impl TlsAead for $name {
+            const KEY_LEN: usize   = $name::KEY_LEN;
+            const NONCE_LEN: usize = $name::NONCE_LEN;
+            const TAG_LEN: usize   = $name::TAG_LEN;
+            const RECORD_LIMIT: u64 = $limit;
+
+            fn new(key: &[u8], nonce: &[u8]) -> Self {
+                $name::new(key, nonce)
+            }
+
+            fn aead_encrypt(&mut self, aad: &[u8], plaintext_and_ciphertext: &mut [u8]) {
+                $name::aead_encrypt(self, aad, plaintext_and_ciphertext)
+            }
+
+            fn aead_decrypt(&mut self, aad: &[u8], ciphertext_and_plaintext: &mut [u8]) -> bool {
+                $name::aead_decrypt(self, aad, ciphertext_and_plaintext)
+            }
+        }
+    }
+}
+
+impl_tls_aead!(Aes128Gcm,        GCM_RECORD_LIMIT);
+impl_tls_aead!(Aes256Gcm,        GCM_RECORD_LIMIT);
+impl_tls_aead!(Aes128Ccm,        CCM_RECORD_LIMIT);
+impl_tls_aead!(Aes128Ccm8,       CCM_RECORD_LIMIT);
+impl_tls_aead!(Chacha20Poly1305, CHACHA_RECORD_LIMIT);
+// ShangMi (SM) Cipher Suites for TLS 1.3
+// https://tools.ietf.org/html/rfc8998
+impl_tls_aead!(Sm4Gcm,           GCM_RECORD_LIMIT);
+impl_tls_aead!(Sm4Ccm,           CCM_RECORD_LIMIT);
+
+
+// 5.3.  Per-Record Nonce
+// https://tools.ietf.org/html/rfc8446#section-5.3
+/// 64 位的序号按网络字节序左侧补 0 到 12 Bytes，再与静态的 IV 异或。
+///
+/// NOTE: TLS 1.3、DTLS 1.3、QUIC 以及 TLS 1.2 的 ChaCha20-Poly1305（RFC 7905）都使用这种构造。
+pub(crate) fn xor_nonce(iv: &[u8; 12], seq: u64) -> [u8; 12] {
+    let mut nonce = *iv;
+    for (n, s) in nonce[4..].iter_mut().zip(seq.to_be_bytes().iter()) {
+        *n ^= s;
+    }
+    nonce
+}
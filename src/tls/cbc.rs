@@ -0,0 +1,50 @@
+use crate::blockmode::{
+    Aes128Cbc, Aes256Cbc,
+    Camellia128Cbc, Camellia256Cbc,
+    Aria128Cbc, Aria256Cbc,
+};
+
+
+/// TLS 1.2 / TLS 1.1 记录层可以使用的 CBC 分组密码
+///
+/// NOTE: TLS 1.1 以后每条记录都带有显式的 IV，所以每条记录都需要重新构造一次。
+pub trait TlsCbcCipher: Sized {
+    const KEY_LEN: usize;
+    const BLOCK_LEN: usize;
+
+    fn new(key: &[u8], iv: &[u8]) -> Self;
+    fn encrypt(&mut self, blocks: &mut [u8]);
+    fn decrypt(&mut self, blocks: &mut [u8]);
+}
+
+macro_rules! impl_tls_cbc_cipher {
+    ($name:tt) => {
+        impl TlsCbcCipher for $name {
+            const KEY_LEN: usize   = $name::KEY_LEN;
+            const BLOCK_LEN: usize = $name::BLOCK_LEN;
+
+            fn new(key: &[u8], iv: &[u8]) -> Self {
+                $name::new(key, iv)
+            }
+
+            fn encrypt(&mut self, blocks: &mut [u8]) {
+                $name::encrypt(self, blocks)
+            }
+
+            fn decrypt(&mut self, blocks: &mut [u8]) {
+                $name::decrypt(self, blocks)
+            }
+        }
+    }
+}
+
+impl_tls_cbc_cipher!(Aes128Cbc);
+impl_tls_cbc_cipher!(Aes256Cbc);
+// Addition of Camellia Cipher Suites to Transport Layer Security (TLS)
+// https://tools.ietf.org/html/rfc5932
+impl_tls_cbc_cipher!(Camellia128Cbc);
+impl_tls_cbc_cipher!(Camellia256Cbc);
+// Addition of the ARIA Cipher Suites to Transport Layer Security (TLS)
+// https://tools.ietf.org/html/rfc6209
+impl_tls_cbc_cipher!(Aria128Cbc);
+impl_tls_cbc_cipher!(Aria256Cbc);
@@ -0,0 +1,783 @@
+// The Datagram Transport Layer Security (DTLS) Protocol Version 1.2
+// https://tools.ietf.org/html/rfc6347
+//
+// The Datagram Transport Layer Security (DTLS) Protocol Version 1.3
+// https://tools.ietf.org/html/rfc9147
+//
+// NOTE: 这里只包含 DTLS 记录层的保护（Epoch 和序列号、防重放、DTLS 1.3 的记录序号加密），
+//       不包含握手消息的分片、重传以及 ACK。
+use super::{TlsAead, RecordError, MAX_FRAGMENT_LEN, xor_nonce};
+use super::{HeaderProtection, Chacha20HeaderProtection, decode_truncated_number, expand_label_fixed, DTLS13_LABEL_PREFIX};
+use super::tls12::{AeadRecordCipher, MAX_CIPHERTEXT_LEN};
+use crate::hkdf::Hkdf;
+use crate::blockcipher::{Aes128, Aes256};
+use crate::blockmode::{Aes128Gcm, Aes256Gcm, Aes128Ccm, Aes128Ccm8};
+use crate::aeadcipher::Chacha20Poly1305;
+
+use std::marker::PhantomData;
+
+
+pub const DTLS1_2_VERSION: u16 = 0xfefd;
+
+/// DTLS 1.2 记录层头部长度：ContentType (1) + ProtocolVersion (2) + Epoch (2) + SequenceNumber (6) + Length (2)
+pub const DTLS12_RECORD_HEADER_LEN: usize = 13;
+/// DTLS 1.2 序列号的最大值：2^48 - 1
+pub const DTLS12_MAX_SEQUENCE_NUMBER: u64 = (1 << 48) - 1;
+
+
+// 4.1.2.6.  Anti-Replay
+// https://tools.ietf.org/html/rfc6347#section-4.1.2.6
+//
+// 4.5.1.  Anti-Replay
+// https://tools.ietf.org/html/rfc9147#section-4.5.1
+/// 防重放的滑动窗口（单个 Epoch），窗口大小为 64。
+#[derive(Debug, Clone)]
+pub struct ReplayWindow {
+    largest: Option<u64>,
+    // NOTE: 第 i 位表示序列号 largest - i 已经收到。
+    bitmap: u64,
+}
+
+impl ReplayWindow {
+    pub const SIZE: u64 = 64;
+
+
+    pub fn new() -> Self {
+        Self { largest: None, bitmap: 0 }
+    }
+
+    /// 已经通过认证的最大序列号
+    pub fn largest(&self) -> Option<u64> {
+        self.largest
+    }
+
+    /// 序列号没有收到过并且没有落在窗口的左侧时返回 true。
+    pub fn check(&self, seq: u64) -> bool {
+        match self.largest {
+            None => true,
+            Some(largest) if seq > largest => true,
+            Some(largest) => {
+                let diff = largest - seq;
+                diff < Self::SIZE && self.bitmap & (1 << diff) == 0
+            },
+        }
+    }
+
+    /// 标记序列号已经收到，只应该在记录通过认证之后调用。
+    pub fn update(&mut self, seq: u64) {
+        match self.largest {
+            None => {
+                self.largest = Some(seq);
+                self.bitmap = 1;
+            },
+            Some(largest) if seq > largest => {
+                let shift = seq - largest;
+                self.bitmap = if shift >= Self::SIZE { 0 } else { self.bitmap << shift };
+                self.bitmap |= 1;
+                self.largest = Some(seq);
+            },
+            Some(largest) => {
+                let diff = largest - seq;
+                if diff < Self::SIZE {
+                    self.bitmap |= 1 << diff;
+                }
+            },
+        }
+    }
+}
+
+impl Default for ReplayWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// 返回数据报中第一条 DTLS 1.2 记录（包括头部）的长度。
+pub fn dtls12_record_len(datagram: &[u8]) -> Result<usize, RecordError> {
+    if datagram.len() < DTLS12_RECORD_HEADER_LEN {
+        return Err(RecordError::DecodeError);
+    }
+
+    let len = DTLS12_RECORD_HEADER_LEN + u16::from_be_bytes([datagram[11], datagram[12]]) as usize;
+    if len > datagram.len() {
+        return Err(RecordError::DecodeError);
+    }
+
+    Ok(len)
+}
+
+/// 返回数据报中第一条 DTLS 1.3 记录（包括头部）的长度，`cid_len` 为本端 Connection ID 的长度。
+///
+/// NOTE: 第一个字节为 0b001xxxxx 时为 DTLSCiphertext（统一头部），否则为 DTLSPlaintext（与 DTLS 1.2 的格式相同）。
+pub fn dtls13_record_len(datagram: &[u8], cid_len: usize) -> Result<usize, RecordError> {
+    if datagram.is_empty() {
+        return Err(RecordError::DecodeError);
+    }
+    if datagram[0] & UNIFIED_HEADER_MASK != UNIFIED_HEADER_BITS {
+        return dtls12_record_len(datagram);
+    }
+
+    parse_unified_header(datagram, cid_len).map(|header| header.record_len)
+}
+
+
+/// DTLS 1.2 AEAD 记录层保护（单个方向、单个 Epoch）
+///
+/// 与 TLS 1.2 相同，只是 seq_num 为 Epoch (16 bits) || SequenceNumber (48 bits)，
+/// 并且序列号随记录一起发送，接收时不要求连续。
+///
+/// ```text
+/// struct {
+///     ContentType type;
+///     ProtocolVersion version;
+///     uint16 epoch;
+///     uint48 sequence_number;
+///     uint16 length;
+///     opaque fragment[DTLSCiphertext.length];
+/// } DTLSCiphertext;
+/// ```
+pub struct Dtls12AeadRecordProtector<C: TlsAead> {
+    cipher: AeadRecordCipher<C>,
+    epoch: u16,
+    seq: u64,
+    window: ReplayWindow,
+}
+
+impl<C: TlsAead> Dtls12AeadRecordProtector<C> {
+    /// `fixed_iv` 的长度决定 Nonce 的构造方式，参见 `tls12::AeadRecordProtector`。
+    pub fn new(epoch: u16, key: &[u8], fixed_iv: &[u8]) -> Self {
+        Self {
+            cipher: AeadRecordCipher::new(key, fixed_iv),
+            epoch,
+            seq: 0,
+            window: ReplayWindow::new(),
+        }
+    }
+
+    pub fn epoch(&self) -> u16 {
+        self.epoch
+    }
+
+    /// 下一条发送的记录的序列号
+    pub fn sequence_number(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn replay_window(&self) -> &ReplayWindow {
+        &self.window
+    }
+
+    // seq_num = epoch || sequence_number
+    fn record_sequence_number(&self, seq: u64) -> u64 {
+        ((self.epoch as u64) << 48) | seq
+    }
+
+    /// 加密一条记录，并把完整的 DTLSCiphertext（包括头部）追加到 `record` 后面。
+    pub fn seal(&mut self, content_type: u8, content: &[u8], record: &mut Vec<u8>) -> Result<(), RecordError> {
+        // NOTE: 序列号用完之后需要重新握手进入新的 Epoch。
+        if self.seq > DTLS12_MAX_SEQUENCE_NUMBER || self.seq >= C::RECORD_LIMIT {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+        if content.len() > MAX_FRAGMENT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        // NOTE: 使用 64 位的 seq_num（包括 Epoch）作为 explicit_nonce。
+        let seq_num = self.record_sequence_number(self.seq);
+        let mut header = [0u8; DTLS12_RECORD_HEADER_LEN - 2];
+        header[0] = content_type;
+        header[1..3].copy_from_slice(&DTLS1_2_VERSION.to_be_bytes());
+        header[3..].copy_from_slice(&seq_num.to_be_bytes());
+        self.cipher.seal(seq_num, content_type, DTLS1_2_VERSION, &header, content, record);
+
+        self.seq += 1;
+
+        Ok(())
+    }
+
+    /// 原地解密一条完整的 DTLSCiphertext（包括头部），返回 ContentType 以及明文。
+    ///
+    /// NOTE: 调用方需要根据记录中的 Epoch 选择对应的 Protector，Epoch 不一致时返回 `DecodeError`。
+    pub fn open<'a>(&mut self, record: &'a mut [u8]) -> Result<(u8, &'a [u8]), RecordError> {
+        if record.len() < DTLS12_RECORD_HEADER_LEN {
+            return Err(RecordError::DecodeError);
+        }
+        if u16::from_be_bytes([record[1], record[2]]) != DTLS1_2_VERSION {
+            return Err(RecordError::DecodeError);
+        }
+        if u16::from_be_bytes([record[3], record[4]]) != self.epoch {
+            return Err(RecordError::DecodeError);
+        }
+
+        let len = u16::from_be_bytes([record[11], record[12]]) as usize;
+        if len != record.len() - DTLS12_RECORD_HEADER_LEN {
+            return Err(RecordError::DecodeError);
+        }
+        if len > MAX_CIPHERTEXT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+        if len < self.cipher.explicit_nonce_len() + C::TAG_LEN {
+            return Err(RecordError::DecodeError);
+        }
+
+        let mut seq = [0u8; 8];
+        seq[2..].copy_from_slice(&record[5..11]);
+        let seq = u64::from_be_bytes(seq);
+        if !self.window.check(seq) {
+            return Err(RecordError::Replayed);
+        }
+
+        let content_type = record[0];
+        let seq_num = self.record_sequence_number(seq);
+        let plen = self.cipher.open(seq_num, content_type, DTLS1_2_VERSION, &mut record[DTLS12_RECORD_HEADER_LEN..])?;
+
+        self.window.update(seq);
+
+        let record: &'a [u8] = record;
+        let start = DTLS12_RECORD_HEADER_LEN + self.cipher.explicit_nonce_len();
+        Ok((content_type, &record[start..start + plen]))
+    }
+}
+
+
+//  0 1 2 3 4 5 6 7
+// +-+-+-+-+-+-+-+-+
+// |0|0|1|C|S|L|E E|
+// +-+-+-+-+-+-+-+-+
+const UNIFIED_HEADER_MASK: u8 = 0b1110_0000;
+const UNIFIED_HEADER_BITS: u8 = 0b0010_0000;
+const FLAG_CID: u8            = 0b0001_0000;
+const FLAG_SEQ_16: u8         = 0b0000_1000;
+const FLAG_LENGTH: u8         = 0b0000_0100;
+const EPOCH_MASK: u8          = 0b0000_0011;
+
+struct UnifiedHeader {
+    seq_offset: usize,
+    seq_len: usize,
+    header_len: usize,
+    record_len: usize,
+}
+
+// 4.  The DTLS Record Layer
+// https://tools.ietf.org/html/rfc9147#section-4
+fn parse_unified_header(record: &[u8], cid_len: usize) -> Result<UnifiedHeader, RecordError> {
+    let flags = record[0];
+
+    let mut offset = 1;
+    if flags & FLAG_CID != 0 {
+        if cid_len == 0 {
+            return Err(RecordError::DecodeError);
+        }
+        offset += cid_len;
+    }
+
+    let seq_offset = offset;
+    let seq_len = if flags & FLAG_SEQ_16 != 0 { 2 } else { 1 };
+    offset += seq_len;
+
+    let header_len;
+    let record_len;
+    if flags & FLAG_LENGTH != 0 {
+        header_len = offset + 2;
+        if record.len() < header_len {
+            return Err(RecordError::DecodeError);
+        }
+        record_len = header_len + u16::from_be_bytes([record[offset], record[offset + 1]]) as usize;
+    } else {
+        // NOTE: 没有 Length 字段时，记录一直延续到数据报的末尾。
+        header_len = offset;
+        record_len = record.len();
+    }
+
+    if header_len > record.len() || record_len > record.len() {
+        return Err(RecordError::DecodeError);
+    }
+
+    Ok(UnifiedHeader { seq_offset, seq_len, header_len, record_len })
+}
+
+/// DTLS 1.3 可以使用的 AEAD 算法，以及对应的记录序号加密算法
+///
+/// 4.2.3.  Record Number Encryption
+/// https://tools.ietf.org/html/rfc9147#section-4.2.3
+pub trait Dtls13Aead: TlsAead {
+    type RecordNumberMask: HeaderProtection;
+
+    // 4.5.3.  AEAD Limits
+    // https://tools.ietf.org/html/rfc9147#section-4.5.3
+    /// 同一个密钥最多允许认证失败的记录数量
+    const INTEGRITY_LIMIT: u64;
+}
+
+macro_rules! impl_dtls13_aead {
+    ($name:tt, $mask:tt, $integrity_limit:expr) => {
+        impl Dtls13Aead for $name {
+            type RecordNumberMask = $mask;
+
+            const INTEGRITY_LIMIT: u64 = $integrity_limit;
+        }
+    }
+}
+
+// AEAD_AES_128_GCM / AEAD_AES_256_GCM / AEAD_CHACHA20_POLY1305: 2^36
+// AEAD_AES_128_CCM: 2^23.5（Appendix B）
+// AEAD_AES_128_CCM_8: 2^7（Appendix C）
+impl_dtls13_aead!(Aes128Gcm,        Aes128,                   1 << 36);
+impl_dtls13_aead!(Aes256Gcm,        Aes256,                   1 << 36);
+impl_dtls13_aead!(Aes128Ccm,        Aes128,                   11863283);
+impl_dtls13_aead!(Aes128Ccm8,       Aes128,                   1 << 7);
+impl_dtls13_aead!(Chacha20Poly1305, Chacha20HeaderProtection, 1 << 36);
+
+
+/// DTLS 1.3 记录层保护（单个方向、单个 Epoch）
+///
+/// 发送时总是使用 16 位的序列号并且包含 Length 字段。
+///
+/// ```text
+/// struct {
+///     opaque unified_hdr[variable];
+///     opaque encrypted_record[length];
+/// } DTLSCiphertext;
+/// ```
+pub struct Dtls13RecordProtector<C: Dtls13Aead> {
+    key: Vec<u8>,
+    iv: [u8; 12],
+    sn: C::RecordNumberMask,
+    epoch: u64,
+    seq: u64,
+    cid: Vec<u8>,
+    window: ReplayWindow,
+    failed: u64,
+    key_update_threshold: u64,
+    _cipher: PhantomData<C>,
+}
+
+impl<C: Dtls13Aead> Dtls13RecordProtector<C> {
+    pub const IV_LEN: usize = 12;
+    /// DTLSCiphertext 的最大长度：2^14 + 256
+    pub const MAX_CIPHERTEXT_LEN: usize = MAX_FRAGMENT_LEN + 256;
+    /// 记录序号加密需要的密文长度
+    pub const SAMPLE_LEN: usize = 16;
+
+
+    pub fn new(epoch: u64, key: &[u8], iv: &[u8], sn_key: &[u8]) -> Self {
+        assert_eq!(C::NONCE_LEN, Self::IV_LEN);
+        assert_eq!(key.len(), C::KEY_LEN);
+        assert_eq!(iv.len(), Self::IV_LEN);
+
+        let mut static_iv = [0u8; 12];
+        static_iv.copy_from_slice(iv);
+
+        // NOTE: 默认在达到使用上限的一半时提示更新密钥，留出足够的时间完成 KeyUpdate。
+        let key_update_threshold = C::RECORD_LIMIT / 2;
+
+        Self {
+            key: key.to_vec(),
+            iv: static_iv,
+            sn: C::RecordNumberMask::new(sn_key),
+            epoch,
+            seq: 0,
+            cid: Vec::new(),
+            window: ReplayWindow::new(),
+            failed: 0,
+            key_update_threshold,
+            _cipher: PhantomData,
+        }
+    }
+
+    /// 从 [sender]_*_traffic_secret 派生 write_key、write_iv 和 sn_key。
+    ///
+    /// [sender]_sn_key = HKDF-Expand-Label(Secret, "sn", "", key_length)
+    pub fn from_traffic_secret<H: Hkdf>(epoch: u64, traffic_secret: &[u8]) -> Self {
+        let mut key = vec![0u8; C::KEY_LEN];
+        let mut iv = [0u8; 12];
+        let mut sn_key = vec![0u8; <C::RecordNumberMask as HeaderProtection>::KEY_LEN];
+        expand_label_fixed::<H>(DTLS13_LABEL_PREFIX, traffic_secret, b"key", &[], &mut key);
+        expand_label_fixed::<H>(DTLS13_LABEL_PREFIX, traffic_secret, b"iv", &[], &mut iv);
+        expand_label_fixed::<H>(DTLS13_LABEL_PREFIX, traffic_secret, b"sn", &[], &mut sn_key);
+
+        Self::new(epoch, &key, &iv, &sn_key)
+    }
+
+    /// 设置记录中携带的 Connection ID（RFC 9146）。
+    ///
+    /// NOTE: 发送方向为对端的 Connection ID，接收方向为本端的 Connection ID。
+    pub fn set_connection_id(&mut self, cid: &[u8]) {
+        assert!(cid.len() <= 255);
+
+        self.cid = cid.to_vec();
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// 下一条发送的记录的序列号
+    pub fn sequence_number(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn replay_window(&self) -> &ReplayWindow {
+        &self.window
+    }
+
+    pub fn set_key_update_threshold(&mut self, threshold: u64) {
+        self.key_update_threshold = std::cmp::min(threshold, C::RECORD_LIMIT);
+    }
+
+    /// 已经发送的记录数达到阈值，应当发送 KeyUpdate。
+    pub fn needs_key_update(&self) -> bool {
+        self.seq >= self.key_update_threshold
+    }
+
+    // NOTE: 与 TLS 1.3 相同，只使用 64 位的序列号，不包括 Epoch。
+    fn nonce(&self, seq: u64) -> [u8; 12] {
+        xor_nonce(&self.iv, seq)
+    }
+
+    // mask = Mask(sn_key, Ciphertext[0..15])
+    fn mask(&self, ciphertext: &[u8]) -> [u8; 5] {
+        let mut sample = [0u8; 16];
+        sample.copy_from_slice(&ciphertext[..Self::SAMPLE_LEN]);
+        self.sn.mask(&sample)
+    }
+
+    /// 加密一条记录，并把完整的 DTLSCiphertext（包括头部）追加到 `record` 后面。
+    ///
+    /// `padding_len` 为 DTLSInnerPlaintext 中填充的 0 的个数，密文不足 16 Bytes 时会自动增加填充。
+    pub fn seal(&mut self, content_type: u8, content: &[u8], padding_len: usize, record: &mut Vec<u8>) -> Result<(), RecordError> {
+        // NOTE: 0 是 DTLSInnerPlaintext 中的填充，不能作为 ContentType。
+        if content_type == 0 {
+            return Err(RecordError::UnexpectedMessage);
+        }
+
+        if self.seq >= C::RECORD_LIMIT {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+
+        let mut inner_len = content.len().saturating_add(padding_len).saturating_add(1);
+        if inner_len > MAX_FRAGMENT_LEN + 1 {
+            return Err(RecordError::RecordOverflow);
+        }
+        // NOTE: 记录序号加密需要至少 16 Bytes 的密文（例如 AES-CCM-8）。
+        if inner_len + C::TAG_LEN < Self::SAMPLE_LEN {
+            inner_len = Self::SAMPLE_LEN - C::TAG_LEN;
+        }
+
+        let mut flags = UNIFIED_HEADER_BITS | FLAG_SEQ_16 | FLAG_LENGTH | (self.epoch as u8 & EPOCH_MASK);
+        if !self.cid.is_empty() {
+            flags |= FLAG_CID;
+        }
+
+        let len = inner_len + C::TAG_LEN;
+        let seq_offset = 1 + self.cid.len();
+        let header_len = seq_offset + 2 + 2;
+        let start = record.len();
+        record.reserve(header_len + len);
+        record.push(flags);
+        record.extend_from_slice(&self.cid);
+        record.extend_from_slice(&(self.seq as u16).to_be_bytes());
+        record.extend_from_slice(&(len as u16).to_be_bytes());
+        record.extend_from_slice(content);
+        record.push(content_type);
+        // NOTE: 填充的 0 以及 Tag 的位置。
+        record.resize(start + header_len + len, 0);
+
+        // NOTE: AAD 为记录序号加密之前的头部。
+        let (header, payload) = record[start..].split_at_mut(header_len);
+        let mut cipher = C::new(&self.key, &self.nonce(self.seq));
+        cipher.aead_encrypt(header, payload);
+
+        let mask = self.mask(payload);
+        header[seq_offset] ^= mask[0];
+        header[seq_offset + 1] ^= mask[1];
+
+        self.seq += 1;
+
+        Ok(())
+    }
+
+    /// 原地解密一条完整的 DTLSCiphertext（包括头部），返回真实的 ContentType 以及去掉填充后的内容。
+    ///
+    /// NOTE: 调用方需要根据头部中 Epoch 的低 2 位选择对应的 Protector，不一致时返回 `DecodeError`。
+    ///       解密失败的记录应当直接丢弃（RFC 9147 Section 4.5.2），
+    ///       但是认证失败的记录数量达到上限之后返回 `AeadLimitReached`，不能再继续使用这个密钥。
+    pub fn open<'a>(&mut self, record: &'a mut [u8]) -> Result<(u8, &'a [u8]), RecordError> {
+        if self.failed >= C::INTEGRITY_LIMIT {
+            return Err(RecordError::AeadLimitReached);
+        }
+        if record.is_empty() {
+            return Err(RecordError::DecodeError);
+        }
+
+        let flags = record[0];
+        if flags & UNIFIED_HEADER_MASK != UNIFIED_HEADER_BITS {
+            return Err(RecordError::UnexpectedMessage);
+        }
+        if flags & EPOCH_MASK != self.epoch as u8 & EPOCH_MASK {
+            return Err(RecordError::DecodeError);
+        }
+
+        let header = parse_unified_header(record, self.cid.len())?;
+        if header.record_len != record.len() {
+            return Err(RecordError::DecodeError);
+        }
+        if (flags & FLAG_CID != 0) != !self.cid.is_empty() || record[1..1 + self.cid.len()] != self.cid[..] {
+            return Err(RecordError::DecodeError);
+        }
+
+        let len = record.len() - header.header_len;
+        if len > Self::MAX_CIPHERTEXT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+        // NOTE: 密文不足 16 Bytes 的记录视为解密失败。
+        if len < Self::SAMPLE_LEN || len < C::TAG_LEN + 1 {
+            return Err(RecordError::BadRecordMac);
+        }
+
+        let (header_bytes, payload) = record.split_at_mut(header.header_len);
+        let mask = self.mask(payload);
+        let mut truncated = 0u64;
+        for i in 0..header.seq_len {
+            header_bytes[header.seq_offset + i] ^= mask[i];
+            truncated = (truncated << 8) | header_bytes[header.seq_offset + i] as u64;
+        }
+
+        let seq = decode_truncated_number(self.window.largest(), truncated, (header.seq_len * 8) as u32, u64::MAX);
+        if !self.window.check(seq) {
+            return Err(RecordError::Replayed);
+        }
+
+        let mut cipher = C::new(&self.key, &self.nonce(seq));
+        if !cipher.aead_decrypt(header_bytes, payload) {
+            self.failed += 1;
+            return Err(RecordError::BadRecordMac);
+        }
+
+        self.window.update(seq);
+
+        let record: &'a [u8] = record;
+        let plaintext = &record[header.header_len..record.len() - C::TAG_LEN];
+        if plaintext.len() > MAX_FRAGMENT_LEN + 1 {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        // NOTE: 从后往前扫描，第一个非零的字节即为 ContentType。
+        match plaintext.iter().rposition(|&b| b != 0) {
+            Some(pos) => Ok((plaintext[pos], &plaintext[..pos])),
+            None => Err(RecordError::UnexpectedMessage),
+        }
+    }
+}
+
+
+#[test]
+fn test_dtls_replay_window() {
+    let mut window = ReplayWindow::new();
+    assert!(window.check(5));
+    window.update(5);
+    assert!(!window.check(5));
+    assert!(window.check(4));
+    assert!(window.check(6));
+
+    window.update(100);
+    assert_eq!(window.largest(), Some(100));
+    // 5 已经落在窗口的左侧
+    assert!(!window.check(5));
+    assert!(!window.check(36));
+    assert!(window.check(37));
+    assert!(window.check(99));
+
+    window.update(99);
+    assert!(!window.check(99));
+    assert!(!window.check(100));
+    assert!(window.check(98));
+}
+
+#[test]
+fn test_dtls_reconstruct_sequence_number() {
+    assert_eq!(decode_truncated_number(None, 0, 16, u64::MAX), 0);
+    assert_eq!(decode_truncated_number(Some(0xffff), 0x0000, 16, u64::MAX), 0x10000);
+    assert_eq!(decode_truncated_number(Some(0x10002), 0xffff, 16, u64::MAX), 0xffff);
+    assert_eq!(decode_truncated_number(Some(0x1ff), 0x05, 8, u64::MAX), 0x205);
+    // NOTE: 不会超出 64 位序列号的范围。
+    assert_eq!(decode_truncated_number(Some(u64::MAX - 1), 0x0000, 16, u64::MAX), u64::MAX - 0xffff);
+}
+
+#[test]
+fn test_dtls12_aead_record_protector() {
+    use super::CONTENT_TYPE_APPLICATION_DATA;
+
+    // NOTE: 与 Python cryptography 的 AESGCM / ChaCha20Poly1305 的计算结果一致。
+    let content = b"hello dtls";
+    let suites = [
+        (0u64, "17fefd000100000000000000220001000000000000f93265d26f3747cb0580078f3cfbd580b9080ffaab6075084797"),
+        (1u64, "17fefd00010000000000010022000100000000000176b68c16a1addd6a6d977dab5092b509ee39bddc33c643febf33"),
+    ];
+
+    let key: Vec<u8> = (0..16).collect();
+    let fixed_iv = hex::decode("a0a1a2a3").unwrap();
+    let mut sender = Dtls12AeadRecordProtector::<Aes128Gcm>::new(1, &key, &fixed_iv);
+    let mut records = Vec::new();
+    for (seq, expected) in suites.iter() {
+        assert_eq!(sender.sequence_number(), *seq);
+        let mut record = Vec::new();
+        sender.seal(CONTENT_TYPE_APPLICATION_DATA, content, &mut record).unwrap();
+        assert_eq!(&record[..], &hex::decode(expected).unwrap()[..]);
+        records.extend_from_slice(&record);
+    }
+
+    // 一个数据报中包含多条记录，并且乱序到达。
+    let first_len = dtls12_record_len(&records).unwrap();
+    let (first, second) = records.split_at_mut(first_len);
+    let mut receiver = Dtls12AeadRecordProtector::<Aes128Gcm>::new(1, &key, &fixed_iv);
+    let mut replay = second.to_vec();
+    assert_eq!(receiver.open(second).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &content[..]));
+    assert_eq!(receiver.open(first).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &content[..]));
+    assert_eq!(receiver.open(&mut replay), Err(RecordError::Replayed));
+
+    let key: Vec<u8> = (0..32).collect();
+    let iv: Vec<u8> = (0x40..0x4c).collect();
+    let suites = [
+        (0u64, "17fefd0001000000000000001a711881d5547f98f399927dcd0a642ac17e07fb54a17558f7efd3"),
+        (1u64, "17fefd0001000000000001001a042ec9b259217b2a3cc1398aa2145e089c4733b76b81d39c5df6"),
+    ];
+    let mut sender = Dtls12AeadRecordProtector::<Chacha20Poly1305>::new(1, &key, &iv);
+    let mut receiver = Dtls12AeadRecordProtector::<Chacha20Poly1305>::new(1, &key, &iv);
+    for (_, expected) in suites.iter() {
+        let mut record = Vec::new();
+        sender.seal(CONTENT_TYPE_APPLICATION_DATA, content, &mut record).unwrap();
+        assert_eq!(&record[..], &hex::decode(expected).unwrap()[..]);
+        assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &content[..]));
+    }
+
+    // 篡改以及 Epoch 不一致
+    let mut record = Vec::new();
+    sender.seal(CONTENT_TYPE_APPLICATION_DATA, content, &mut record).unwrap();
+    let mut other_epoch = record.clone();
+    other_epoch[4] = 2;
+    assert_eq!(receiver.open(&mut other_epoch), Err(RecordError::DecodeError));
+    let mut tampered = record.clone();
+    tampered[15] ^= 1;
+    assert_eq!(receiver.open(&mut tampered), Err(RecordError::BadRecordMac));
+    assert_eq!(receiver.replay_window().largest(), Some(1));
+    assert!(receiver.open(&mut record).is_ok());
+}
+
+#[test]
+fn test_dtls13_record_protector() {
+    use crate::hash::Sha256;
+    use super::{CONTENT_TYPE_APPLICATION_DATA, CONTENT_TYPE_HANDSHAKE};
+
+    // NOTE: 与 Python cryptography 的计算结果一致（HKDF-Expand-Label 使用 "dtls13" 前缀）。
+    let secret = hex::decode("9ac312a7f877468ebe69422748ad00a15443f18203a07d6060f688f30f21632b").unwrap();
+    let content = b"hello dtls";
+
+    let mut sender = Dtls13RecordProtector::<Aes128Gcm>::from_traffic_secret::<Sha256>(3, &secret);
+    assert_eq!(&sender.key[..], &hex::decode("8f5190c76b66a745c4f5665e8944f82c").unwrap()[..]);
+    assert_eq!(&sender.iv[..], &hex::decode("e91cdd2d4990f4bf16dc5393").unwrap()[..]);
+
+    let mut records = Vec::new();
+    sender.seal(CONTENT_TYPE_APPLICATION_DATA, content, 0, &mut records).unwrap();
+    assert_eq!(&records[..], &hex::decode("2f8956001beb7c93a088b60cf7b750401dcbe756aa67f257d8daf25614e2dc5e").unwrap()[..]);
+    let first_len = records.len();
+    sender.seal(CONTENT_TYPE_APPLICATION_DATA, content, 0, &mut records).unwrap();
+    assert_eq!(&records[first_len..], &hex::decode("2f02d9001b54e6202e197cfb2759ba76c48caa2bb664940c1fa44d4f0f6c6079").unwrap()[..]);
+
+    assert_eq!(dtls13_record_len(&records, 0).unwrap(), first_len);
+    let (first, second) = records.split_at_mut(first_len);
+    let mut receiver = Dtls13RecordProtector::<Aes128Gcm>::from_traffic_secret::<Sha256>(3, &secret);
+    let mut replay = first.to_vec();
+    assert_eq!(receiver.open(second).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &content[..]));
+    assert_eq!(receiver.open(first).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &content[..]));
+    assert_eq!(receiver.open(&mut replay), Err(RecordError::Replayed));
+    assert_eq!(receiver.replay_window().largest(), Some(1));
+
+    // Connection ID
+    let cid = hex::decode("c0ffee").unwrap();
+    let mut sender = Dtls13RecordProtector::<Aes128Gcm>::from_traffic_secret::<Sha256>(3, &secret);
+    sender.set_connection_id(&cid);
+    let mut record = Vec::new();
+    sender.seal(CONTENT_TYPE_HANDSHAKE, &[0x01, 0x02], 4, &mut record).unwrap();
+    assert_eq!(&record[..], &hex::decode("3fc0ffeee8ac0017821be9cce79668f48c7d70dfacb39814cd7a253a4e1ff1").unwrap()[..]);
+
+    let mut receiver = Dtls13RecordProtector::<Aes128Gcm>::from_traffic_secret::<Sha256>(3, &secret);
+    assert_eq!(receiver.open(&mut record.clone()), Err(RecordError::DecodeError));
+    receiver.set_connection_id(&cid);
+    assert_eq!(dtls13_record_len(&record, cid.len()).unwrap(), record.len());
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_HANDSHAKE, &[0x01, 0x02][..]));
+
+    // ChaCha20-Poly1305：序列号 0x10002 在头部中被截断为 0x0002
+    let mut sender = Dtls13RecordProtector::<Chacha20Poly1305>::from_traffic_secret::<Sha256>(2, &secret);
+    assert_eq!(&sender.key[..], &hex::decode("417a98a603329c4ee6c1eb74a9d71e91ed8f50680f7bbb2d9cbb8ed4ad197708").unwrap()[..]);
+    sender.seq = 0x10002;
+    let mut record = Vec::new();
+    sender.seal(CONTENT_TYPE_APPLICATION_DATA, content, 0, &mut record).unwrap();
+    assert_eq!(&record[..], &hex::decode("2e6e06001bf68864f94eb5457bcae176f75b4f782f1c7b6d0a3828a589a88c7c").unwrap()[..]);
+
+    let mut receiver = Dtls13RecordProtector::<Chacha20Poly1305>::from_traffic_secret::<Sha256>(2, &secret);
+    receiver.window.update(0xfff0);
+    let mut tampered = record.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert_eq!(receiver.open(&mut tampered), Err(RecordError::BadRecordMac));
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &content[..]));
+    assert_eq!(receiver.replay_window().largest(), Some(0x10002));
+
+    // Epoch 不一致
+    let mut sender = Dtls13RecordProtector::<Chacha20Poly1305>::from_traffic_secret::<Sha256>(3, &secret);
+    let mut record = Vec::new();
+    sender.seal(CONTENT_TYPE_APPLICATION_DATA, content, 0, &mut record).unwrap();
+    assert_eq!(receiver.open(&mut record), Err(RecordError::DecodeError));
+}
+
+#[test]
+fn test_dtls13_record_protector_short_ciphertext() {
+    use crate::hash::Sha256;
+    use super::CONTENT_TYPE_ALERT;
+
+    // NOTE: AES-CCM-8 的 Tag 只有 8 Bytes，密文不足 16 Bytes 时需要填充。
+    let secret = [0x11u8; 32];
+    let mut sender = Dtls13RecordProtector::<Aes128Ccm8>::from_traffic_secret::<Sha256>(4, &secret);
+    let mut receiver = Dtls13RecordProtector::<Aes128Ccm8>::from_traffic_secret::<Sha256>(4, &secret);
+
+    let mut record = Vec::new();
+    sender.seal(CONTENT_TYPE_ALERT, &[0x01, 0x00], 0, &mut record).unwrap();
+    assert_eq!(record.len(), 5 + 16);
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_ALERT, &[0x01, 0x00][..]));
+
+    let mut record = hex::decode("2c000000080102030405060708").unwrap();
+    assert_eq!(receiver.open(&mut record), Err(RecordError::BadRecordMac));
+
+    // ContentType 为 0
+    let mut record = Vec::new();
+    assert_eq!(sender.seal(0, &[0x01, 0x00], 0, &mut record), Err(RecordError::UnexpectedMessage));
+    assert!(record.is_empty());
+}
+
+#[test]
+fn test_dtls13_record_protector_aead_limit() {
+    use crate::hash::Sha256;
+    use super::CONTENT_TYPE_APPLICATION_DATA;
+
+    // NOTE: AES-CCM-8 只允许 2^7 条认证失败的记录。
+    let secret = [0x22u8; 32];
+    let mut sender = Dtls13RecordProtector::<Aes128Ccm8>::from_traffic_secret::<Sha256>(3, &secret);
+    let mut receiver = Dtls13RecordProtector::<Aes128Ccm8>::from_traffic_secret::<Sha256>(3, &secret);
+
+    let mut record = Vec::new();
+    sender.seal(CONTENT_TYPE_APPLICATION_DATA, b"hello dtls", 0, &mut record).unwrap();
+    for _ in 0..<Aes128Ccm8 as Dtls13Aead>::INTEGRITY_LIMIT {
+        let mut tampered = record.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert_eq!(receiver.open(&mut tampered), Err(RecordError::BadRecordMac));
+    }
+    assert_eq!(receiver.open(&mut record.clone()), Err(RecordError::AeadLimitReached));
+    assert_eq!(receiver.replay_window().largest(), None);
+
+    // NOTE: 新的密钥重新开始计数。
+    let mut receiver = Dtls13RecordProtector::<Aes128Ccm8>::from_traffic_secret::<Sha256>(3, &secret);
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &b"hello dtls"[..]));
+}
@@ -0,0 +1,116 @@
+// Using TLS to Secure QUIC
+// 5.4.  Header Protection
+// https://tools.ietf.org/html/rfc9001#section-5.4
+//
+// The Datagram Transport Layer Security (DTLS) Protocol Version 1.3
+// 4.2.3.  Record Number Encryption
+// https://tools.ietf.org/html/rfc9147#section-4.2.3
+//
+// NOTE: QUIC 的头部保护和 DTLS 1.3 的 Record Number Encryption 使用相同的掩码算法，
+//       都是对 16 Bytes 的密文采样计算掩码。
+use crate::blockcipher::{Aes128, Aes256};
+use crate::streamcipher::Chacha20;
+
+
+/// QUIC 头部保护 / DTLS 1.3 记录序号加密的掩码算法
+pub trait HeaderProtection: Sized + Clone {
+    const KEY_LEN: usize;
+
+    fn new(hp_key: &[u8]) -> Self;
+    /// 根据密文的采样计算 5 Bytes 的掩码
+    fn mask(&self, sample: &[u8; 16]) -> [u8; 5];
+}
+
+// 5.4.3.  AES-Based Header Protection
+// https://tools.ietf.org/html/rfc9001#section-5.4.3
+//
+// mask = AES-ECB(hp_key, sample)
+macro_rules! impl_aes_header_protection {
+    ($name:tt) => {
+        impl HeaderProtection for $name {
+            const KEY_LEN: usize = $name::KEY_LEN;
+
+            fn new(hp_key: &[u8]) -> Self {
+                $name::new(hp_key)
+            }
+
+            fn mask(&self, sample: &[u8; 16]) -> [u8; 5] {
+                let mut block = *sample;
+                self.encrypt(&mut block);
+
+                let mut mask = [0u8; 5];
+                mask.copy_from_slice(&block[..5]);
+                mask
+            }
+        }
+    }
+}
+
+impl_aes_header_protection!(Aes128);
+impl_aes_header_protection!(Aes256);
+
+// 5.4.4.  ChaCha20-Based Header Protection
+// https://tools.ietf.org/html/rfc9001#section-5.4.4
+/// 基于 ChaCha20 的头部保护
+#[derive(Clone)]
+pub struct Chacha20HeaderProtection {
+    key: [u8; 32],
+}
+
+impl HeaderProtection for Chacha20HeaderProtection {
+    const KEY_LEN: usize = Chacha20::KEY_LEN;
+
+    fn new(hp_key: &[u8]) -> Self {
+        assert_eq!(hp_key.len(), Self::KEY_LEN);
+
+        let mut key = [0u8; 32];
+        key.copy_from_slice(hp_key);
+        Self { key }
+    }
+
+    fn mask(&self, sample: &[u8; 16]) -> [u8; 5] {
+        // counter = sample[0..3]
+        // nonce = sample[4..15]
+        // mask = ChaCha20(hp_key, counter, nonce, {0,0,0,0,0})
+        let counter = u32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+
+        let mut mask = [0u8; 5];
+        let mut chacha20 = Chacha20::new_with_counter(&self.key, counter, &sample[4..16]);
+        chacha20.encrypt(&mut mask);
+        mask
+    }
+}
+
+
+// RFC 9000 A.3.  Sample Packet Number Decoding Algorithm
+// https://tools.ietf.org/html/rfc9000#appendix-A.3
+//
+// RFC 9147 4.2.2.  Reconstructing the Sequence Number and Epoch
+// https://tools.ietf.org/html/rfc9147#section-4.2.2
+/// 还原被截断为 `nbits` 位的 QUIC 包序号 / DTLS 1.3 记录序号，选择最接近 `largest + 1` 的值。
+///
+/// NOTE: `max` 为序号的最大值（QUIC 为 2^62 - 1，DTLS 1.3 为 2^64 - 1），
+///       调用方需要保证 `nbits` 在 1 ~ 32 之间并且 `truncated` 不超过 `nbits` 位。
+pub(crate) fn decode_truncated_number(largest: Option<u64>, truncated: u64, nbits: u32, max: u64) -> u64 {
+    debug_assert!((1..=32).contains(&nbits) && truncated >> nbits == 0);
+
+    let expected = match largest {
+        Some(largest) => largest.saturating_add(1),
+        None => 0,
+    };
+    let win = 1u64 << nbits;
+    let hwin = win / 2;
+    let mask = win - 1;
+
+    // The incoming packet number should be greater than
+    // expected_pn - pn_hwin and less than or equal to
+    // expected_pn + pn_hwin
+    let candidate = (expected & !mask) | truncated;
+    if candidate.saturating_add(hwin) <= expected && candidate <= max - win {
+        candidate + win
+    } else if candidate > expected.saturating_add(hwin) && candidate >= win {
+        candidate - win
+    } else {
+        candidate
+    }
+}
@@ -0,0 +1,370 @@
+// 7.1.  Key Schedule
+// https://tools.ietf.org/html/rfc8446#section-7.1
+// 
+// Example Handshake Traces for TLS 1.3
+// https://tools.ietf.org/html/rfc8448
+use crate::hkdf::{Hkdf, HkdfExpander};
+use crate::error::InvalidParams;
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
+
+use std::marker::PhantomData;
+
+
+/// HKDF-Expand-Label(Secret, Label, Context, Length) =
+///      HKDF-Expand(Secret, HkdfLabel, Length)
+///
+/// NOTE: `label` 不包含 "tls13 " 前缀，输出长度由 `output.len()` 决定。
+///       label、context 超出 HkdfLabel 的长度限制或者输出过长时返回 `InvalidParams`。
+pub fn tls13_hkdf_expand_label<H: Hkdf>(secret: &[u8], label: &[u8], context: &[u8], output: &mut [u8]) -> Result<(), InvalidParams> {
+    hkdf_expand_label::<H>(TLS13_LABEL_PREFIX, secret, label, context, output)
+}
+
+/// DTLS 1.3 的 HKDF-Expand-Label，前缀为 "dtls13"（没有空格）。
+///
+/// 5.9.  Cryptographic Label Prefix
+/// https://tools.ietf.org/html/rfc9147#section-5.9
+pub fn dtls13_hkdf_expand_label<H: Hkdf>(secret: &[u8], label: &[u8], context: &[u8], output: &mut [u8]) -> Result<(), InvalidParams> {
+    hkdf_expand_label::<H>(DTLS13_LABEL_PREFIX, secret, label, context, output)
+}
+
+pub(crate) const TLS13_LABEL_PREFIX: &[u8] = b"tls13 ";
+pub(crate) const DTLS13_LABEL_PREFIX: &[u8] = b"dtls13";
+
+/// 库内部派生密钥时使用的 HKDF-Expand-Label。
+///
+/// NOTE: 调用方的 label 都是协议中固定的常量，context 为空或者 Hash.length 字节，
+///       输出为 AEAD 的 key/iv、头部保护的密钥或者 Hash.length，
+///       都在 HkdfLabel 的长度限制以及 255 * HashLen 之内，所以这里不会出错。
+pub(crate) fn expand_label_fixed<H: Hkdf>(prefix: &[u8], secret: &[u8], label: &[u8], context: &[u8], output: &mut [u8]) {
+    debug_assert!(output.len() <= 255 * hash_len::<H>());
+
+    hkdf_expand_label::<H>(prefix, secret, label, context, output).unwrap()
+}
+
+fn hkdf_expand_label<H: Hkdf>(prefix: &[u8], secret: &[u8], label: &[u8], context: &[u8], output: &mut [u8]) -> Result<(), InvalidParams> {
+    // struct {
+    //     uint16 length = Length;
+    //     opaque label<7..255> = "tls13 " + Label;
+    //     opaque context<0..255> = Context;
+    // } HkdfLabel;
+    if prefix.len() + label.len() > 255 || context.len() > 255 || output.len() > u16::MAX as usize {
+        return Err(InvalidParams);
+    }
+
+    let length = (output.len() as u16).to_be_bytes();
+    let label_len = [(prefix.len() + label.len()) as u8];
+    let context_len = [context.len() as u8];
+
+    let info: [&[u8]; 6] = [&length, &label_len, prefix, label, &context_len, context];
+    // 超出 255 * HashLen
+    HkdfExpander::<H>::new(secret, &info).read(output).map_err(|_| InvalidParams)
+}
+
+/// Derive-Secret(Secret, Label, Messages) =
+///      HKDF-Expand-Label(Secret, Label,
+///                        Transcript-Hash(Messages), Hash.length)
+///
+/// NOTE: 调用方需要自行计算 Transcript-Hash。
+pub fn tls13_derive_secret<H: Hkdf>(secret: &[u8], label: &[u8], transcript_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+    let mut output = vec![0u8; hash_len::<H>()];
+    tls13_hkdf_expand_label::<H>(secret, label, transcript_hash, &mut output)?;
+    Ok(output)
+}
+
+/// 7.3.  Traffic Key Calculation
+///
+/// [sender]_write_key = HKDF-Expand-Label(Secret, "key", "", key_length)
+/// [sender]_write_iv  = HKDF-Expand-Label(Secret, "iv", "", iv_length)
+pub fn tls13_traffic_key_iv<H: Hkdf>(traffic_secret: &[u8], key: &mut [u8], iv: &mut [u8]) -> Result<(), InvalidParams> {
+    tls13_hkdf_expand_label::<H>(traffic_secret, b"key", &[], key)?;
+    tls13_hkdf_expand_label::<H>(traffic_secret, b"iv", &[], iv)
+}
+
+/// 4.4.4.  Finished
+///
+/// finished_key = HKDF-Expand-Label(BaseKey, "finished", "", Hash.length)
+pub fn tls13_finished_key<H: Hkdf>(base_key: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; hash_len::<H>()];
+    expand_label_fixed::<H>(TLS13_LABEL_PREFIX, base_key, b"finished", &[], &mut output);
+    output
+}
+
+/// 7.2.  Updating Traffic Secrets
+///
+/// application_traffic_secret_N+1 =
+///     HKDF-Expand-Label(application_traffic_secret_N,
+///                       "traffic upd", "", Hash.length)
+pub fn tls13_next_traffic_secret<H: Hkdf>(traffic_secret: &[u8]) -> Vec<u8> {
+    let mut output = vec![0u8; hash_len::<H>()];
+    expand_label_fixed::<H>(TLS13_LABEL_PREFIX, traffic_secret, b"traffic upd", &[], &mut output);
+    output
+}
+
+/// 4.6.1.  New Session Ticket Message
+///
+/// HKDF-Expand-Label(resumption_master_secret,
+///                   "resumption", ticket_nonce, Hash.length)
+pub fn tls13_resumption_psk<H: Hkdf>(resumption_master_secret: &[u8], ticket_nonce: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+    let mut output = vec![0u8; hash_len::<H>()];
+    tls13_hkdf_expand_label::<H>(resumption_master_secret, b"resumption", ticket_nonce, &mut output)?;
+    Ok(output)
+}
+
+
+fn hash_len<H: Hkdf>() -> usize {
+    <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN
+}
+
+// Derive-Secret(., "derived", "")
+fn derived_secret<H: Hkdf>(secret: &[u8]) -> Vec<u8> {
+    let empty_hash = H::build_hasher().digest();
+    let mut output = vec![0u8; hash_len::<H>()];
+    expand_label_fixed::<H>(TLS13_LABEL_PREFIX, secret, b"derived", empty_hash.array_as_slice(), &mut output);
+    output
+}
+
+
+//             0
+//             |
+//             v
+//   PSK ->  HKDF-Extract = Early Secret
+//             |
+//             +-----> Derive-Secret(., "ext binder" | "res binder", "")
+//             |                     = binder_key
+//             |
+//             +-----> Derive-Secret(., "c e traffic", ClientHello)
+//             |                     = client_early_traffic_secret
+//             |
+//             +-----> Derive-Secret(., "e exp master", ClientHello)
+//             |                     = early_exporter_master_secret
+//             v
+//       Derive-Secret(., "derived", "")
+//             |
+//             v
+//   (EC)DHE -> HKDF-Extract = Handshake Secret
+//             |
+//             +-----> Derive-Secret(., "c hs traffic",
+//             |                     ClientHello...ServerHello)
+//             |                     = client_handshake_traffic_secret
+//             |
+//             +-----> Derive-Secret(., "s hs traffic",
+//             |                     ClientHello...ServerHello)
+//             |                     = server_handshake_traffic_secret
+//             v
+//       Derive-Secret(., "derived", "")
+//             |
+//             v
+//   0 -> HKDF-Extract = Master Secret
+//             |
+//             +-----> Derive-Secret(., "c ap traffic",
+//             |                     ClientHello...server Finished)
+//             |                     = client_application_traffic_secret_0
+//             |
+//             +-----> Derive-Secret(., "s ap traffic",
+//             |                     ClientHello...server Finished)
+//             |                     = server_application_traffic_secret_0
+//             |
+//             +-----> Derive-Secret(., "exp master",
+//             |                     ClientHello...server Finished)
+//             |                     = exporter_master_secret
+//             |
+//             +-----> Derive-Secret(., "res master",
+//                                   ClientHello...client Finished)
+//                                   = resumption_master_secret
+
+/// TLS 1.3 Early Secret
+pub struct Tls13EarlySecret<H: Hkdf> {
+    secret: Vec<u8>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: Hkdf> Tls13EarlySecret<H> {
+    /// 没有 PSK 时使用 Hash.length 个 0 作为 PSK。
+    pub fn new(psk: Option<&[u8]>) -> Self {
+        let zeros = vec![0u8; hash_len::<H>()];
+        let psk = psk.unwrap_or(&zeros);
+        let secret = H::hkdf_extract(&zeros, psk).array_as_slice().to_vec();
+
+        Self { secret, _hasher: PhantomData }
+    }
+
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    /// `external` 为 true 时使用 "ext binder"（外部 PSK），否则使用 "res binder"（会话恢复）。
+    pub fn binder_key(&self, external: bool) -> Vec<u8> {
+        let label: &[u8] = if external { b"ext binder" } else { b"res binder" };
+        let empty_hash = H::build_hasher().digest();
+        let mut output = vec![0u8; hash_len::<H>()];
+        expand_label_fixed::<H>(TLS13_LABEL_PREFIX, &self.secret, label, empty_hash.array_as_slice(), &mut output);
+        output
+    }
+
+    pub fn client_early_traffic_secret(&self, client_hello_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"c e traffic", client_hello_hash)
+    }
+
+    pub fn early_exporter_master_secret(&self, client_hello_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"e exp master", client_hello_hash)
+    }
+
+    /// 没有 (EC)DHE 时（psk_ke 模式）使用 Hash.length 个 0。
+    pub fn handshake_secret(&self, ecdhe: Option<&[u8]>) -> Tls13HandshakeSecret<H> {
+        let zeros = vec![0u8; hash_len::<H>()];
+        let ecdhe = ecdhe.unwrap_or(&zeros);
+        let salt = derived_secret::<H>(&self.secret);
+        let secret = H::hkdf_extract(&salt, ecdhe).array_as_slice().to_vec();
+
+        Tls13HandshakeSecret { secret, _hasher: PhantomData }
+    }
+}
+
+/// TLS 1.3 Handshake Secret
+pub struct Tls13HandshakeSecret<H: Hkdf> {
+    secret: Vec<u8>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: Hkdf> Tls13HandshakeSecret<H> {
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    /// `transcript_hash` 为 ClientHello...ServerHello 的 Transcript-Hash。
+    pub fn client_handshake_traffic_secret(&self, transcript_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"c hs traffic", transcript_hash)
+    }
+
+    /// `transcript_hash` 为 ClientHello...ServerHello 的 Transcript-Hash。
+    pub fn server_handshake_traffic_secret(&self, transcript_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"s hs traffic", transcript_hash)
+    }
+
+    pub fn master_secret(&self) -> Tls13MasterSecret<H> {
+        let zeros = vec![0u8; hash_len::<H>()];
+        let salt = derived_secret::<H>(&self.secret);
+        let secret = H::hkdf_extract(&salt, &zeros).array_as_slice().to_vec();
+
+        Tls13MasterSecret { secret, _hasher: PhantomData }
+    }
+}
+
+/// TLS 1.3 Master Secret
+pub struct Tls13MasterSecret<H: Hkdf> {
+    secret: Vec<u8>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: Hkdf> Tls13MasterSecret<H> {
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    /// `transcript_hash` 为 ClientHello...server Finished 的 Transcript-Hash。
+    pub fn client_application_traffic_secret_0(&self, transcript_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"c ap traffic", transcript_hash)
+    }
+
+    /// `transcript_hash` 为 ClientHello...server Finished 的 Transcript-Hash。
+    pub fn server_application_traffic_secret_0(&self, transcript_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"s ap traffic", transcript_hash)
+    }
+
+    /// `transcript_hash` 为 ClientHello...server Finished 的 Transcript-Hash。
+    pub fn exporter_master_secret(&self, transcript_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"exp master", transcript_hash)
+    }
+
+    /// `transcript_hash` 为 ClientHello...client Finished 的 Transcript-Hash。
+    pub fn resumption_master_secret(&self, transcript_hash: &[u8]) -> Result<Vec<u8>, InvalidParams> {
+        tls13_derive_secret::<H>(&self.secret, b"res master", transcript_hash)
+    }
+}
+
+
+#[test]
+fn test_tls13_key_schedule_rfc8448() {
+    use crate::hash::Sha256;
+
+    // 3.  Simple 1-RTT Handshake
+    // https://tools.ietf.org/html/rfc8448#section-3
+    let early = Tls13EarlySecret::<Sha256>::new(None);
+    assert_eq!(early.secret(), &hex::decode("33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a").unwrap()[..]);
+    assert_eq!(&derived_secret::<Sha256>(early.secret())[..], &hex::decode("6f2615a108c702c5678f54fc9dbab69716c076189c48250cebeac3576c3611ba").unwrap()[..]);
+
+    let ecdhe = hex::decode("8bd4054fb55b9d63fdfbacf9f04b9f0d35e6d63f537563efd46272900f89492d").unwrap();
+    let handshake = early.handshake_secret(Some(&ecdhe));
+    assert_eq!(handshake.secret(), &hex::decode("1dc826e93606aa6fdc0aadc12f741b01046aa6b99f691ed221a9f0ca043fbeac").unwrap()[..]);
+
+    // ClientHello...ServerHello
+    let th = hex::decode("860c06edc07858ee8e78f0e7428c58edd6b43f2ca3e6e95f02ed063cf0e1cad8").unwrap();
+    let chs = handshake.client_handshake_traffic_secret(&th).unwrap();
+    let shs = handshake.server_handshake_traffic_secret(&th).unwrap();
+    assert_eq!(&chs[..], &hex::decode("b3eddb126e067f35a780b3abf45e2d8f3b1a950738f52e9600746a0e27a55a21").unwrap()[..]);
+    assert_eq!(&shs[..], &hex::decode("b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38").unwrap()[..]);
+
+    let mut key = [0u8; 16];
+    let mut iv = [0u8; 12];
+    tls13_traffic_key_iv::<Sha256>(&shs, &mut key, &mut iv).unwrap();
+    assert_eq!(&key[..], &hex::decode("3fce516009c21727d0f2e4e86ee403bc").unwrap()[..]);
+    assert_eq!(&iv[..], &hex::decode("5d313eb2671276ee13000b30").unwrap()[..]);
+    tls13_traffic_key_iv::<Sha256>(&chs, &mut key, &mut iv).unwrap();
+    assert_eq!(&key[..], &hex::decode("dbfaa693d1762c5b666af5d950258d01").unwrap()[..]);
+    assert_eq!(&iv[..], &hex::decode("5bd3c71b836e0b76bb73265f").unwrap()[..]);
+
+    assert_eq!(&tls13_finished_key::<Sha256>(&shs)[..], &hex::decode("008d3b66f816ea559f96b537e885c31fc068bf492c652f01f288a1d8cdc19fc8").unwrap()[..]);
+
+    assert_eq!(&derived_secret::<Sha256>(handshake.secret())[..], &hex::decode("43de77e0c77713859a944db9db2590b53190a65b3ee2e4f12dd7a0bb7ce254b4").unwrap()[..]);
+    let master = handshake.master_secret();
+    assert_eq!(master.secret(), &hex::decode("18df06843d13a08bf2a449844c5f8a478001bc4d4c627984d5a41da8d0402919").unwrap()[..]);
+
+    // ClientHello...server Finished
+    let th = hex::decode("9608102a0f1ccc6db6250b7b7e417b1a000eaada3daae4777a7686c9ff83df13").unwrap();
+    let cap = master.client_application_traffic_secret_0(&th).unwrap();
+    let sap = master.server_application_traffic_secret_0(&th).unwrap();
+    assert_eq!(&cap[..], &hex::decode("9e40646ce79a7f9dc05af8889bce6552875afa0b06df0087f792ebb7c17504a5").unwrap()[..]);
+    assert_eq!(&sap[..], &hex::decode("a11af9f05531f856ad47116b45a950328204b4f44bfb6b3a4b4f1f3fcb631643").unwrap()[..]);
+    assert_eq!(&master.exporter_master_secret(&th).unwrap()[..], &hex::decode("fe22f881176eda18eb8f44529e6792c50c9a3f89452f68d8ae311b4309d3cf50").unwrap()[..]);
+
+    tls13_traffic_key_iv::<Sha256>(&sap, &mut key, &mut iv).unwrap();
+    assert_eq!(&key[..], &hex::decode("9f02283b6c9c07efc26bb9f2ac92e356").unwrap()[..]);
+    assert_eq!(&iv[..], &hex::decode("cf782b88dd83549aadf1e984").unwrap()[..]);
+
+    assert_eq!(&tls13_next_traffic_secret::<Sha256>(&sap)[..], &hex::decode("51921b8aa3001976eb401d0a4319a8516416a6c56001a357e5d162031e84f916").unwrap()[..]);
+
+    // ClientHello...client Finished
+    let th = hex::decode("209145a96ee8e2a122ff810047cc952684658d6049e86429426db87c54ad143d").unwrap();
+    let rms = master.resumption_master_secret(&th).unwrap();
+    assert_eq!(&rms[..], &hex::decode("7df235f2031d2a051287d02b0241b0bfdaf86cc856231f2d5aba46c434ec196c").unwrap()[..]);
+
+    // 4.  Resumed 0-RTT Handshake
+    // https://tools.ietf.org/html/rfc8448#section-4
+    let psk = tls13_resumption_psk::<Sha256>(&rms, &[0, 0]).unwrap();
+    assert_eq!(&psk[..], &hex::decode("4ecd0eb6ec3b4d87f5d6028f922ca4c5851a277fd41311c9e62d2c9492e1c4f3").unwrap()[..]);
+
+    let early = Tls13EarlySecret::<Sha256>::new(Some(&psk));
+    assert_eq!(early.secret(), &hex::decode("9b2188e9b2fc6d64d71dc329900e20bb41915000f678aa839cbb797cb7d8332c").unwrap()[..]);
+    assert_eq!(&early.binder_key(false)[..], &hex::decode("69fe131a3bbad5d63c64eebcc30e395b9d8107726a13d074e389dbc8a4e47256").unwrap()[..]);
+}
+
+#[test]
+fn test_tls13_hkdf_expand_label() {
+    use crate::hash::Sha256;
+
+    let secret = [0u8; 32];
+    let mut output = vec![0u8; u16::MAX as usize + 1];
+    assert!(tls13_hkdf_expand_label::<Sha256>(&secret, b"key", &[], &mut output).is_err());
+    // 超出 255 * HashLen
+    let mut output = [0u8; 255 * 32 + 1];
+    assert!(tls13_hkdf_expand_label::<Sha256>(&secret, b"key", &[], &mut output).is_err());
+
+    // opaque label<7..255>, opaque context<0..255>
+    let mut output = [0u8; 32];
+    assert_eq!(tls13_hkdf_expand_label::<Sha256>(&secret, &[b'a'; 249], &[], &mut output), Ok(()));
+    assert_eq!(tls13_hkdf_expand_label::<Sha256>(&secret, &[b'a'; 250], &[], &mut output), Err(InvalidParams));
+    assert_eq!(dtls13_hkdf_expand_label::<Sha256>(&secret, &[b'a'; 250], &[], &mut output), Err(InvalidParams));
+    assert_eq!(tls13_hkdf_expand_label::<Sha256>(&secret, b"key", &[0u8; 255], &mut output), Ok(()));
+    assert_eq!(tls13_hkdf_expand_label::<Sha256>(&secret, b"key", &[0u8; 256], &mut output), Err(InvalidParams));
+    assert_eq!(tls13_derive_secret::<Sha256>(&secret, b"c hs traffic", &[0u8; 256]), Err(InvalidParams));
+}
@@ -0,0 +1,89 @@
+// Transport Layer Security (TLS)
+// 
+// The Transport Layer Security (TLS) Protocol Version 1.2
+// https://tools.ietf.org/html/rfc5246
+// 
+// The Transport Layer Security (TLS) Protocol Version 1.3
+// https://tools.ietf.org/html/rfc8446
+// 
+// NOTE: 这里只包含 TLS 中与密码学相关的部分（密钥派生、记录层保护），不包含握手状态机。
+
+mod aead;
+pub use self::aead::*;
+#[cfg(any(feature = "tls1_1", feature = "tls1_2"))]
+mod cbc;
+#[cfg(any(feature = "tls1_1", feature = "tls1_2"))]
+pub use self::cbc::*;
+
+#[cfg(feature = "tls1_2")]
+mod prf;
+#[cfg(feature = "tls1_3")]
+mod key_schedule;
+
+#[cfg(feature = "tls1_2")]
+pub use self::prf::*;
+#[cfg(feature = "tls1_3")]
+pub use self::key_schedule::*;
+
+#[cfg(feature = "tls1_3")]
+mod header_protection;
+#[cfg(feature = "tls1_3")]
+pub use self::header_protection::*;
+
+#[cfg(any(feature = "tls1_1", feature = "tls1_2"))]
+pub mod tls12;
+#[cfg(feature = "tls1_3")]
+pub mod tls13;
+#[cfg(feature = "dtls")]
+pub mod dtls;
+
+
+pub const TLS1_1_VERSION: u16 = 0x0302;
+pub const TLS1_2_VERSION: u16 = 0x0303;
+
+// 5.  Record Protocol
+// https://tools.ietf.org/html/rfc8446#section-5
+pub const CONTENT_TYPE_CHANGE_CIPHER_SPEC: u8 = 20;
+pub const CONTENT_TYPE_ALERT: u8              = 21;
+pub const CONTENT_TYPE_HANDSHAKE: u8          = 22;
+pub const CONTENT_TYPE_APPLICATION_DATA: u8   = 23;
+
+/// 记录层明文的最大长度：2^14
+pub const MAX_FRAGMENT_LEN: usize = 16384;
+/// 记录层头部长度：ContentType (1) + ProtocolVersion (2) + Length (2)
+pub const RECORD_HEADER_LEN: usize = 5;
+
+
+/// 记录层保护失败的原因，对应需要发送的 Alert。
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum RecordError {
+    /// 认证失败（bad_record_mac）
+    BadRecordMac,
+    /// 记录过长（record_overflow）
+    RecordOverflow,
+    /// 记录格式错误（decode_error）
+    DecodeError,
+    /// 记录类型错误或者没有找到非零的 ContentType（unexpected_message）
+    UnexpectedMessage,
+    /// 序列号或者密钥的使用次数已经达到上限，需要更新密钥。
+    KeyUpdateRequired,
+    /// 重放的记录（DTLS），应当直接丢弃。
+    Replayed,
+    /// 认证失败的记录数量达到上限（DTLS 1.3），需要更新密钥或者关闭连接。
+    AeadLimitReached,
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::BadRecordMac => write!(f, "Bad Record Mac"),
+            RecordError::RecordOverflow => write!(f, "Record Overflow"),
+            RecordError::DecodeError => write!(f, "Decode Error"),
+            RecordError::UnexpectedMessage => write!(f, "Unexpected Message"),
+            RecordError::KeyUpdateRequired => write!(f, "Key Update Required"),
+            RecordError::Replayed => write!(f, "Replayed"),
+            RecordError::AeadLimitReached => write!(f, "AEAD Limit Reached"),
+        }
+    }
+}
+impl std::error::Error for RecordError { }
@@ -0,0 +1,155 @@
+// 5.  HMAC and the Pseudorandom Function
+// https://tools.ietf.org/html/rfc5246#section-5
+// 
+// Transport Layer Security (TLS) Session Hash and Extended Master Secret Extension
+// https://tools.ietf.org/html/rfc7627
+use crate::hmac::{Hmac, HmacState};
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
+
+
+pub const TLS12_MASTER_SECRET_LEN: usize = 48;
+pub const TLS12_RANDOM_LEN: usize        = 32;
+
+
+/// TLS 1.2 PRF: PRF(secret, label, seed) = P_<hash>(secret, label + seed)
+///
+/// NOTE: `seed` 可以由多个片段组成（例如 ClientHello.random + ServerHello.random），不需要先拼接。
+pub fn tls12_prf<H: Hmac>(secret: &[u8], label: &[u8], seed: &[&[u8]], output: &mut [u8])
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    // P_hash(secret, seed) = HMAC_hash(secret, A(1) + seed) +
+    //                        HMAC_hash(secret, A(2) + seed) +
+    //                        HMAC_hash(secret, A(3) + seed) + ...
+    // 
+    // A(0) = seed
+    // A(i) = HMAC_hash(secret, A(i-1))
+    // 
+    // NOTE: secret 只处理一次，之后每次 HMAC 都从已经处理过密钥的状态 `clone` 出来。
+    let keyed = HmacState::<H>::new(secret);
+
+    let mut m = keyed.clone();
+    m.update(label);
+    for data in seed.iter() {
+        m.update(data);
+    }
+    let mut a = m.finalize();
+
+    let hash_len = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+    for chunk in output.chunks_mut(hash_len) {
+        let mut m = keyed.clone();
+        m.update(a.array_as_slice());
+        m.update(label);
+        for data in seed.iter() {
+            m.update(data);
+        }
+        let t = m.finalize();
+        chunk.copy_from_slice(&t.array_as_slice()[..chunk.len()]);
+
+        let mut m = keyed.clone();
+        m.update(a.array_as_slice());
+        a = m.finalize();
+    }
+}
+
+/// master_secret = PRF(pre_master_secret, "master secret",
+///                     ClientHello.random + ServerHello.random) [0..47];
+pub fn tls12_master_secret<H: Hmac>(pre_master_secret: &[u8],
+                                    client_random: &[u8; TLS12_RANDOM_LEN],
+                                    server_random: &[u8; TLS12_RANDOM_LEN]) -> [u8; TLS12_MASTER_SECRET_LEN]
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    let mut master_secret = [0u8; TLS12_MASTER_SECRET_LEN];
+    tls12_prf::<H>(pre_master_secret, b"master secret", &[client_random, server_random], &mut master_secret);
+    master_secret
+}
+
+/// master_secret = PRF(pre_master_secret, "extended master secret",
+///                     session_hash) [0..47];
+///
+/// `session_hash` 为握手消息（直到 ClientKeyExchange）的 Hash。
+pub fn tls12_extended_master_secret<H: Hmac>(pre_master_secret: &[u8], session_hash: &[u8]) -> [u8; TLS12_MASTER_SECRET_LEN]
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    let mut master_secret = [0u8; TLS12_MASTER_SECRET_LEN];
+    tls12_prf::<H>(pre_master_secret, b"extended master secret", &[session_hash], &mut master_secret);
+    master_secret
+}
+
+/// key_block = PRF(SecurityParameters.master_secret,
+///                 "key expansion",
+///                 SecurityParameters.server_random +
+///                 SecurityParameters.client_random);
+///
+/// 依次切分为 client_write_MAC_key、server_write_MAC_key、client_write_key、
+/// server_write_key、client_write_IV、server_write_IV。
+pub fn tls12_key_block<H: Hmac>(master_secret: &[u8; TLS12_MASTER_SECRET_LEN],
+                                client_random: &[u8; TLS12_RANDOM_LEN],
+                                server_random: &[u8; TLS12_RANDOM_LEN],
+                                key_block: &mut [u8])
+where
+    <H as BuildCryptoHasher>::Hasher: Clone,
+{
+    tls12_prf::<H>(master_secret, b"key expansion", &[server_random, client_random], key_block);
+}
+
+
+#[cfg(test)]
+fn test_random(start: u8) -> [u8; TLS12_RANDOM_LEN] {
+    let mut random = [0u8; TLS12_RANDOM_LEN];
+    for i in 0..TLS12_RANDOM_LEN {
+        random[i] = start + i as u8;
+    }
+    random
+}
+
+#[test]
+fn test_tls12_prf() {
+    use crate::hash::{Sha256, Sha384};
+
+    // NOTE: 与 OpenSSL `openssl kdf TLS1-PRF` 的输出一致。
+    let secret = hex::decode("9bbe436ba940f017b17652849a71db35").unwrap();
+    let seed = hex::decode("a0ba9f936cda311827a6f796ffd5198c").unwrap();
+    let mut output = [0u8; 100];
+    tls12_prf::<Sha256>(&secret, b"test label", &[&seed], &mut output);
+    assert_eq!(&output[..], &hex::decode("e3f229ba727be17b8d122620557cd453c2aab21d07c3d495329b52d4e61edb5a\
+6b301791e90d35c9c9a46b4e14baf9af0fa022f7077def17abfd3797c0564bab\
+4fbc91666e9def9b97fce34f796789baa48082d122ee42c5a72e5a5110fff701\
+87347b66").unwrap()[..]);
+
+    let secret = hex::decode("b80b733d6ceefcdc71566ea48e5567df").unwrap();
+    let seed = hex::decode("cd665cf6a8447dd6ff8b27555edb7465").unwrap();
+    let mut output = [0u8; 148];
+    tls12_prf::<Sha384>(&secret, b"test label", &[&seed[..5], &seed[5..]], &mut output);
+    assert_eq!(&output[..], &hex::decode("7b0c18e9ced410ed1804f2cfa34a336a1c14dffb4900bb5fd7942107e81c83cd\
+e9ca0faa60be9fe34f82b1233c9146a0e534cb400fed2700884f9dc236f80edd\
+8bfa961144c9e8d792eca722a7b32fc3d416d473ebc2c5fd4abfdad05d918425\
+9b5bf8cd4d90fa0d31e2dec479e4f1a26066f2eea9a69236a3e52655c9e9aee6\
+91c8f3a26854308d5eaa3be85e0990703d73e56f").unwrap()[..]);
+}
+
+#[test]
+fn test_tls12_key_derivation() {
+    use crate::hash::{Sha256, Sha384};
+
+    let pre_master_secret: Vec<u8> = (0..48).collect();
+    let client_random = test_random(100);
+    let server_random = test_random(200);
+
+    let master_secret = tls12_master_secret::<Sha256>(&pre_master_secret, &client_random, &server_random);
+    assert_eq!(&master_secret[..], &hex::decode("e25039f679b6fa968aae7b5752a611e27e1a603f924837aaa59aa6dc01abcd38\
+ec5adc9b9a894938a19e50d6cde9eaf7").unwrap()[..]);
+
+    let mut key_block = [0u8; 72];
+    tls12_key_block::<Sha256>(&master_secret, &client_random, &server_random, &mut key_block);
+    assert_eq!(&key_block[..], &hex::decode("3ae41ae23b6022c9f89a9777799ceeb99e46b1c581178a8ff8be0cacc87ae1ff\
+b82a41a3dd9186d80f6374dcc56ba3e9e39da49f16d6c414e48d30d631f833be\
+70a925c9b9ae65d7").unwrap()[..]);
+
+    let session_hash = test_random(50);
+    let master_secret = tls12_extended_master_secret::<Sha384>(&pre_master_secret, &session_hash);
+    assert_eq!(&master_secret[..], &hex::decode("69a150fbec83493b599f4360763235e88222c3bb7636f2958d9add7f4005a433\
+9a447d28e3e8cdf1ea60559f235f851d").unwrap()[..]);
+}
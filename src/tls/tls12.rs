@@ -0,0 +1,623 @@
+// 6.2.3.  Record Payload Protection
+// https://tools.ietf.org/html/rfc5246#section-6.2.3
+//
+// Encrypt-then-MAC for Transport Layer Security (TLS) and
+// Datagram Transport Layer Security (DTLS)
+// https://tools.ietf.org/html/rfc7366
+//
+// Lucky Thirteen: Breaking the TLS and DTLS Record Protocols
+// http://www.isg.rhul.ac.uk/tls/TLStiming.pdf
+//
+// NOTE: TLS 1.1 和 TLS 1.2 的 CBC 记录格式相同（显式 IV），TLS 1.1 不支持 AEAD。
+//       RC4 等流密码已经被禁止使用（RFC 7465），这里不提供。
+use super::{TlsCbcCipher, RecordError};
+use super::{MAX_FRAGMENT_LEN, RECORD_HEADER_LEN, TLS1_1_VERSION, TLS1_2_VERSION};
+#[cfg(feature = "tls1_2")]
+use super::{TlsAead, xor_nonce};
+use crate::hmac::{Hmac, HmacState};
+use crate::hash::{Array, CryptoHasher, BuildCryptoHasher};
+use crate::random::{CryptoRng, ChaChaRng};
+
+use subtle;
+
+use std::marker::PhantomData;
+
+
+/// TLSCiphertext.length 的最大值：2^14 + 2048
+pub const MAX_CIPHERTEXT_LEN: usize = MAX_FRAGMENT_LEN + 2048;
+
+// seq_num + TLSCompressed.type + TLSCompressed.version + TLSCompressed.length
+pub(super) fn pseudo_header(seq: u64, content_type: u8, version: u16, len: usize) -> [u8; 13] {
+    let mut header = [0u8; 13];
+    header[0..8].copy_from_slice(&seq.to_be_bytes());
+    header[8] = content_type;
+    header[9..11].copy_from_slice(&version.to_be_bytes());
+    header[11..13].copy_from_slice(&(len as u16).to_be_bytes());
+    header
+}
+
+// 检查记录头部，返回 TLSCiphertext.length
+fn parse_header(record: &[u8], version: u16) -> Result<usize, RecordError> {
+    if record.len() < RECORD_HEADER_LEN {
+        return Err(RecordError::DecodeError);
+    }
+    if u16::from_be_bytes([record[1], record[2]]) != version {
+        return Err(RecordError::DecodeError);
+    }
+
+    let len = u16::from_be_bytes([record[3], record[4]]) as usize;
+    if len != record.len() - RECORD_HEADER_LEN {
+        return Err(RecordError::DecodeError);
+    }
+    if len > MAX_CIPHERTEXT_LEN {
+        return Err(RecordError::RecordOverflow);
+    }
+
+    Ok(len)
+}
+
+
+// TLS 1.2 / DTLS 1.2 AEAD 记录的加解密，序列号（DTLS 中包括 Epoch）由调用方管理。
+//
+// NOTE: Nonce 的构造方式参见 `AeadRecordProtector`。
+#[cfg(feature = "tls1_2")]
+pub(super) struct AeadRecordCipher<C: TlsAead> {
+    key: Vec<u8>,
+    iv: [u8; 12],
+    explicit_nonce_len: usize,
+    _cipher: PhantomData<C>,
+}
+
+#[cfg(feature = "tls1_2")]
+impl<C: TlsAead> AeadRecordCipher<C> {
+    pub(super) fn new(key: &[u8], fixed_iv: &[u8]) -> Self {
+        assert_eq!(C::NONCE_LEN, 12);
+        assert_eq!(key.len(), C::KEY_LEN);
+        assert!(fixed_iv.len() == 4 || fixed_iv.len() == 12);
+
+        let mut iv = [0u8; 12];
+        iv[..fixed_iv.len()].copy_from_slice(fixed_iv);
+        let explicit_nonce_len = 12 - fixed_iv.len();
+
+        Self { key: key.to_vec(), iv, explicit_nonce_len, _cipher: PhantomData }
+    }
+
+    pub(super) fn explicit_nonce_len(&self) -> usize {
+        self.explicit_nonce_len
+    }
+
+    fn nonce(&self, seq_num: u64, explicit_nonce: &[u8]) -> [u8; 12] {
+        if self.explicit_nonce_len == 0 {
+            xor_nonce(&self.iv, seq_num)
+        } else {
+            let mut nonce = self.iv;
+            nonce[4..].copy_from_slice(explicit_nonce);
+            nonce
+        }
+    }
+
+    /// 把 `header || length || explicit_nonce || 密文 || Tag` 追加到 `record` 后面。
+    ///
+    /// `header` 为记录头部中 Length 字段之前的部分，调用方需要检查 `content` 的长度。
+    pub(super) fn seal(&self, seq_num: u64, content_type: u8, version: u16, header: &[u8], content: &[u8], record: &mut Vec<u8>) {
+        // NOTE: 使用 seq_num 作为 explicit_nonce，保证同一个密钥下不会重复。
+        let seq_num_bytes = seq_num.to_be_bytes();
+        let explicit_nonce = &seq_num_bytes[..self.explicit_nonce_len];
+        let nonce = self.nonce(seq_num, explicit_nonce);
+
+        // additional_data = seq_num + TLSCompressed.type +
+        //                   TLSCompressed.version + TLSCompressed.length;
+        let aad = pseudo_header(seq_num, content_type, version, content.len());
+
+        let header_len = header.len() + 2;
+        let len = self.explicit_nonce_len + content.len() + C::TAG_LEN;
+        let start = record.len();
+        record.reserve(header_len + len);
+        record.extend_from_slice(header);
+        record.extend_from_slice(&(len as u16).to_be_bytes());
+        record.extend_from_slice(explicit_nonce);
+        record.extend_from_slice(content);
+        record.resize(start + header_len + len, 0);
+
+        let payload = &mut record[start + header_len + self.explicit_nonce_len..];
+        let mut cipher = C::new(&self.key, &nonce);
+        cipher.aead_encrypt(&aad, payload);
+    }
+
+    /// 原地解密 `fragment`（explicit_nonce || 密文 || Tag），返回明文的长度。
+    ///
+    /// NOTE: 明文位于 `fragment[explicit_nonce_len..]`。
+    pub(super) fn open(&self, seq_num: u64, content_type: u8, version: u16, fragment: &mut [u8]) -> Result<usize, RecordError> {
+        if fragment.len() < self.explicit_nonce_len + C::TAG_LEN {
+            return Err(RecordError::DecodeError);
+        }
+
+        let plen = fragment.len() - self.explicit_nonce_len - C::TAG_LEN;
+        let aad = pseudo_header(seq_num, content_type, version, plen);
+
+        let (explicit_nonce, payload) = fragment.split_at_mut(self.explicit_nonce_len);
+        let nonce = self.nonce(seq_num, explicit_nonce);
+        let mut cipher = C::new(&self.key, &nonce);
+        if !cipher.aead_decrypt(&aad, payload) {
+            return Err(RecordError::BadRecordMac);
+        }
+        if plen > MAX_FRAGMENT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        Ok(plen)
+    }
+}
+
+/// TLS 1.2 AEAD 记录层保护（单个方向）
+///
+/// `fixed_iv` 的长度决定 Nonce 的构造方式：
+///
+/// *   4 Bytes: AES-GCM (RFC 5288) / AES-CCM (RFC 6655)，
+///     nonce = fixed_iv || explicit_nonce，explicit_nonce（即序列号）随记录一起发送。
+/// *   12 Bytes: ChaCha20-Poly1305 (RFC 7905)，nonce = fixed_iv ^ seq_num，没有显式的 Nonce。
+#[cfg(feature = "tls1_2")]
+pub struct AeadRecordProtector<C: TlsAead> {
+    cipher: AeadRecordCipher<C>,
+    seq: u64,
+}
+
+#[cfg(feature = "tls1_2")]
+impl<C: TlsAead> AeadRecordProtector<C> {
+    pub fn new(key: &[u8], fixed_iv: &[u8]) -> Self {
+        Self { cipher: AeadRecordCipher::new(key, fixed_iv), seq: 0 }
+    }
+
+    pub fn sequence_number(&self) -> u64 {
+        self.seq
+    }
+
+    /// 加密一条记录，并把完整的 TLSCiphertext（包括头部）追加到 `record` 后面。
+    pub fn seal(&mut self, content_type: u8, content: &[u8], record: &mut Vec<u8>) -> Result<(), RecordError> {
+        if self.seq >= C::RECORD_LIMIT {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+        if content.len() > MAX_FRAGMENT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        let version = TLS1_2_VERSION.to_be_bytes();
+        let header = [content_type, version[0], version[1]];
+        self.cipher.seal(self.seq, content_type, TLS1_2_VERSION, &header, content, record);
+
+        self.seq += 1;
+
+        Ok(())
+    }
+
+    /// 原地解密一条完整的 TLSCiphertext（包括头部），返回 ContentType 以及明文。
+    pub fn open<'a>(&mut self, record: &'a mut [u8]) -> Result<(u8, &'a [u8]), RecordError> {
+        if self.seq == u64::MAX {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+
+        parse_header(record, TLS1_2_VERSION)?;
+
+        let content_type = record[0];
+        let plen = self.cipher.open(self.seq, content_type, TLS1_2_VERSION, &mut record[RECORD_HEADER_LEN..])?;
+
+        self.seq += 1;
+
+        let record: &'a [u8] = record;
+        let start = RECORD_HEADER_LEN + self.cipher.explicit_nonce_len();
+        Ok((content_type, &record[start..start + plen]))
+    }
+}
+
+
+// NOTE: 以下的函数返回全 1（真）或者全 0（假）的掩码，要求输入小于 2^(usize::BITS - 1)。
+#[inline]
+fn ct_lt(a: usize, b: usize) -> usize {
+    (a.wrapping_sub(b) >> (usize::BITS - 1)).wrapping_neg()
+}
+
+#[inline]
+fn ct_le(a: usize, b: usize) -> usize {
+    !ct_lt(b, a)
+}
+
+#[inline]
+fn ct_eq(a: usize, b: usize) -> usize {
+    ct_le(a, b) & ct_le(b, a)
+}
+
+/// TLS 1.2 / TLS 1.1 CBC + HMAC 记录层保护（单个方向）
+///
+/// `encrypt_then_mac` 为 true 时使用 RFC 7366 的 Encrypt-then-MAC，
+/// 否则使用 MAC-then-Encrypt（解密时填充和 MAC 的检查都是常量时间的）。
+pub struct CbcHmacRecordProtector<C: TlsCbcCipher, H: Hmac> {
+    version: u16,
+    enc_key: Vec<u8>,
+    mac_key: Vec<u8>,
+    encrypt_then_mac: bool,
+    seq: u64,
+    rng: ChaChaRng,
+    _cipher: PhantomData<C>,
+    _hasher: PhantomData<H>,
+}
+
+impl<C: TlsCbcCipher, H: Hmac> CbcHmacRecordProtector<C, H> {
+    pub const MAC_LEN: usize = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::OUTPUT_LEN;
+
+
+    pub fn new(version: u16, enc_key: &[u8], mac_key: &[u8], encrypt_then_mac: bool) -> Self {
+        assert!(version == TLS1_1_VERSION || version == TLS1_2_VERSION);
+        assert_eq!(enc_key.len(), C::KEY_LEN);
+        // NOTE: 常量时间的 MAC 提取使用固定大小的缓冲区。
+        assert!(Self::MAC_LEN <= 64);
+
+        Self {
+            version,
+            enc_key: enc_key.to_vec(),
+            mac_key: mac_key.to_vec(),
+            encrypt_then_mac,
+            seq: 0,
+            rng: ChaChaRng::new(),
+            _cipher: PhantomData,
+            _hasher: PhantomData,
+        }
+    }
+
+    pub fn sequence_number(&self) -> u64 {
+        self.seq
+    }
+
+    fn mac(&self, header: &[u8; 13], data: &[u8]) -> <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::Output {
+        let mut m = HmacState::<H>::new(&self.mac_key);
+        m.update(header);
+        m.update(data);
+        m.finalize()
+    }
+
+    /// 加密一条记录，并把完整的 TLSCiphertext（包括头部）追加到 `record` 后面。
+    ///
+    /// NOTE: 每条记录使用随机的 IV，填充使用最小长度。
+    pub fn seal(&mut self, content_type: u8, content: &[u8], record: &mut Vec<u8>) -> Result<(), RecordError> {
+        let mut iv = [0u8; 16];
+        let iv = &mut iv[..C::BLOCK_LEN];
+        self.rng.fill_bytes(iv);
+
+        self.seal_with_iv(content_type, content, iv, record)
+    }
+
+    fn seal_with_iv(&mut self, content_type: u8, content: &[u8], iv: &[u8], record: &mut Vec<u8>) -> Result<(), RecordError> {
+        if self.seq == u64::MAX {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+        if content.len() > MAX_FRAGMENT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        let block_len = C::BLOCK_LEN;
+        let mac_len = if self.encrypt_then_mac { 0 } else { Self::MAC_LEN };
+        // NOTE: padding_length 加上其本身的 1 Byte。
+        let pad_len = block_len - (content.len() + mac_len) % block_len;
+        let enc_len = content.len() + mac_len + pad_len;
+        let len = if self.encrypt_then_mac { block_len + enc_len + Self::MAC_LEN } else { block_len + enc_len };
+
+        let start = record.len();
+        record.reserve(RECORD_HEADER_LEN + len);
+        record.push(content_type);
+        record.extend_from_slice(&self.version.to_be_bytes());
+        record.extend_from_slice(&(len as u16).to_be_bytes());
+        record.extend_from_slice(iv);
+        record.extend_from_slice(content);
+        if !self.encrypt_then_mac {
+            // MAC(MAC_write_key, seq_num +
+            //                    TLSCompressed.type +
+            //                    TLSCompressed.version +
+            //                    TLSCompressed.length +
+            //                    TLSCompressed.fragment);
+            let header = pseudo_header(self.seq, content_type, self.version, content.len());
+            let mac = self.mac(&header, content);
+            record.extend_from_slice(mac.array_as_slice());
+        }
+        for _ in 0..pad_len {
+            record.push((pad_len - 1) as u8);
+        }
+
+        let enc_start = start + RECORD_HEADER_LEN + block_len;
+        let mut cipher = C::new(&self.enc_key, iv);
+        cipher.encrypt(&mut record[enc_start..enc_start + enc_len]);
+
+        if self.encrypt_then_mac {
+            // MAC(MAC_write_key, seq_num +
+            //     TLSCipherText.type +
+            //     TLSCipherText.version +
+            //     TLSCipherText.length +
+            //     IV +
+            //     ENC(content + padding + padding_length));
+            let header = pseudo_header(self.seq, content_type, self.version, block_len + enc_len);
+            let mac = self.mac(&header, &record[start + RECORD_HEADER_LEN..]);
+            record.extend_from_slice(mac.array_as_slice());
+        }
+
+        self.seq += 1;
+
+        Ok(())
+    }
+
+    /// 原地解密一条完整的 TLSCiphertext（包括头部），返回 ContentType 以及明文。
+    ///
+    /// NOTE: 填充错误和 MAC 错误都返回 `BadRecordMac`，不区分具体的原因。
+    pub fn open<'a>(&mut self, record: &'a mut [u8]) -> Result<(u8, &'a [u8]), RecordError> {
+        if self.seq == u64::MAX {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+
+        let len = parse_header(record, self.version)?;
+        let content_type = record[0];
+
+        let (start, content_len) = if self.encrypt_then_mac {
+            self.open_encrypt_then_mac(content_type, &mut record[RECORD_HEADER_LEN..])?
+        } else {
+            self.open_mac_then_encrypt(content_type, &mut record[RECORD_HEADER_LEN..])?
+        };
+        if content_len > MAX_FRAGMENT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        self.seq += 1;
+
+        let record: &'a [u8] = record;
+        let start = RECORD_HEADER_LEN + start;
+        Ok((content_type, &record[start..start + content_len]))
+    }
+
+    fn open_encrypt_then_mac(&self, content_type: u8, fragment: &mut [u8]) -> Result<(usize, usize), RecordError> {
+        let block_len = C::BLOCK_LEN;
+        let len = fragment.len();
+        if len < block_len * 2 + Self::MAC_LEN || !(len - Self::MAC_LEN).is_multiple_of(block_len) {
+            return Err(RecordError::BadRecordMac);
+        }
+
+        // NOTE: 先验证 MAC，之后的解密和填充检查都不会泄露任何信息。
+        let (data, input_mac) = fragment.split_at_mut(len - Self::MAC_LEN);
+        let header = pseudo_header(self.seq, content_type, self.version, data.len());
+        let mac = self.mac(&header, data);
+        if !bool::from(subtle::ConstantTimeEq::ct_eq(mac.array_as_slice(), &input_mac[..])) {
+            return Err(RecordError::BadRecordMac);
+        }
+
+        let (iv, ciphertext) = data.split_at_mut(block_len);
+        let mut cipher = C::new(&self.enc_key, iv);
+        cipher.decrypt(ciphertext);
+
+        let plen = ciphertext.len();
+        let pad = ciphertext[plen - 1] as usize;
+        if pad + 1 > plen || ciphertext[plen - 1 - pad..].iter().any(|&b| b as usize != pad) {
+            return Err(RecordError::BadRecordMac);
+        }
+
+        Ok((block_len, plen - pad - 1))
+    }
+
+    fn open_mac_then_encrypt(&self, content_type: u8, fragment: &mut [u8]) -> Result<(usize, usize), RecordError> {
+        let block_len = C::BLOCK_LEN;
+        let mac_len = Self::MAC_LEN;
+        let len = fragment.len();
+        // NOTE: 长度只和公开的信息有关，可以直接返回。
+        if !len.is_multiple_of(block_len) || len < block_len + std::cmp::max(block_len, (mac_len + 1).div_ceil(block_len) * block_len) {
+            return Err(RecordError::BadRecordMac);
+        }
+
+        let (iv, plaintext) = fragment.split_at_mut(block_len);
+        let mut cipher = C::new(&self.enc_key, iv);
+        cipher.decrypt(plaintext);
+
+        let plaintext: &[u8] = plaintext;
+        let plen = plaintext.len();
+
+        // 1. 常量时间检查填充：检查最后 256 Bytes（或者全部）。
+        let pad = plaintext[plen - 1] as usize;
+        let mut good = ct_le(pad + 1 + mac_len, plen);
+        let to_check = std::cmp::min(256, plen);
+        for (i, &b) in plaintext.iter().rev().take(to_check).enumerate() {
+            good &= !(ct_le(i, pad) & !ct_eq(b as usize, pad));
+        }
+        // NOTE: 填充错误时当作没有填充继续计算 MAC，避免出现时间差异（RFC 5246 Section 6.2.3.2）。
+        let pad = pad & good;
+        let content_len = plen - mac_len - 1 - pad;
+
+        // 2. 常量时间提取 MAC：MAC 可能出现的位置只和公开的长度有关。
+        let mut input_mac = [0u8; 64];
+        let scan_start = plen.saturating_sub(mac_len + 256);
+        for (j, &b) in plaintext.iter().enumerate().take(plen - 1).skip(scan_start) {
+            for (k, m) in input_mac[..mac_len].iter_mut().enumerate() {
+                *m |= b & (ct_eq(j, content_len + k) as u8);
+            }
+        }
+
+        // 3. 计算 MAC，并补齐哈希函数的压缩次数，使得总的计算量和 content_len 无关。
+        let header = pseudo_header(self.seq, content_type, self.version, content_len);
+        let mac = self.mac(&header, &plaintext[..content_len]);
+
+        let hash_block_len = <<H as BuildCryptoHasher>::Hasher as CryptoHasher>::BLOCK_LEN;
+        // NOTE: MD 结构的哈希函数末尾至少填充 0x80 和消息长度（SHA-384/SHA-512 为 16 Bytes，其余为 8 Bytes）。
+        let length_field_len = if hash_block_len == 128 { 16 } else { 8 };
+        let compressions = |n: usize| (hash_block_len + header.len() + n + 1 + length_field_len).div_ceil(hash_block_len);
+        let max_content_len = plen - mac_len - 1;
+        let extra = compressions(max_content_len) - compressions(content_len);
+
+        let dummy_block = [0u8; 128];
+        let mut dummy = H::build_hasher();
+        for _ in 0..extra {
+            dummy.write(&dummy_block[..hash_block_len]);
+        }
+        std::hint::black_box(dummy);
+
+        let mac_ok = subtle::ConstantTimeEq::ct_eq(mac.array_as_slice(), &input_mac[..mac_len]).unwrap_u8() as usize;
+        good &= mac_ok.wrapping_neg();
+        if good == 0 {
+            return Err(RecordError::BadRecordMac);
+        }
+
+        Ok((block_len, content_len))
+    }
+}
+
+
+#[cfg(all(test, feature = "tls1_2"))]
+#[test]
+fn test_tls12_aead_record_protector() {
+    use crate::blockmode::Aes128Gcm;
+    use crate::aeadcipher::Chacha20Poly1305;
+    use super::CONTENT_TYPE_APPLICATION_DATA;
+
+    fn check<C: TlsAead>(key: &[u8], fixed_iv: &[u8], records: &[&str]) {
+        let mut sender = AeadRecordProtector::<C>::new(key, fixed_iv);
+        let mut receiver = AeadRecordProtector::<C>::new(key, fixed_iv);
+
+        for expected in records.iter() {
+            let mut record = Vec::new();
+            sender.seal(CONTENT_TYPE_APPLICATION_DATA, b"hello world", &mut record).unwrap();
+            assert_eq!(&record[..], &hex::decode(expected).unwrap()[..]);
+
+            let mut tampered = record.clone();
+            tampered[0] = 22;
+            let mut other = AeadRecordProtector::<C>::new(key, fixed_iv);
+            other.seq = receiver.seq;
+            assert_eq!(other.open(&mut tampered), Err(RecordError::BadRecordMac));
+
+            let (content_type, content) = receiver.open(&mut record).unwrap();
+            assert_eq!(content_type, CONTENT_TYPE_APPLICATION_DATA);
+            assert_eq!(content, b"hello world");
+        }
+    }
+
+    let key: Vec<u8> = (0..32).collect();
+    // AES-GCM Cipher Suites for TLS
+    // https://tools.ietf.org/html/rfc5288
+    check::<Aes128Gcm>(&key[..16], &hex::decode("a0a1a2a3").unwrap(), &[
+        "17030300230000000000000000e238a2f128af430021aa4e3c63a7f38c5c145142e2984f21acdde2",
+        "170303002300000000000000012e14709e03a4003ff5002281216142fd36e26d7eee17865068420c",
+    ]);
+    // ChaCha20-Poly1305 Cipher Suites for Transport Layer Security (TLS)
+    // https://tools.ietf.org/html/rfc7905
+    check::<Chacha20Poly1305>(&key, &hex::decode("404142434445464748494a4b").unwrap(), &[
+        "170303001b903110ed1d6a996e2aa3923ab25f6b0151b6262e725deeb1921d54",
+        "170303001bc356de18c90c5dc06252f4b54acd1b5c19af4d3bc8586740815cd7",
+    ]);
+}
+
+#[test]
+fn test_tls12_cbc_hmac_record_protector() {
+    use crate::hash::{Sha1, Sha256};
+    use crate::blockmode::{Aes128Cbc, Aes256Cbc};
+    use super::{CONTENT_TYPE_HANDSHAKE, CONTENT_TYPE_APPLICATION_DATA};
+
+    let iv = hex::decode("808182838485868788898a8b8c8d8e8f").unwrap();
+    let key: Vec<u8> = (0..32).collect();
+    let mac_key: Vec<u8> = (0x20..0x40).collect();
+
+    // MAC-then-Encrypt
+    let mut sender = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_2_VERSION, &key[..16], &mac_key[..20], false);
+    let mut receiver = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_2_VERSION, &key[..16], &mac_key[..20], false);
+    let mut record = Vec::new();
+    sender.seal_with_iv(CONTENT_TYPE_APPLICATION_DATA, b"hello world", &iv, &mut record).unwrap();
+    assert_eq!(&record[..], &hex::decode("1703030030808182838485868788898a8b8c8d8e8ffb12cde5f8bddcd22f43a000039fa04016c6980d47\
+43090d72fac48d022243cf").unwrap()[..]);
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &b"hello world"[..]));
+
+    // TLS 1.1
+    let mut sender = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_1_VERSION, &key[..16], &mac_key[..20], false);
+    let mut receiver = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_1_VERSION, &key[..16], &mac_key[..20], false);
+    sender.seq = 1;
+    receiver.seq = 1;
+    let mut record = Vec::new();
+    sender.seal_with_iv(CONTENT_TYPE_HANDSHAKE, b"hello world", &iv, &mut record).unwrap();
+    assert_eq!(&record[..], &hex::decode("1603020030808182838485868788898a8b8c8d8e8f437d6eb67cf05114b3f05ad504b9dc50f9db3442\
+66f653deabe371e4d42df9c4").unwrap()[..]);
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_HANDSHAKE, &b"hello world"[..]));
+
+    // Encrypt-then-MAC
+    let mut sender = CbcHmacRecordProtector::<Aes256Cbc, Sha256>::new(TLS1_2_VERSION, &key, &mac_key, true);
+    let mut receiver = CbcHmacRecordProtector::<Aes256Cbc, Sha256>::new(TLS1_2_VERSION, &key, &mac_key, true);
+    let mut record = Vec::new();
+    sender.seal_with_iv(CONTENT_TYPE_APPLICATION_DATA, b"hello world", &iv, &mut record).unwrap();
+    assert_eq!(&record[..], &hex::decode("1703030040808182838485868788898a8b8c8d8e8f6c0ea8116dd1617157438b8a8d66ed66a449c7f7\
+3079042b63b080a5ee469f86ba4c9c05ec0b03b10b52c98ba675c2e0").unwrap()[..]);
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &b"hello world"[..]));
+
+    let mut record = Vec::new();
+    sender.seal_with_iv(CONTENT_TYPE_APPLICATION_DATA, &[0u8; 16], &iv, &mut record).unwrap();
+    assert_eq!(&record[..], &hex::decode("1703030050808182838485868788898a8b8c8d8e8fb0b0f57bcb97b783a1538ff78e9a68c24d7422a6\
+45566cf26531b5f42be2273f92363f903277194be95001d1131940713957101898752572e54adf18a76b0c30").unwrap()[..]);
+    assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &[0u8; 16][..]));
+
+    // 随机 IV
+    for content_len in 0..100 {
+        let content = vec![0x5a; content_len];
+        let mut record = Vec::new();
+        sender.seal(CONTENT_TYPE_APPLICATION_DATA, &content, &mut record).unwrap();
+        assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_APPLICATION_DATA, &content[..]));
+    }
+}
+
+#[test]
+fn test_tls12_cbc_hmac_record_protector_errors() {
+    use crate::hash::{Sha1, Sha384};
+    use crate::blockmode::{Aes128Cbc, Aes256Cbc};
+    use super::CONTENT_TYPE_APPLICATION_DATA;
+
+    let key: Vec<u8> = (0..32).collect();
+    let mac_key: Vec<u8> = (0x20..0x50).collect();
+    let iv = [0x11u8; 16];
+
+    for &encrypt_then_mac in [false, true].iter() {
+        let mut sender = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_2_VERSION, &key[..16], &mac_key[..20], encrypt_then_mac);
+        let mut record = Vec::new();
+        sender.seal_with_iv(CONTENT_TYPE_APPLICATION_DATA, &[0x42u8; 40], &iv, &mut record).unwrap();
+
+        // 篡改每一个字节（包括 IV、密文、填充以及 MAC）
+        for i in RECORD_HEADER_LEN..record.len() {
+            let mut receiver = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_2_VERSION, &key[..16], &mac_key[..20], encrypt_then_mac);
+            let mut tampered = record.clone();
+            tampered[i] ^= 0x01;
+            assert_eq!(receiver.open(&mut tampered), Err(RecordError::BadRecordMac));
+            assert_eq!(receiver.sequence_number(), 0);
+        }
+
+        // 长度不是分组长度的整数倍
+        let mut receiver = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_2_VERSION, &key[..16], &mac_key[..20], encrypt_then_mac);
+        let mut truncated = record[..record.len() - 1].to_vec();
+        truncated[4] -= 1;
+        assert_eq!(receiver.open(&mut truncated), Err(RecordError::BadRecordMac));
+
+        // 版本不一致
+        let mut receiver = CbcHmacRecordProtector::<Aes128Cbc, Sha1>::new(TLS1_1_VERSION, &key[..16], &mac_key[..20], encrypt_then_mac);
+        assert_eq!(receiver.open(&mut record.clone()), Err(RecordError::DecodeError));
+    }
+
+    // MAC-then-Encrypt：填充内容错误、填充长度过长
+    let mut receiver = CbcHmacRecordProtector::<Aes256Cbc, Sha384>::new(TLS1_2_VERSION, &key, &mac_key, false);
+    let mut sender = CbcHmacRecordProtector::<Aes256Cbc, Sha384>::new(TLS1_2_VERSION, &key, &mac_key, false);
+    let mut record = Vec::new();
+    sender.seal_with_iv(CONTENT_TYPE_APPLICATION_DATA, &[0x42u8; 15], &iv, &mut record).unwrap();
+    assert_eq!(record.len(), RECORD_HEADER_LEN + 16 + 64);
+
+    for &pad in [0x00u8, 0x01, 0x02, 0x10, 0xff].iter() {
+        // NOTE: 直接构造明文：content (15) + MAC (48) + padding_length (1) = 64
+        let mut plaintext = [0x42u8; 64];
+        let header = pseudo_header(0, CONTENT_TYPE_APPLICATION_DATA, TLS1_2_VERSION, 15);
+        let mac = receiver.mac(&header, &plaintext[..15]);
+        plaintext[15..63].copy_from_slice(mac.array_as_slice());
+        plaintext[63] = pad;
+        Aes256Cbc::new(&key, &iv).encrypt(&mut plaintext);
+
+        let mut forged = record.clone();
+        forged[RECORD_HEADER_LEN + 16..].copy_from_slice(&plaintext);
+        let ret = receiver.open(&mut forged);
+        if pad == 0 {
+            assert_eq!(ret, Ok((CONTENT_TYPE_APPLICATION_DATA, &[0x42u8; 15][..])));
+            receiver.seq = 0;
+        } else {
+            assert_eq!(ret, Err(RecordError::BadRecordMac));
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// 5.2.  Record Payload Protection
+// https://tools.ietf.org/html/rfc8446#section-5.2
+// 
+// 5.3.  Per-Record Nonce
+// https://tools.ietf.org/html/rfc8446#section-5.3
+use super::{TlsAead, RecordError, xor_nonce, expand_label_fixed, TLS13_LABEL_PREFIX};
+use super::{CONTENT_TYPE_APPLICATION_DATA, MAX_FRAGMENT_LEN, RECORD_HEADER_LEN};
+use crate::hkdf::Hkdf;
+
+use std::marker::PhantomData;
+
+
+/// TLS 1.3 记录层保护（单个方向）
+///
+/// ```text
+/// struct {
+///     opaque content[TLSPlaintext.length];
+///     ContentType type;
+///     uint8 zeros[length_of_padding];
+/// } TLSInnerPlaintext;
+///
+/// struct {
+///     ContentType opaque_type = application_data; /* 23 */
+///     ProtocolVersion legacy_record_version = 0x0303; /* TLS v1.2 */
+///     uint16 length;
+///     opaque encrypted_record[TLSCiphertext.length];
+/// } TLSCiphertext;
+/// ```
+pub struct RecordProtector<C: TlsAead> {
+    key: Vec<u8>,
+    iv: [u8; 12],
+    seq: u64,
+    key_update_threshold: u64,
+    _cipher: PhantomData<C>,
+}
+
+impl<C: TlsAead> RecordProtector<C> {
+    pub const IV_LEN: usize = 12;
+    /// TLSCiphertext.length 的最大值：2^14 + 256
+    pub const MAX_CIPHERTEXT_LEN: usize = MAX_FRAGMENT_LEN + 256;
+
+
+    pub fn new(key: &[u8], iv: &[u8]) -> Self {
+        assert_eq!(C::NONCE_LEN, Self::IV_LEN);
+        assert_eq!(key.len(), C::KEY_LEN);
+        assert_eq!(iv.len(), Self::IV_LEN);
+
+        let mut static_iv = [0u8; 12];
+        static_iv.copy_from_slice(iv);
+
+        // NOTE: 默认在达到使用上限的一半时提示更新密钥，留出足够的时间完成 KeyUpdate。
+        let key_update_threshold = C::RECORD_LIMIT / 2;
+
+        Self { key: key.to_vec(), iv: static_iv, seq: 0, key_update_threshold, _cipher: PhantomData }
+    }
+
+    /// 从 [sender]_*_traffic_secret 派生 write_key 和 write_iv。
+    pub fn from_traffic_secret<H: Hkdf>(traffic_secret: &[u8]) -> Self {
+        let mut key = vec![0u8; C::KEY_LEN];
+        let mut iv = [0u8; 12];
+        expand_label_fixed::<H>(TLS13_LABEL_PREFIX, traffic_secret, b"key", &[], &mut key);
+        expand_label_fixed::<H>(TLS13_LABEL_PREFIX, traffic_secret, b"iv", &[], &mut iv);
+
+        Self::new(&key, &iv)
+    }
+
+    pub fn sequence_number(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn set_key_update_threshold(&mut self, threshold: u64) {
+        self.key_update_threshold = std::cmp::min(threshold, C::RECORD_LIMIT);
+    }
+
+    /// 已经处理的记录数达到阈值，应当发送 KeyUpdate。
+    pub fn needs_key_update(&self) -> bool {
+        self.seq >= self.key_update_threshold
+    }
+
+    // 1.  The 64-bit record sequence number is encoded in network byte
+    //     order and padded to the left with zeros to iv_length.
+    // 
+    // 2.  The padded sequence number is XORed with either the static
+    //     client_write_iv or server_write_iv (depending on the role).
+    fn nonce(&self) -> [u8; 12] {
+        xor_nonce(&self.iv, self.seq)
+    }
+
+    /// 加密一条记录，并把完整的 TLSCiphertext（包括头部）追加到 `record` 后面。
+    ///
+    /// `padding_len` 为 TLSInnerPlaintext 中填充的 0 的个数。
+    pub fn seal(&mut self, content_type: u8, content: &[u8], padding_len: usize, record: &mut Vec<u8>) -> Result<(), RecordError> {
+        // NOTE: 0 是 TLSInnerPlaintext 中的填充，不能作为 ContentType。
+        if content_type == 0 {
+            return Err(RecordError::UnexpectedMessage);
+        }
+
+        if self.seq >= C::RECORD_LIMIT {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+
+        // NOTE: TLSInnerPlaintext 的长度不能超过 2^14 + 1。
+        let inner_len = content.len().saturating_add(padding_len).saturating_add(1);
+        if inner_len > MAX_FRAGMENT_LEN + 1 {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        let len = inner_len + C::TAG_LEN;
+        let start = record.len();
+        record.reserve(RECORD_HEADER_LEN + len);
+        record.push(CONTENT_TYPE_APPLICATION_DATA);
+        record.extend_from_slice(&[0x03, 0x03]);
+        record.extend_from_slice(&(len as u16).to_be_bytes());
+        record.extend_from_slice(content);
+        record.push(content_type);
+        // NOTE: 填充的 0 以及 Tag 的位置。
+        record.resize(start + RECORD_HEADER_LEN + len, 0);
+
+        // additional_data = TLSCiphertext.opaque_type ||
+        //                   TLSCiphertext.legacy_record_version ||
+        //                   TLSCiphertext.length
+        let (header, payload) = record[start..].split_at_mut(RECORD_HEADER_LEN);
+        let mut cipher = C::new(&self.key, &self.nonce());
+        cipher.aead_encrypt(header, payload);
+
+        self.seq += 1;
+
+        Ok(())
+    }
+
+    /// 原地解密一条完整的 TLSCiphertext（包括头部），返回真实的 ContentType 以及去掉填充后的内容。
+    pub fn open<'a>(&mut self, record: &'a mut [u8]) -> Result<(u8, &'a [u8]), RecordError> {
+        if self.seq == u64::MAX {
+            return Err(RecordError::KeyUpdateRequired);
+        }
+
+        if record.len() < RECORD_HEADER_LEN {
+            return Err(RecordError::DecodeError);
+        }
+        if record[0] != CONTENT_TYPE_APPLICATION_DATA {
+            return Err(RecordError::UnexpectedMessage);
+        }
+
+        let len = u16::from_be_bytes([record[3], record[4]]) as usize;
+        if len != record.len() - RECORD_HEADER_LEN {
+            return Err(RecordError::DecodeError);
+        }
+        if len > Self::MAX_CIPHERTEXT_LEN {
+            return Err(RecordError::RecordOverflow);
+        }
+        if len < C::TAG_LEN + 1 {
+            return Err(RecordError::DecodeError);
+        }
+
+        let (header, payload) = record.split_at_mut(RECORD_HEADER_LEN);
+        let mut cipher = C::new(&self.key, &self.nonce());
+        if !cipher.aead_decrypt(header, payload) {
+            return Err(RecordError::BadRecordMac);
+        }
+
+        self.seq += 1;
+
+        let record: &'a [u8] = record;
+        let plaintext = &record[RECORD_HEADER_LEN..RECORD_HEADER_LEN + len - C::TAG_LEN];
+        if plaintext.len() > MAX_FRAGMENT_LEN + 1 {
+            return Err(RecordError::RecordOverflow);
+        }
+
+        // NOTE: 从后往前扫描，第一个非零的字节即为 ContentType。
+        match plaintext.iter().rposition(|&b| b != 0) {
+            Some(pos) => Ok((plaintext[pos], &plaintext[..pos])),
+            None => Err(RecordError::UnexpectedMessage),
+        }
+    }
+}
+
+
+#[test]
+fn test_tls13_record_protector_rfc8448() {
+    use crate::hash::Sha256;
+    use crate::blockmode::Aes128Gcm;
+    use super::CONTENT_TYPE_APPLICATION_DATA;
+
+    // 3.  Simple 1-RTT Handshake
+    // https://tools.ietf.org/html/rfc8448#section-3
+    // 
+    // {client}  send application_data record
+    let cap = hex::decode("9e40646ce79a7f9dc05af8889bce6552875afa0b06df0087f792ebb7c17504a5").unwrap();
+    let payload: Vec<u8> = (0..50).collect();
+    let expected = hex::decode("1703030043a23f7054b62c94d0affafe8228ba55cbefacea42f914aa66bcab3f\
+2b9819a8a5b46b395bd54a9a20441e2b62974e1f5a6292a2977014bd1e3deae6\
+3aeebb21694915e4").unwrap();
+
+    let mut client = RecordProtector::<Aes128Gcm>::from_traffic_secret::<Sha256>(&cap);
+    let mut record = Vec::new();
+    client.seal(CONTENT_TYPE_APPLICATION_DATA, &payload, 0, &mut record).unwrap();
+    assert_eq!(&record[..], &expected[..]);
+    assert_eq!(client.sequence_number(), 1);
+
+    let mut server = RecordProtector::<Aes128Gcm>::from_traffic_secret::<Sha256>(&cap);
+    let (content_type, content) = server.open(&mut record).unwrap();
+    assert_eq!(content_type, CONTENT_TYPE_APPLICATION_DATA);
+    assert_eq!(content, &payload[..]);
+    assert_eq!(server.sequence_number(), 1);
+}
+
+#[test]
+fn test_tls13_record_protector() {
+    use crate::blockmode::{Aes128Ccm, Aes128Ccm8, Sm4Gcm, Sm4Ccm};
+    use crate::aeadcipher::Chacha20Poly1305;
+    use super::CONTENT_TYPE_HANDSHAKE;
+
+    fn check<C: TlsAead>(key: &[u8], records: &[&str]) {
+        let iv = hex::decode("404142434445464748494a4b").unwrap();
+        let mut sender = RecordProtector::<C>::new(key, &iv);
+        let mut receiver = RecordProtector::<C>::new(key, &iv);
+
+        for expected in records.iter() {
+            let mut record = Vec::new();
+            sender.seal(CONTENT_TYPE_HANDSHAKE, b"hello", 3, &mut record).unwrap();
+            assert_eq!(&record[..], &hex::decode(expected).unwrap()[..]);
+
+            let (content_type, content) = receiver.open(&mut record).unwrap();
+            assert_eq!(content_type, CONTENT_TYPE_HANDSHAKE);
+            assert_eq!(content, b"hello");
+        }
+    }
+
+    let key: Vec<u8> = (0..32).collect();
+    check::<Chacha20Poly1305>(&key, &[
+        "1703030019903110ed1d5cee0158f3455cddab3ab89ee02046a04524d0b2",
+        "1703030019c356de18c93a2aaf1096792784c37810200de691791cb6f7f3",
+    ]);
+    check::<Aes128Ccm>(&key[..16], &[
+        "170303001918eac142f0af28349f4787adfe99cf4794728ae3177551d38c",
+        "170303001941d8d3687470bcdbb34f4b945e0601a3b0216f0f75273905f8",
+    ]);
+    check::<Aes128Ccm8>(&key[..16], &[
+        "170303001118eac142f0af28349f04c00cb0bc94ce0a",
+        "170303001141d8d3687470bcdbb3302335412b4978ac",
+    ]);
+
+    // NOTE: SM4-GCM、SM4-CCM（RFC 8998）只检查往返加解密以及篡改后的记录被拒绝。
+    fn check_roundtrip<C: TlsAead>(key: &[u8]) {
+        let iv = [0u8; 12];
+        let mut sender = RecordProtector::<C>::new(key, &iv);
+        let mut receiver = RecordProtector::<C>::new(key, &iv);
+        for _ in 0..3 {
+            let mut record = Vec::new();
+            sender.seal(CONTENT_TYPE_HANDSHAKE, b"hello", 0, &mut record).unwrap();
+            assert_eq!(record.len(), RECORD_HEADER_LEN + 5 + 1 + C::TAG_LEN);
+
+            let mut tampered = record.clone();
+            let last = tampered.len() - 1;
+            tampered[last] ^= 1;
+            assert_eq!(receiver.open(&mut tampered), Err(RecordError::BadRecordMac));
+            let mut tampered = record.clone();
+            tampered[RECORD_HEADER_LEN] ^= 1;
+            assert_eq!(receiver.open(&mut tampered), Err(RecordError::BadRecordMac));
+
+            assert_eq!(receiver.open(&mut record).unwrap(), (CONTENT_TYPE_HANDSHAKE, &b"hello"[..]));
+        }
+        assert_eq!(receiver.sequence_number(), 3);
+    }
+
+    check_roundtrip::<Sm4Gcm>(&key[..16]);
+    check_roundtrip::<Sm4Ccm>(&key[..16]);
+}
+
+#[test]
+fn test_tls13_record_protector_errors() {
+    use crate::blockmode::Aes128Gcm;
+    use super::CONTENT_TYPE_HANDSHAKE;
+
+    let key = [1u8; 16];
+    let iv = [2u8; 12];
+    let mut sender = RecordProtector::<Aes128Gcm>::new(&key, &iv);
+
+    // 超出 2^14 + 1
+    let mut record = Vec::new();
+    assert_eq!(sender.seal(CONTENT_TYPE_HANDSHAKE, &[0u8; MAX_FRAGMENT_LEN], 1, &mut record), Err(RecordError::RecordOverflow));
+    assert!(sender.seal(CONTENT_TYPE_HANDSHAKE, &[0u8; MAX_FRAGMENT_LEN], 0, &mut record).is_ok());
+    assert_eq!(record.len(), RECORD_HEADER_LEN + MAX_FRAGMENT_LEN + 1 + 16);
+
+    // ContentType 为 0
+    let mut empty = Vec::new();
+    assert_eq!(sender.seal(0, b"hello", 0, &mut empty), Err(RecordError::UnexpectedMessage));
+    assert!(empty.is_empty());
+    assert_eq!(sender.sequence_number(), 1);
+
+    // 篡改
+    let mut receiver = RecordProtector::<Aes128Gcm>::new(&key, &iv);
+    let mut tampered = record.clone();
+    tampered[10] ^= 1;
+    assert_eq!(receiver.open(&mut tampered), Err(RecordError::BadRecordMac));
+    assert_eq!(receiver.sequence_number(), 0);
+    let mut truncated = record.clone();
+    truncated.pop();
+    assert_eq!(receiver.open(&mut truncated), Err(RecordError::DecodeError));
+    assert!(receiver.open(&mut record).is_ok());
+
+    // 序列号不一致（重放）
+    let mut record = Vec::new();
+    sender.seal(CONTENT_TYPE_HANDSHAKE, b"", 0, &mut record).unwrap();
+    let mut replay = record.clone();
+    assert!(receiver.open(&mut record).is_ok());
+    assert_eq!(receiver.open(&mut replay), Err(RecordError::BadRecordMac));
+
+    // 全部为 0 的 TLSInnerPlaintext
+    let mut record = [0u8; RECORD_HEADER_LEN + 4 + 16];
+    record[..RECORD_HEADER_LEN].copy_from_slice(&[0x17, 0x03, 0x03, 0x00, 4 + 16]);
+    let (header, payload) = record.split_at_mut(RECORD_HEADER_LEN);
+    Aes128Gcm::new(&key, &iv).aead_encrypt(header, payload);
+    let mut receiver = RecordProtector::<Aes128Gcm>::new(&key, &iv);
+    assert_eq!(receiver.open(&mut record), Err(RecordError::UnexpectedMessage));
+
+    // 密钥使用上限
+    let mut sender = RecordProtector::<Aes128Gcm>::new(&key, &iv);
+    sender.set_key_update_threshold(10);
+    sender.seq = 9;
+    assert!(!sender.needs_key_update());
+    sender.seal(CONTENT_TYPE_HANDSHAKE, b"", 0, &mut Vec::new()).unwrap();
+    assert!(sender.needs_key_update());
+    sender.seq = <Aes128Gcm as TlsAead>::RECORD_LIMIT;
+    assert_eq!(sender.seal(CONTENT_TYPE_HANDSHAKE, b"", 0, &mut Vec::new()), Err(RecordError::KeyUpdateRequired));
+}
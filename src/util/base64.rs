@@ -0,0 +1,74 @@
+// 不带填充（`=`）的 Base64 编码，供 PHC 字符串、bcrypt 等使用。
+// https://github.com/P-H-C/phc-string-format/blob/master/phc-sf-spec.md#b64
+
+pub const BASE64_STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// OpenBSD bcrypt
+pub const BASE64_BCRYPT: &[u8; 64]   = b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+
+pub fn base64_encode(alphabet: &[u8; 64], data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 4).div_ceil(3));
+
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = if chunk.len() > 1 { chunk[1] as u32 } else { 0 };
+        let b2 = if chunk.len() > 2 { chunk[2] as u32 } else { 0 };
+        let n = (b0 << 16) | (b1 << 8) | b2;
+
+        for i in 0..chunk.len() + 1 {
+            let idx = (n >> (18 - 6 * i)) & 0x3f;
+            out.push(alphabet[idx as usize] as char);
+        }
+    }
+
+    out
+}
+
+// NOTE: 只接受规范的编码（最后一个字符中未使用的比特必须为 0）。
+pub fn base64_decode(alphabet: &[u8; 64], s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    if s.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+
+    for chunk in s.chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let idx = alphabet.iter().position(|a| a == c)? as u32;
+            n |= idx << (18 - 6 * i);
+        }
+
+        let len = chunk.len() - 1;
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        if len < 3 && bytes[len] != 0 {
+            return None;
+        }
+        out.extend_from_slice(&bytes[..len]);
+    }
+
+    Some(out)
+}
+
+
+#[test]
+fn test_base64() {
+    let suites: &[(&[u8], &str)] = &[
+        (b"", ""),
+        (b"f", "Zg"),
+        (b"fo", "Zm8"),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg"),
+        (b"fooba", "Zm9vYmE"),
+        (b"foobar", "Zm9vYmFy"),
+    ];
+    for (data, s) in suites.iter() {
+        assert_eq!(&base64_encode(BASE64_STANDARD, data), s);
+        assert_eq!(base64_decode(BASE64_STANDARD, s).as_deref(), Some(*data));
+    }
+
+    assert_eq!(base64_decode(BASE64_STANDARD, "Zh"), None);
+    assert_eq!(base64_decode(BASE64_STANDARD, "Z"), None);
+    assert_eq!(base64_decode(BASE64_STANDARD, "Zm9v="), None);
+}
@@ -1,6 +1,8 @@
 mod xor;
 mod and;
+mod base64;
 
 
 pub use self::xor::*;
 pub use self::and::*;
+pub use self::base64::*;